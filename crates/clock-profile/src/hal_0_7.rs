@@ -0,0 +1,53 @@
+//! Applying a `Profile` with stm32f3xx-hal 0.7, the HAL of stm32f3-discovery 0.7.
+
+use stm32f3xx_hal_0_7::{
+    flash::ACR,
+    pac::I2C1,
+    rcc::{Clocks, CFGR},
+    time::rate::Hertz,
+};
+
+use crate::Profile;
+
+/// Sets the clock tree up as `profile` says.
+///
+/// Panics if the HAL can't find dividers that give exactly the profile's frequencies.
+pub fn freeze(cfgr: CFGR, acr: &mut ACR, profile: Profile) -> Clocks {
+    let f = profile.frequencies();
+
+    let cfgr = match f.hse {
+        // The ST-LINK drives OSC_IN, there's no crystal to oscillate
+        Some(hse) => cfgr.use_hse(Hertz(hse)).bypass_hse(),
+        None => cfgr,
+    };
+    let clocks = cfgr
+        .sysclk(Hertz(f.sysclk))
+        .hclk(Hertz(f.hclk))
+        .pclk1(Hertz(f.pclk1))
+        .pclk2(Hertz(f.pclk2))
+        .freeze(acr);
+
+    // The timings derived from the profile are only right if the HAL found exact dividers
+    assert_eq!(clocks.sysclk().0, f.sysclk);
+    assert_eq!(clocks.hclk().0, f.hclk);
+    assert_eq!(clocks.pclk1().0, f.pclk1);
+    assert_eq!(clocks.pclk2().0, f.pclk2);
+
+    clocks
+}
+
+/// Programs the I2C1 bus timing for the clock I2C1 runs at with `profile`. The HAL works it out
+/// from PCLK1, but I2C1 is clocked by the internal oscillator (`I2C1SW` reset value).
+///
+/// Call it right after `I2c::new`, before anything uses the bus. Panics if `bus_hz` can't be
+/// reached.
+pub fn set_i2c1_timing(profile: Profile, bus_hz: u32) {
+    let timing = profile.frequencies().i2c1_timing(bus_hz).unwrap();
+
+    // `I2c::new` has just set I2C1 up, and nothing is using it yet
+    let i2c1 = unsafe { &*I2C1::ptr() };
+    // TIMINGR can only be written while the peripheral is disabled
+    i2c1.cr1.modify(|_, w| w.pe().clear_bit());
+    i2c1.timingr.write(|w| unsafe { w.bits(timing.bits()) });
+    i2c1.cr1.modify(|_, w| w.pe().set_bit());
+}
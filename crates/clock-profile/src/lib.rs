@@ -0,0 +1,397 @@
+//! Clock tree configurations for the F3 Discovery, and the peripheral timings that follow from
+//! them.
+//!
+//! The auxiliary crates set the clocks up from a `Profile`, and program the USART baud rate,
+//! the I2C bus timing, the timer prescalers and the `MonoTimer` from the same frequencies, so
+//! that switching profiles doesn't break any of them.
+//!
+//! ```
+//! use clock_profile::Profile;
+//!
+//! let f = Profile::Hsi8MHz.frequencies();
+//! assert_eq!(f.usart1_brr(115_200), Ok(69));
+//!
+//! let f = Profile::Hse72MHz.frequencies();
+//! assert_eq!(f.sysclk, 72_000_000);
+//! assert_eq!(f.usart1_brr(115_200), Ok(625));
+//! // APB1 is divided down to 36 MHz, so its timers run at twice that
+//! assert_eq!(f.apb1_timer_clock(), 72_000_000);
+//! // A 10 kHz tick for TIM6
+//! assert_eq!(f.apb1_timer_prescaler(10_000), Ok(7_199));
+//! assert_eq!(Profile::Hsi8MHz.frequencies().apb1_timer_prescaler(10_000), Ok(799));
+//!
+//! // Whatever the profile, the serial port and the sensors keep working
+//! for profile in [Profile::Hsi8MHz, Profile::Hse72MHz, Profile::LowPower].iter() {
+//!     let f = profile.frequencies();
+//!     assert!(f.usart1_brr(115_200).is_ok());
+//!     assert!(f.i2c1_timing(400_000).is_ok());
+//! }
+//! ```
+//!
+//! The `hal-0-6` and `hal-0-7` features add a module with `freeze`, which sets the clock tree up,
+//! and `set_i2c1_timing` for the stm32f3xx-hal version the auxiliary crate is built on.
+
+#![no_std]
+
+use core::fmt;
+
+#[cfg(feature = "hal-0-6")]
+pub mod hal_0_6;
+#[cfg(feature = "hal-0-7")]
+pub mod hal_0_7;
+
+/// Frequency of the internal RC oscillator.
+pub const HSI: u32 = 8_000_000;
+
+/// Frequency of the clock the ST-LINK feeds to the OSC_IN pin. The board has no crystal, so
+/// the HSE oscillator has to be bypassed.
+pub const HSE: u32 = 8_000_000;
+
+/// Largest difference between the baud rate asked for and the one the USART divider gives, in
+/// percent.
+pub const MAX_BAUD_RATE_ERROR_PERCENT: u32 = 2;
+
+/// How the clock tree is set up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Profile {
+    /// Everything at 8 MHz from the internal oscillator, what the microcontroller starts with.
+    #[default]
+    Hsi8MHz,
+    /// The ST-LINK's 8 MHz multiplied by 9 with the PLL, as fast as the microcontroller goes.
+    /// APB1 can't go over 36 MHz and is divided by 2.
+    Hse72MHz,
+    /// The internal oscillator with the core and both buses divided down to 4 MHz, to save
+    /// power.
+    LowPower,
+}
+
+impl Profile {
+    pub const fn frequencies(self) -> Frequencies {
+        match self {
+            Profile::Hsi8MHz => Frequencies {
+                hse: None,
+                sysclk: HSI,
+                hclk: HSI,
+                pclk1: HSI,
+                pclk2: HSI,
+                i2c1: HSI,
+            },
+            Profile::Hse72MHz => Frequencies {
+                hse: Some(HSE),
+                sysclk: 72_000_000,
+                hclk: 72_000_000,
+                pclk1: 36_000_000,
+                pclk2: 72_000_000,
+                i2c1: HSI,
+            },
+            Profile::LowPower => Frequencies {
+                hse: None,
+                sysclk: HSI,
+                hclk: 4_000_000,
+                pclk1: 4_000_000,
+                pclk2: 4_000_000,
+                i2c1: HSI,
+            },
+        }
+    }
+}
+
+/// The clock frequencies of a profile, in Hz.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frequencies {
+    /// The external clock, `None` when running from the internal oscillator.
+    pub hse: Option<u32>,
+    pub sysclk: u32,
+    /// The core and AHB clock, which the `MonoTimer` counts.
+    pub hclk: u32,
+    /// APB1: TIM2 to TIM7, I2C, USART2 and USART3.
+    pub pclk1: u32,
+    /// APB2: USART1, TIM1 and TIM8.
+    pub pclk2: u32,
+    /// The I2C1 kernel clock, which stays on the internal oscillator (`I2C1SW` reset value).
+    pub i2c1: u32,
+}
+
+impl Frequencies {
+    /// Clock of the APB1 timers.
+    pub fn apb1_timer_clock(&self) -> u32 {
+        apb_timer_clock(self.hclk, self.pclk1)
+    }
+
+    /// Clock of the APB2 timers.
+    pub fn apb2_timer_clock(&self) -> u32 {
+        apb_timer_clock(self.hclk, self.pclk2)
+    }
+
+    /// `BRR` value of USART1, which runs off APB2, for `baud_rate`.
+    pub fn usart1_brr(&self, baud_rate: u32) -> Result<u16, Error> {
+        usart_brr(self.pclk2, baud_rate)
+    }
+
+    /// `TIMINGR` value of I2C1 for a bus clock of `bus_hz`.
+    pub fn i2c1_timing(&self, bus_hz: u32) -> Result<I2cTiming, Error> {
+        i2c_timing(self.i2c1, bus_hz)
+    }
+
+    /// `PSC` value that makes an APB1 timer count at `tick_hz`.
+    pub fn apb1_timer_prescaler(&self, tick_hz: u32) -> Result<u16, Error> {
+        timer_prescaler(self.apb1_timer_clock(), tick_hz)
+    }
+
+    /// Frequency of the `MonoTimer`, which counts core clock cycles.
+    pub fn mono_timer(&self) -> u32 {
+        self.hclk
+    }
+}
+
+/// A timing the peripheral can't produce from the clock it runs at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The closest baud rate is more than `MAX_BAUD_RATE_ERROR_PERCENT` off, or the divider
+    /// doesn't fit.
+    BaudRate,
+    /// The I2C clock is too slow for the bus speed, or the bus speed is over 1 MHz.
+    I2cSpeed,
+    /// The timer clock isn't a multiple of the tick rate, or the prescaler doesn't fit.
+    TickRate,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Error::BaudRate => "baud rate out of reach of the USART clock",
+            Error::I2cSpeed => "I2C bus speed out of reach of the I2C clock",
+            Error::TickRate => "tick rate out of reach of the timer clock",
+        })
+    }
+}
+
+/// Clock of the timers on an APB bus: the bus clock, or twice that when the bus is divided
+/// down from the core clock.
+pub fn apb_timer_clock(hclk: u32, pclk: u32) -> u32 {
+    if pclk == hclk {
+        pclk
+    } else {
+        2 * pclk
+    }
+}
+
+/// `BRR` value for `baud_rate` with 16x oversampling, rounded to the closest divider.
+///
+/// ```
+/// use clock_profile::{usart_brr, Error};
+///
+/// assert_eq!(usart_brr(8_000_000, 9_600), Ok(833));
+/// // 8 MHz / 16 is the fastest 16x oversampling goes
+/// assert_eq!(usart_brr(8_000_000, 500_000), Ok(16));
+/// assert_eq!(usart_brr(8_000_000, 921_600), Err(Error::BaudRate));
+/// // 8 MHz / 12 = 667 kbps, 3.5% off
+/// assert_eq!(usart_brr(8_000_000, 691_200), Err(Error::BaudRate));
+/// ```
+pub fn usart_brr(usart_clock: u32, baud_rate: u32) -> Result<u16, Error> {
+    if baud_rate == 0 {
+        return Err(Error::BaudRate);
+    }
+    let brr = (u64::from(usart_clock) + u64::from(baud_rate) / 2) / u64::from(baud_rate);
+    if brr < 16 || brr > u64::from(u16::MAX) {
+        return Err(Error::BaudRate);
+    }
+
+    let actual = u64::from(usart_clock) / brr;
+    let error = if actual > u64::from(baud_rate) {
+        actual - u64::from(baud_rate)
+    } else {
+        u64::from(baud_rate) - actual
+    };
+    if error * 100 > u64::from(baud_rate) * u64::from(MAX_BAUD_RATE_ERROR_PERCENT) {
+        return Err(Error::BaudRate);
+    }
+    Ok(brr as u16)
+}
+
+/// `PSC` value that makes a timer clocked at `timer_clock` count at exactly `tick_hz`.
+///
+/// ```
+/// use clock_profile::{timer_prescaler, Error};
+///
+/// assert_eq!(timer_prescaler(8_000_000, 1_000_000), Ok(7));
+/// assert_eq!(timer_prescaler(8_000_000, 3_000), Err(Error::TickRate));
+/// // Would need a divider of 72_000
+/// assert_eq!(timer_prescaler(72_000_000, 1_000), Err(Error::TickRate));
+/// ```
+pub fn timer_prescaler(timer_clock: u32, tick_hz: u32) -> Result<u16, Error> {
+    if tick_hz == 0 || !timer_clock.is_multiple_of(tick_hz) {
+        return Err(Error::TickRate);
+    }
+    let divider = timer_clock / tick_hz;
+    if divider > 1 << 16 {
+        return Err(Error::TickRate);
+    }
+    Ok((divider - 1) as u16)
+}
+
+/// Longest a single shot of a basic timer can be, in timer clock cycles: the prescaler and the
+/// counter are both 16 bits wide.
+pub const MAX_SHOT_CYCLES: u64 = 1 << 32;
+
+/// Longest single shot of a timer clocked at `timer_clock`, in microseconds.
+///
+/// ```
+/// assert_eq!(clock_profile::max_shot_us(8_000_000), 536_870_912);
+/// assert_eq!(clock_profile::max_shot_us(72_000_000), 59_652_323);
+/// ```
+pub fn max_shot_us(timer_clock: u32) -> u32 {
+    let us = MAX_SHOT_CYCLES * 1_000_000 / u64::from(timer_clock);
+    if us > u64::from(u32::MAX) {
+        u32::MAX
+    } else {
+        us as u32
+    }
+}
+
+/// `PSC` and `ARR` values for a one-pulse shot of `us` microseconds of a timer clocked at
+/// `timer_clock`, never shorter than asked. Shots longer than `max_shot_us` are cut to it.
+///
+/// The counter ticks at `timer_clock / (PSC + 1)` and the update event comes `ARR + 1` ticks
+/// after it starts. The prescaler is kept as small as possible, for the finest resolution.
+///
+/// ```
+/// assert_eq!(clock_profile::shot_timing(8_000_000, 1), (0, 7));
+/// assert_eq!(clock_profile::shot_timing(72_000_000, 1_000), (1, 35_999));
+/// ```
+pub fn shot_timing(timer_clock: u32, us: u32) -> (u16, u16) {
+    let cycles = (u64::from(us) * u64::from(timer_clock)).div_ceil(1_000_000);
+    let cycles = cycles.clamp(2, MAX_SHOT_CYCLES);
+
+    let psc = (cycles - 1) / (1 << 16);
+    let ticks = cycles.div_ceil(psc + 1);
+    // `ARR` 0 would stop the counter
+    let arr = ticks.max(2) - 1;
+
+    (psc as u16, arr as u16)
+}
+
+/// The fields of the I2C `TIMINGR` register.
+///
+/// With `t = (PRESC + 1) / I2CCLK`, SCL is low for `(SCLL + 1) t` and high for `(SCLH + 1) t`,
+/// data changes `SDADEL t` after SCL falls and is held `(SCLDEL + 1) t` before it rises.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct I2cTiming {
+    pub presc: u8,
+    pub scldel: u8,
+    pub sdadel: u8,
+    pub sclh: u8,
+    pub scll: u8,
+}
+
+impl I2cTiming {
+    /// The value to write to `TIMINGR`.
+    pub fn bits(&self) -> u32 {
+        u32::from(self.presc) << 28
+            | u32::from(self.scldel) << 20
+            | u32::from(self.sdadel) << 16
+            | u32::from(self.sclh) << 8
+            | u32::from(self.scll)
+    }
+}
+
+/// Minimum SCL low and high times, data setup time plus rise time, and data hold time of an I2C
+/// bus mode, in ns.
+struct Mode {
+    low: u32,
+    high: u32,
+    setup: u32,
+    hold: u32,
+    /// Part of the period SCL is high, in halves or thirds.
+    high_share: u32,
+}
+
+const STANDARD: Mode = Mode {
+    low: 4_700,
+    high: 4_000,
+    setup: 1_250,
+    hold: 500,
+    high_share: 2,
+};
+const FAST: Mode = Mode {
+    low: 1_300,
+    high: 600,
+    setup: 400,
+    hold: 125,
+    high_share: 3,
+};
+const FAST_PLUS: Mode = Mode {
+    low: 500,
+    high: 260,
+    setup: 170,
+    hold: 0,
+    high_share: 3,
+};
+
+/// `TIMINGR` fields for a bus clock of `bus_hz` with an I2C clock of `i2c_clock`.
+///
+/// The smallest prescaler that fits is used, for the finest resolution. SCL is high half of the
+/// period in standard mode and a third of it in fast mode, which meets the minimum low and high
+/// times, and the bus ends up a little slower than asked because of the synchronization delays.
+///
+/// ```
+/// use clock_profile::{i2c_timing, Error, I2cTiming};
+///
+/// let timing = i2c_timing(8_000_000, 400_000).unwrap();
+/// assert_eq!(
+///     timing,
+///     I2cTiming { presc: 0, scldel: 3, sdadel: 1, sclh: 5, scll: 13 }
+/// );
+/// assert_eq!(timing.bits(), 0x0031_050d);
+///
+/// // A 72 MHz I2C clock has to be divided down for the 4-bit SCLDEL
+/// assert_eq!(i2c_timing(72_000_000, 100_000).unwrap().presc, 5);
+///
+/// assert_eq!(i2c_timing(8_000_000, 2_000_000), Err(Error::I2cSpeed));
+/// ```
+pub fn i2c_timing(i2c_clock: u32, bus_hz: u32) -> Result<I2cTiming, Error> {
+    let mode = match bus_hz {
+        0 => return Err(Error::I2cSpeed),
+        1..=100_000 => STANDARD,
+        100_001..=400_000 => FAST,
+        400_001..=1_000_000 => FAST_PLUS,
+        _ => return Err(Error::I2cSpeed),
+    };
+
+    for presc in 0..16 {
+        let tick_hz = i2c_clock / (presc + 1);
+        let period = tick_hz / bus_hz;
+        let high = period / mode.high_share;
+        let low = period - high;
+        let scldel = ticks(mode.setup, tick_hz);
+        let sdadel = ticks(mode.hold, tick_hz);
+
+        if low > 256 || scldel > 16 || sdadel > 15 {
+            continue;
+        }
+        if high == 0 || ns(low, tick_hz) < mode.low || ns(high, tick_hz) < mode.high || scldel == 0
+        {
+            // A coarser tick won't help
+            return Err(Error::I2cSpeed);
+        }
+
+        return Ok(I2cTiming {
+            presc: presc as u8,
+            scldel: (scldel - 1) as u8,
+            sdadel: sdadel as u8,
+            sclh: (high - 1) as u8,
+            scll: (low - 1) as u8,
+        });
+    }
+    Err(Error::I2cSpeed)
+}
+
+/// Ticks at `tick_hz` it takes to cover `ns`, rounded up.
+fn ticks(ns: u32, tick_hz: u32) -> u32 {
+    (u64::from(ns) * u64::from(tick_hz)).div_ceil(1_000_000_000) as u32
+}
+
+/// Duration of `ticks` ticks at `tick_hz`, in ns, rounded down.
+fn ns(ticks: u32, tick_hz: u32) -> u32 {
+    (u64::from(ticks) * 1_000_000_000 / u64::from(tick_hz)) as u32
+}
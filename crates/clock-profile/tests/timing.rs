@@ -0,0 +1,116 @@
+//! One-pulse timer shots at the timer clocks of the clock profiles.
+
+use clock_profile::{max_shot_us, shot_timing, MAX_SHOT_CYCLES};
+
+/// The timer clocks an APB1 timer runs at with the three profiles.
+const TIMER_CLOCKS: [u32; 3] = [8_000_000, 36_000_000, 72_000_000];
+
+/// How many timer clock cycles a shot programmed with `PSC` and `ARR` lasts.
+fn cycles((psc, arr): (u16, u16)) -> u64 {
+    (u64::from(psc) + 1) * (u64::from(arr) + 1)
+}
+
+/// How many timer clock cycles `us` microseconds are, rounded up.
+fn wanted(timer_clock: u32, us: u32) -> u64 {
+    (u64::from(us) * u64::from(timer_clock)).div_ceil(1_000_000)
+}
+
+#[test]
+fn one_microsecond() {
+    assert_eq!(shot_timing(8_000_000, 1), (0, 7));
+    assert_eq!(shot_timing(36_000_000, 1), (0, 35));
+    assert_eq!(shot_timing(72_000_000, 1), (0, 71));
+}
+
+#[test]
+fn one_millisecond() {
+    assert_eq!(shot_timing(8_000_000, 1_000), (0, 7_999));
+    assert_eq!(shot_timing(36_000_000, 1_000), (0, 35_999));
+    // 72_000 cycles don't fit the counter, the prescaler halves them
+    assert_eq!(shot_timing(72_000_000, 1_000), (1, 35_999));
+}
+
+#[test]
+fn shortest_shot() {
+    // The counter needs to count to at least 1
+    for &timer_clock in TIMER_CLOCKS.iter() {
+        assert_eq!(shot_timing(timer_clock, 0), (0, 1));
+    }
+    assert_eq!(shot_timing(1_000_000, 1), (0, 1));
+}
+
+#[test]
+fn max_shot() {
+    assert_eq!(max_shot_us(8_000_000), 536_870_912);
+    assert_eq!(max_shot_us(36_000_000), 119_304_647);
+    assert_eq!(max_shot_us(72_000_000), 59_652_323);
+    // Slower clocks would take more microseconds than a `u32` holds
+    assert_eq!(max_shot_us(1_000_000), u32::MAX);
+}
+
+#[test]
+fn max_shot_boundary() {
+    for &timer_clock in TIMER_CLOCKS.iter() {
+        let max = max_shot_us(timer_clock);
+        // The longest shot still fits, and is long enough
+        let timing = shot_timing(timer_clock, max);
+        assert!(cycles(timing) >= wanted(timer_clock, max));
+        assert!(cycles(timing) <= MAX_SHOT_CYCLES);
+        // A microsecond more doesn't, and is cut to the longest the timer can do
+        assert!(wanted(timer_clock, max + 1) > MAX_SHOT_CYCLES);
+        assert_eq!(shot_timing(timer_clock, max + 1), (u16::MAX, u16::MAX));
+        assert_eq!(shot_timing(timer_clock, u32::MAX), (u16::MAX, u16::MAX));
+    }
+}
+
+#[test]
+fn never_shorter_than_asked() {
+    for &timer_clock in TIMER_CLOCKS
+        .iter()
+        .chain([1_000_000, 48_000_000, 64_000_000].iter())
+    {
+        let max = max_shot_us(timer_clock);
+        // Every duration up to 10 ms, then ever bigger steps, which hit odd numbers too
+        let mut us: u32 = 0;
+        loop {
+            let timing = shot_timing(timer_clock, us);
+            let wanted = wanted(timer_clock, us).max(2);
+            let actual = cycles(timing);
+            assert!(
+                actual >= wanted,
+                "{} Hz, {} us: {:?}",
+                timer_clock,
+                us,
+                timing
+            );
+            // Off by less than one tick of the prescaled counter
+            assert!(
+                actual - wanted <= u64::from(timing.0),
+                "{} Hz, {} us: {:?}",
+                timer_clock,
+                us,
+                timing
+            );
+            let step = if us < 10_000 { 1 } else { us / 997 };
+            us = match us.checked_add(step) {
+                Some(next) if next <= max => next,
+                _ => break,
+            };
+        }
+    }
+}
+
+#[test]
+fn finest_resolution() {
+    // The prescaler is only as big as it has to be for the counter to hold the shot
+    for &timer_clock in TIMER_CLOCKS.iter() {
+        for &us in [1, 999, 1_000, 12_345, 1_000_000, 30_000_000].iter() {
+            let (psc, _) = shot_timing(timer_clock, us);
+            let wanted = wanted(timer_clock, us);
+            assert!(wanted <= (u64::from(psc) + 1) << 16);
+            if psc > 0 {
+                assert!(wanted > u64::from(psc) << 16);
+            }
+        }
+    }
+}
@@ -0,0 +1,420 @@
+//! Hard and soft iron calibration of a magnetometer, independent of any board.
+//!
+//! Translated from <https://github.com/lancaster-university/codal-microbit-v2/blob/006abf5566774fbcf674c0c7df27e8a9d20013de/source/MicroBitCompassCalibrator.cpp>
+//!
+//! A [`Collector`] plays the tilting game of the micro:bit: the accelerometer moves a cursor
+//! over a 5x5 grid and every cell the cursor reaches for the first time asks for one
+//! magnetometer reading. Once all 25 cells are lit, [`calibrate`] looks for the center of the
+//! sphere the readings lie on (the hard iron offset), the scale per axis that makes it round
+//! (the soft iron distortion) and how much the result can be trusted.
+//!
+//! Nothing in here touches hardware; the LED compass chapter shows the grid on the display and
+//! the same code runs on the host against the `lsm303agr-replay` sensor.
+//!
+//! ```
+//! use compass_calibration::{calibrate, Collector, Vector};
+//!
+//! let mut collector = Collector::new();
+//! // Tilting the board to the far left of the grid asks for a first sample
+//! assert!(collector.update(Vector::new(-700, 0, 700)));
+//! collector.add(Vector::new(-20_000, 3_000, -40_000));
+//! assert!(!collector.update(Vector::new(-700, 0, 700)));
+//! assert!(!collector.is_complete());
+//! ```
+
+#![no_std]
+
+use libm::{fabsf, sqrtf};
+
+/// Number of samples a calibration is computed from, one per LED.
+pub const PERIMETER_POINTS: usize = 25;
+/// Tilt, in milli-g, that moves the cursor one LED away from the center.
+const PIXEL1_THRESHOLD: i32 = 200;
+/// Tilt, in milli-g, that moves the cursor to the edge of the grid.
+const PIXEL2_THRESHOLD: i32 = 600;
+const CALIBRATION_INCREMENT: i32 = 200;
+
+/// Score below which a calibration should be repeated.
+pub const MIN_SCORE: u8 = 50;
+const OUTLIER_TOLERANCE: f32 = 0.25;
+
+/// A three axis reading, in whatever unit the sensor reports.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Vector {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl Vector {
+    pub const fn new(x: i32, y: i32, z: i32) -> Vector {
+        Vector { x, y, z }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    center: Vector,
+    scale: Vector,
+    radius: u32,
+    quality: Quality,
+}
+
+/// How well the collected samples describe a sphere once the calibration is applied.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Quality {
+    /// `measure_score` of the calibrated samples around the origin, relative to the squared
+    /// radius of the furthest sample. 0 is a perfect sphere.
+    pub spread: f32,
+    /// Extent of the calibrated samples along the x, y and z axes, relative to the diameter
+    /// of the sphere. 1 means the samples reach both poles of that axis.
+    pub coverage: [f32; 3],
+    /// Number of samples whose distance to the center deviates from the mean distance by
+    /// more than `OUTLIER_TOLERANCE`.
+    pub outliers: u8,
+    /// Number of times the samples were collected.
+    pub attempts: u8,
+}
+
+impl Quality {
+    /// Overall quality, from 0 (useless) to 100 (perfect sphere, fully covered).
+    pub fn score(&self) -> u8 {
+        let spread = 1.0 - self.spread.min(1.0);
+        let coverage = self
+            .coverage
+            .iter()
+            .fold(1.0_f32, |min, axis| min.min(*axis))
+            .min(1.0);
+        let inliers = 1.0 - self.outliers as f32 / PERIMETER_POINTS as f32;
+        (100.0 * spread * coverage * inliers) as u8
+    }
+
+    pub fn is_good(&self) -> bool {
+        self.score() >= MIN_SCORE
+    }
+}
+
+impl Default for Calibration {
+    fn default() -> Calibration {
+        Calibration {
+            center: Vector::new(0, 0, 0),
+            scale: Vector::new(1024, 1024, 1024),
+            radius: 0,
+            quality: Quality::default(),
+        }
+    }
+}
+
+impl Calibration {
+    pub fn quality(&self) -> &Quality {
+        &self.quality
+    }
+
+    /// Hard iron offset, in the east, north, up frame the samples are collected in.
+    pub fn center(&self) -> Vector {
+        self.center
+    }
+
+    /// Soft iron correction per axis, 1024 being a factor of one.
+    pub fn scale(&self) -> Vector {
+        self.scale
+    }
+
+    /// Distance of the furthest sample from the center.
+    pub fn radius(&self) -> u32 {
+        self.radius
+    }
+
+    /// Applies the calibration to a raw magnetometer reading and returns it in the x
+    /// (right), y (up the board) and z frame the LED compass works in.
+    pub fn apply(&self, mag: Vector) -> Vector {
+        let enu = measurement_to_enu(mag);
+        enu_to_cartesian(Vector {
+            x: ((enu.x - self.center.x) * self.scale.x) >> 10,
+            y: ((enu.y - self.center.y) * self.scale.y) >> 10,
+            z: ((enu.z - self.center.z) * self.scale.z) >> 10,
+        })
+    }
+}
+
+/// The tilting game that collects the samples of a calibration, see the crate documentation.
+#[derive(Debug, Clone)]
+pub struct Collector {
+    leds: [[u8; 5]; 5],
+    cursor: (usize, usize),
+    samples: [Vector; PERIMETER_POINTS],
+    len: usize,
+}
+
+impl Default for Collector {
+    fn default() -> Collector {
+        Collector::new()
+    }
+}
+
+impl Collector {
+    pub const fn new() -> Collector {
+        Collector {
+            leds: [[0; 5]; 5],
+            cursor: (2, 2),
+            samples: [Vector::new(0, 0, 0); PERIMETER_POINTS],
+            len: 0,
+        }
+    }
+
+    /// Moves the cursor according to an accelerometer reading in milli-g.
+    ///
+    /// Returns `true` if the cursor reached an LED that is still off, in which case the next
+    /// magnetometer reading has to be passed to [`Collector::add`].
+    pub fn update(&mut self, accel: Vector) -> bool {
+        let column = grid_position(accel.x);
+        // Turn the y axis properly
+        let row = 4 - grid_position(accel.y);
+        self.cursor = (row, column);
+        !self.is_complete() && self.leds[row][column] == 0
+    }
+
+    /// Records a magnetometer reading for the LED under the cursor.
+    pub fn add(&mut self, mag: Vector) {
+        let (row, column) = self.cursor;
+        if self.leds[row][column] == 0 && !self.is_complete() {
+            self.leds[row][column] = 1;
+            self.samples[self.len] = measurement_to_enu(mag);
+            self.len += 1;
+        }
+    }
+
+    /// The LEDs that already have a sample.
+    pub fn leds(&self) -> [[u8; 5]; 5] {
+        self.leds
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.len == PERIMETER_POINTS
+    }
+
+    /// The samples once all LEDs are lit.
+    pub fn samples(&self) -> Option<&[Vector; PERIMETER_POINTS]> {
+        if self.is_complete() {
+            Some(&self.samples)
+        } else {
+            None
+        }
+    }
+}
+
+fn grid_position(tilt: i32) -> usize {
+    if tilt < -PIXEL2_THRESHOLD {
+        0
+    } else if tilt < -PIXEL1_THRESHOLD {
+        1
+    } else if tilt > PIXEL2_THRESHOLD {
+        4
+    } else if tilt > PIXEL1_THRESHOLD {
+        3
+    } else {
+        2
+    }
+}
+
+/// Computes a calibration from the samples of a [`Collector`] and rates it.
+///
+/// `attempts` is the number of times samples have been collected so far and is only
+/// reported in the [`Quality`].
+pub fn calibrate(data: &[Vector; PERIMETER_POINTS], attempts: u8) -> Calibration {
+    let mut calibration = fit(data);
+    calibration.quality = assess(&calibration, data, attempts);
+    calibration
+}
+
+fn difference_square(a: Vector, b: Vector) -> f32 {
+    let dx = (a.x - b.x) as f32;
+    let dy = (a.y - b.y) as f32;
+    let dz = (a.z - b.z) as f32;
+
+    (dx * dx) + (dy * dy) + (dz * dz)
+}
+
+fn measure_score(center: Vector, data: &[Vector]) -> f32 {
+    let mut min_d = difference_square(center, data[0]);
+    let mut max_d = min_d;
+
+    for point in data[1..].iter() {
+        let d = difference_square(center, *point);
+        if d < min_d {
+            min_d = d;
+        }
+
+        if d > max_d {
+            max_d = d;
+        }
+    }
+
+    max_d - min_d
+}
+
+fn fit(data: &[Vector]) -> Calibration {
+    // Approximate a center for the data
+    let mut center = Vector::new(0, 0, 0);
+    let mut best = center;
+
+    for point in data {
+        center.x += point.x;
+        center.y += point.y;
+        center.z += point.z;
+    }
+
+    center.x /= data.len() as i32;
+    center.y /= data.len() as i32;
+    center.z /= data.len() as i32;
+
+    let mut current = center;
+    let mut score = measure_score(current, data);
+
+    // Calculate a fixpoint position
+    loop {
+        for x in [-CALIBRATION_INCREMENT, 0, CALIBRATION_INCREMENT].iter() {
+            for y in [-CALIBRATION_INCREMENT, 0, CALIBRATION_INCREMENT].iter() {
+                for z in [-CALIBRATION_INCREMENT, 0, CALIBRATION_INCREMENT].iter() {
+                    let mut attempt = current;
+                    attempt.x += x;
+                    attempt.y += y;
+                    attempt.z += z;
+
+                    let attempt_score = measure_score(attempt, data);
+                    if attempt_score < score {
+                        score = attempt_score;
+                        best = attempt;
+                    }
+                }
+            }
+        }
+
+        if best == current {
+            break;
+        }
+
+        current = best;
+    }
+
+    spherify(current, data)
+}
+
+fn assess(calibration: &Calibration, data: &[Vector; PERIMETER_POINTS], attempts: u8) -> Quality {
+    let origin = Vector::new(0, 0, 0);
+    let mut corrected = [origin; PERIMETER_POINTS];
+    for (out, point) in corrected.iter_mut().zip(data.iter()) {
+        *out = Vector {
+            x: ((point.x - calibration.center.x) * calibration.scale.x) >> 10,
+            y: ((point.y - calibration.center.y) * calibration.scale.y) >> 10,
+            z: ((point.z - calibration.center.z) * calibration.scale.z) >> 10,
+        };
+    }
+
+    let mut max_d = 0.0;
+    let mut mean = 0.0;
+    let mut min = [i32::MAX; 3];
+    let mut max = [i32::MIN; 3];
+    for point in corrected.iter() {
+        let d = difference_square(origin, *point);
+        if d > max_d {
+            max_d = d;
+        }
+        mean += sqrtf(d);
+
+        for (axis, value) in [point.x, point.y, point.z].iter().enumerate() {
+            min[axis] = min[axis].min(*value);
+            max[axis] = max[axis].max(*value);
+        }
+    }
+    mean /= corrected.len() as f32;
+
+    if max_d == 0.0 {
+        return Quality {
+            spread: 1.0,
+            attempts,
+            ..Quality::default()
+        };
+    }
+
+    let diameter = 2.0 * sqrtf(max_d);
+    let mut coverage = [0.0; 3];
+    for axis in 0..3 {
+        coverage[axis] = (max[axis] - min[axis]) as f32 / diameter;
+    }
+
+    let outliers = corrected
+        .iter()
+        .filter(|point| {
+            fabsf(sqrtf(difference_square(origin, **point)) - mean) > OUTLIER_TOLERANCE * mean
+        })
+        .count() as u8;
+
+    Quality {
+        spread: measure_score(origin, &corrected) / max_d,
+        coverage,
+        outliers,
+        attempts,
+    }
+}
+
+fn spherify(center: Vector, data: &[Vector]) -> Calibration {
+    let mut radius = 0;
+    for point in data {
+        let d = sqrtf(difference_square(center, *point)) as u32;
+        if d > radius {
+            radius = d;
+        }
+    }
+
+    let mut scale: f32 = 0.0;
+    let mut weight_x = 0.0;
+    let mut weight_y = 0.0;
+    let mut weight_z = 0.0;
+
+    for point in data {
+        let d = sqrtf(difference_square(center, *point));
+        let s = (radius as f32 / d) - 1.0;
+        scale = scale.max(s);
+
+        let dx = point.x - center.x;
+        let dy = point.y - center.y;
+        let dz = point.z - center.z;
+
+        weight_x += s * fabsf(dx as f32 / d);
+        weight_y += s * fabsf(dy as f32 / d);
+        weight_z += s * fabsf(dz as f32 / d);
+    }
+
+    let wmag = sqrtf((weight_x * weight_x) + (weight_y * weight_y) + (weight_z * weight_z));
+    let scale_x = 1.0 + scale * (weight_x / wmag);
+    let scale_y = 1.0 + scale * (weight_y / wmag);
+    let scale_z = 1.0 + scale * (weight_z / wmag);
+
+    Calibration {
+        center,
+        radius,
+        scale: Vector {
+            x: (1024.0 * scale_x) as i32,
+            y: (1024.0 * scale_y) as i32,
+            z: (1024.0 * scale_z) as i32,
+        },
+        quality: Quality::default(),
+    }
+}
+
+fn measurement_to_enu(measurement: Vector) -> Vector {
+    Vector {
+        x: -measurement.y,
+        y: -measurement.x,
+        z: measurement.z,
+    }
+}
+
+fn enu_to_cartesian(measurement: Vector) -> Vector {
+    Vector {
+        x: -measurement.y,
+        y: measurement.x,
+        z: measurement.z,
+    }
+}
@@ -0,0 +1,161 @@
+use compass_calibration::{calibrate, Calibration, Collector, Vector, PERIMETER_POINTS};
+use test_support::{random_rotation, rotate, rotation, Random, Rotation, EARTH, GRAVITY};
+
+const HARD_IRON: Vector = Vector::new(12_000, -7_000, 3_000);
+const SOFT_IRON: [f32; 3] = [1.08, 0.94, 1.0];
+
+/// What the sensors of a board held in orientation `r` measure.
+fn readings(r: &Rotation) -> (Vector, Vector) {
+    let accel = rotate(r, GRAVITY);
+    let mag = rotate(r, EARTH);
+    (
+        Vector::new(accel[0] as i32, accel[1] as i32, accel[2] as i32),
+        Vector::new(
+            (mag[0] * SOFT_IRON[0]) as i32 + HARD_IRON.x,
+            (mag[1] * SOFT_IRON[1]) as i32 + HARD_IRON.y,
+            (mag[2] * SOFT_IRON[2]) as i32 + HARD_IRON.z,
+        ),
+    )
+}
+
+/// Plays the tilting game with a board that is turned around at random, `tilt` limiting how
+/// far it is tipped over (PI for all the way).
+fn play(tilt: f32) -> (Collector, usize) {
+    let mut random = Random::new(1);
+    let mut collector = Collector::new();
+    let mut readings_taken = 0;
+    while !collector.is_complete() {
+        readings_taken += 1;
+        assert!(readings_taken < 100_000, "the game never ends");
+
+        let r = random_rotation(&mut random, tilt);
+        let (accel, mag) = readings(&r);
+        if collector.update(accel) {
+            collector.add(mag);
+        }
+    }
+    (collector, readings_taken)
+}
+
+fn magnitude(v: Vector) -> f32 {
+    let (x, y, z) = (v.x as f32, v.y as f32, v.z as f32);
+    (x * x + y * y + z * z).sqrt()
+}
+
+#[test]
+fn every_led_collects_one_sample() {
+    let mut collector = Collector::new();
+    let mut samples = 0;
+    for &x in [-700, -300, 0, 300, 700].iter() {
+        for &y in [-700, -300, 0, 300, 700].iter() {
+            assert!(collector.update(Vector::new(x, y, 500)));
+            collector.add(Vector::new(x, y, 0));
+            samples += 1;
+            // Staying on the same LED doesn't ask for more
+            assert!(!collector.update(Vector::new(x, y, 500)));
+            assert_eq!(collector.is_complete(), samples == PERIMETER_POINTS);
+        }
+    }
+    assert_eq!(collector.leds(), [[1; 5]; 5]);
+    assert!(collector.samples().is_some());
+}
+
+#[test]
+fn leds_follow_the_tilt() {
+    let mut collector = Collector::new();
+    // Tilted to the right and towards the user: right column, bottom row
+    assert!(collector.update(Vector::new(800, -800, 0)));
+    collector.add(Vector::default());
+    let mut expected = [[0; 5]; 5];
+    expected[4][4] = 1;
+    assert_eq!(collector.leds(), expected);
+    assert!(collector.samples().is_none());
+}
+
+#[test]
+fn finds_hard_iron_offset() {
+    let (collector, _) = play(core::f32::consts::PI);
+    let calibration = calibrate(collector.samples().unwrap(), 1);
+
+    // Samples are collected in the east, north, up frame
+    let expected = Vector::new(-HARD_IRON.y, -HARD_IRON.x, HARD_IRON.z);
+    let center = calibration.center();
+    let error = magnitude(Vector::new(
+        center.x - expected.x,
+        center.y - expected.y,
+        center.z - expected.z,
+    ));
+    assert!(
+        error < 2_000.0,
+        "center {:?}, expected {:?}",
+        center,
+        expected
+    );
+    assert!(
+        calibration.quality().is_good(),
+        "{:?}",
+        calibration.quality()
+    );
+}
+
+#[test]
+fn calibrated_readings_lie_on_a_sphere() {
+    let (collector, _) = play(core::f32::consts::PI);
+    let calibration = calibrate(collector.samples().unwrap(), 1);
+
+    let mut random = Random::new(7);
+    let (mut min, mut max) = (f32::MAX, 0.0_f32);
+    let (mut raw_min, mut raw_max) = (f32::MAX, 0.0_f32);
+    for _ in 0..200 {
+        let r = random_rotation(&mut random, core::f32::consts::PI);
+        let (_, mag) = readings(&r);
+        let calibrated = magnitude(calibration.apply(mag));
+        min = min.min(calibrated);
+        max = max.max(calibrated);
+        let raw = magnitude(mag);
+        raw_min = raw_min.min(raw);
+        raw_max = raw_max.max(raw);
+    }
+
+    assert!(
+        (max - min) / max < 0.2,
+        "calibrated magnitude between {} and {}",
+        min,
+        max
+    );
+    assert!((max - min) / max < (raw_max - raw_min) / raw_max / 2.0);
+}
+
+#[test]
+fn flat_board_is_a_poor_calibration() {
+    // Only turned around on the table, so the samples form a circle instead of a sphere
+    let mut samples = [Vector::default(); PERIMETER_POINTS];
+    for (i, sample) in samples.iter_mut().enumerate() {
+        let yaw = i as f32 * 2.0 * core::f32::consts::PI / PERIMETER_POINTS as f32;
+        let (_, mag) = readings(&rotation(yaw, 0.0, 0.0));
+        *sample = Vector::new(-mag.y, -mag.x, mag.z);
+    }
+    let calibration = calibrate(&samples, 3);
+    assert!(
+        !calibration.quality().is_good(),
+        "{:?}",
+        calibration.quality()
+    );
+    assert_eq!(calibration.quality().attempts, 3);
+}
+
+#[test]
+fn identical_samples_score_zero() {
+    let samples = [Vector::new(100, 200, 300); PERIMETER_POINTS];
+    let calibration = calibrate(&samples, 1);
+    assert_eq!(calibration.quality().score(), 0);
+}
+
+#[test]
+fn default_calibration_only_changes_the_frame() {
+    let calibration = Calibration::default();
+    assert_eq!(
+        calibration.apply(Vector::new(1, 2, 3)),
+        Vector::new(1, -2, 3)
+    );
+}
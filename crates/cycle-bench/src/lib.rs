@@ -0,0 +1,265 @@
+//! Micro-benchmarks measured in clock cycles.
+//!
+//! Register the code to measure as named closures, then let the `Harness` run each of them a
+//! number of times with interrupts disabled. It reports the minimum, mean and maximum number of
+//! cycles, and the same in microseconds, one CSV line per benchmark:
+//!
+//! ``` text
+//! bench,name,runs,min_cycles,mean_cycles,max_cycles,min_us,mean_us,max_us
+//! bench,tdr-loop,100,22415,22480,22610,2801.875,2810.000,2826.250
+//! ```
+//!
+//! Every line starts with `bench,` so the results can be picked out of a log that has other
+//! output mixed in, and `parse_line` reads them back, e.g. to compare two runs on the host.
+//!
+//! Where the cycles come from, and how interrupts are disabled, is up to the `Platform`:
+//!
+//! ```
+//! use std::cell::Cell;
+//!
+//! use cycle_bench::{Harness, Platform};
+//!
+//! /// A clock that advances 10 cycles every time it is read.
+//! struct FakePlatform {
+//!     cycles: Cell<u64>,
+//! }
+//!
+//! impl Platform for FakePlatform {
+//!     fn cycles(&self) -> u64 {
+//!         self.cycles.set(self.cycles.get() + 10);
+//!         self.cycles.get()
+//!     }
+//!
+//!     fn frequency(&self) -> u32 {
+//!         8_000_000
+//!     }
+//! }
+//!
+//! let platform = FakePlatform { cycles: Cell::new(0) };
+//! let mut sum = 0;
+//! let mut add = || sum += 1;
+//! let mut nothing = || {};
+//!
+//! let mut harness: Harness<_, 4> = Harness::new(platform, 3);
+//! harness.add("add", &mut add).unwrap();
+//! harness.add("nothing", &mut nothing).unwrap();
+//!
+//! let mut report = String::new();
+//! harness.run(&mut report).unwrap();
+//!
+//! let lines: Vec<_> = report.lines().collect();
+//! assert_eq!(lines[0], cycle_bench::HEADER);
+//! // The fake clock only moves when it's read, so the time the closures take is the same as
+//! // the overhead of measuring, which is subtracted
+//! assert_eq!(lines[1], "bench,add,3,0,0,0,0.000,0.000,0.000");
+//! drop(harness);
+//! assert_eq!(sum, 3);
+//!
+//! let result = cycle_bench::parse_line(lines[2]).unwrap();
+//! assert_eq!(result.name, "nothing");
+//! assert_eq!(result.runs, 3);
+//! ```
+
+#![no_std]
+
+use core::fmt;
+
+use heapless::Vec;
+
+/// The first line of a report, naming the columns.
+pub const HEADER: &str = "bench,name,runs,min_cycles,mean_cycles,max_cycles,min_us,mean_us,max_us";
+
+/// What the harness needs from the target it runs on.
+pub trait Platform {
+    /// Clock cycles since some point in the past. Must not wrap around during a benchmark.
+    fn cycles(&self) -> u64;
+
+    /// Frequency of the clock that `cycles` counts, in Hz.
+    fn frequency(&self) -> u32;
+
+    /// Runs `f` with interrupts disabled, so that they don't end up in the measurements. The
+    /// default just runs `f`, which is what host code wants.
+    fn without_interrupts(&self, f: &mut dyn FnMut()) {
+        f()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The harness has no room for another benchmark.
+    Full,
+    /// Names can't be empty, or contain commas or whitespace, so that the report stays easy to
+    /// parse.
+    InvalidName,
+}
+
+/// Cycle counts of the runs of a benchmark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub runs: u32,
+    pub min: u64,
+    pub max: u64,
+    /// Sum of all the runs.
+    pub total: u64,
+}
+
+impl Stats {
+    const fn new() -> Stats {
+        Stats {
+            runs: 0,
+            min: u64::MAX,
+            max: 0,
+            total: 0,
+        }
+    }
+
+    fn record(&mut self, cycles: u64) {
+        self.runs += 1;
+        self.min = self.min.min(cycles);
+        self.max = self.max.max(cycles);
+        self.total += cycles;
+    }
+
+    /// Average number of cycles per run, rounded down.
+    pub fn mean(&self) -> u64 {
+        if self.runs == 0 {
+            0
+        } else {
+            self.total / u64::from(self.runs)
+        }
+    }
+}
+
+struct Bench<'b> {
+    name: &'b str,
+    run: &'b mut dyn FnMut(),
+}
+
+/// Runs up to `N` benchmarks, `runs` times each.
+pub struct Harness<'b, P, const N: usize> {
+    platform: P,
+    runs: u32,
+    benches: Vec<Bench<'b>, N>,
+}
+
+impl<'b, P, const N: usize> Harness<'b, P, N>
+where
+    P: Platform,
+{
+    pub fn new(platform: P, runs: u32) -> Self {
+        Harness {
+            platform,
+            runs,
+            benches: Vec::new(),
+        }
+    }
+
+    /// Registers a benchmark. They run in the order they are added.
+    pub fn add(&mut self, name: &'b str, run: &'b mut dyn FnMut()) -> Result<(), Error> {
+        if name.is_empty() || name.contains(|c: char| c == ',' || c.is_whitespace()) {
+            return Err(Error::InvalidName);
+        }
+        self.benches
+            .push(Bench { name, run })
+            .map_err(|_| Error::Full)
+    }
+
+    /// Runs every benchmark and writes the report to `out`, one line as soon as each benchmark
+    /// is done.
+    pub fn run(&mut self, out: &mut dyn fmt::Write) -> fmt::Result {
+        let overhead = self.measure(&mut || {}).min;
+
+        writeln!(out, "{}", HEADER)?;
+        for i in 0..self.benches.len() {
+            let run = &mut *self.benches[i].run;
+            let stats = measure(&self.platform, self.runs, run);
+            let stats = Stats {
+                min: stats.min.saturating_sub(overhead),
+                max: stats.max.saturating_sub(overhead),
+                total: stats.total.saturating_sub(overhead * u64::from(stats.runs)),
+                ..stats
+            };
+            write_line(out, self.benches[i].name, &stats, self.platform.frequency())?;
+        }
+        Ok(())
+    }
+
+    /// Measures a single closure, without subtracting the overhead of measuring.
+    pub fn measure(&self, f: &mut dyn FnMut()) -> Stats {
+        measure(&self.platform, self.runs, f)
+    }
+
+    /// Gives the platform back.
+    pub fn free(self) -> P {
+        self.platform
+    }
+}
+
+fn measure(platform: &dyn Platform, runs: u32, f: &mut dyn FnMut()) -> Stats {
+    let mut stats = Stats::new();
+    for _ in 0..runs.max(1) {
+        let mut cycles = 0;
+        platform.without_interrupts(&mut || {
+            let start = platform.cycles();
+            f();
+            cycles = platform.cycles() - start;
+        });
+        stats.record(cycles);
+    }
+    stats
+}
+
+fn write_line(out: &mut dyn fmt::Write, name: &str, stats: &Stats, frequency: u32) -> fmt::Result {
+    write!(out, "bench,{},{}", name, stats.runs)?;
+    for cycles in [stats.min, stats.mean(), stats.max].iter() {
+        write!(out, ",{}", cycles)?;
+    }
+    for cycles in [stats.min, stats.mean(), stats.max].iter() {
+        let nanos = cycles * 1_000_000_000 / u64::from(frequency);
+        write!(out, ",{}.{:03}", nanos / 1_000, nanos % 1_000)?;
+    }
+    writeln!(out)
+}
+
+/// A benchmark result read back from a report.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Record<'l> {
+    pub name: &'l str,
+    pub runs: u32,
+    pub min_cycles: u64,
+    pub mean_cycles: u64,
+    pub max_cycles: u64,
+    pub min_us: f32,
+    pub mean_us: f32,
+    pub max_us: f32,
+}
+
+/// Parses a result line of a report. Returns `None` for the header and any other line.
+pub fn parse_line(line: &str) -> Option<Record<'_>> {
+    let mut fields = line.trim().split(',');
+    if fields.next()? != "bench" {
+        return None;
+    }
+    let name = fields.next()?;
+    let runs = fields.next()?.parse().ok()?;
+    let min_cycles = fields.next()?.parse().ok()?;
+    let mean_cycles = fields.next()?.parse().ok()?;
+    let max_cycles = fields.next()?.parse().ok()?;
+    let min_us = fields.next()?.parse().ok()?;
+    let mean_us = fields.next()?.parse().ok()?;
+    let max_us = fields.next()?.parse().ok()?;
+    if fields.next().is_some() {
+        return None;
+    }
+
+    Some(Record {
+        name,
+        runs,
+        min_cycles,
+        mean_cycles,
+        max_cycles,
+        min_us,
+        mean_us,
+        max_us,
+    })
+}
@@ -0,0 +1,202 @@
+//! The harness against a fake clock that replays known cycle counts.
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+
+use cycle_bench::{parse_line, Error, Harness, Platform, Record, Stats, HEADER};
+
+/// A clock whose every pair of reads, start and end of a run, is apart by the next of the
+/// given durations.
+struct Script {
+    reads: RefCell<VecDeque<u64>>,
+    frequency: u32,
+    without_interrupts: Cell<u32>,
+}
+
+impl Script {
+    fn new(frequency: u32, durations: &[u64]) -> Script {
+        let mut now = 1_000;
+        let mut reads = VecDeque::new();
+        for duration in durations {
+            reads.push_back(now);
+            now += duration;
+            reads.push_back(now);
+            // Time passes in between runs, too
+            now += 7;
+        }
+        Script {
+            reads: RefCell::new(reads),
+            frequency,
+            without_interrupts: Cell::new(0),
+        }
+    }
+}
+
+impl Platform for Script {
+    fn cycles(&self) -> u64 {
+        self.reads
+            .borrow_mut()
+            .pop_front()
+            .expect("read more often than scripted")
+    }
+
+    fn frequency(&self) -> u32 {
+        self.frequency
+    }
+
+    fn without_interrupts(&self, f: &mut dyn FnMut()) {
+        self.without_interrupts
+            .set(self.without_interrupts.get() + 1);
+        f()
+    }
+}
+
+fn report<P: Platform, const N: usize>(harness: &mut Harness<P, N>) -> Vec<String> {
+    let mut report = String::new();
+    harness.run(&mut report).unwrap();
+    report.lines().map(String::from).collect()
+}
+
+#[test]
+fn measure_records_every_run() {
+    let harness: Harness<_, 1> = Harness::new(Script::new(8_000_000, &[30, 10, 50, 20]), 4);
+    let stats = harness.measure(&mut || {});
+    assert_eq!(
+        stats,
+        Stats {
+            runs: 4,
+            min: 10,
+            max: 50,
+            total: 110,
+        }
+    );
+    // Rounded down from 27.5
+    assert_eq!(stats.mean(), 27);
+    let platform = harness.free();
+    assert_eq!(platform.without_interrupts.get(), 4);
+    assert!(platform.reads.borrow().is_empty());
+}
+
+#[test]
+fn overhead_is_subtracted() {
+    // The first four runs measure nothing, i.e. the overhead, which is their minimum
+    let durations = [6, 5, 9, 5, 105, 125, 115, 135];
+    let mut work = || {};
+    let mut harness: Harness<_, 1> = Harness::new(Script::new(8_000_000, &durations), 4);
+    harness.add("work", &mut work).unwrap();
+
+    let lines = report(&mut harness);
+    assert_eq!(lines[0], HEADER);
+    // 100, 120, 110 and 130 cycles, at 125 ns each
+    assert_eq!(lines[1], "bench,work,4,100,115,130,12.500,14.375,16.250");
+    assert_eq!(lines.len(), 2);
+    assert_eq!(harness.free().without_interrupts.get(), 8);
+}
+
+#[test]
+fn faster_than_the_overhead_is_zero() {
+    let durations = [20, 20, 15, 27];
+    let mut tiny = || {};
+    let mut harness: Harness<_, 1> = Harness::new(Script::new(8_000_000, &durations), 2);
+    harness.add("tiny", &mut tiny).unwrap();
+    assert_eq!(
+        report(&mut harness)[1],
+        "bench,tiny,2,0,1,7,0.000,0.125,0.875"
+    );
+}
+
+#[test]
+fn cycles_to_microseconds() {
+    // At 72 MHz a cycle is 13.9 ns, which the report rounds down to whole nanoseconds
+    let durations = [0, 1, 72, 7_200_000];
+    let mut closures = [|| {}; 3];
+    let mut harness: Harness<_, 3> = Harness::new(Script::new(72_000_000, &durations), 1);
+    let names = ["one", "us", "tenth-second"];
+    for (name, run) in names.iter().zip(closures.iter_mut()) {
+        harness.add(name, run).unwrap();
+    }
+    let lines = report(&mut harness);
+    assert_eq!(lines[1], "bench,one,1,1,1,1,0.013,0.013,0.013");
+    assert_eq!(lines[2], "bench,us,1,72,72,72,1.000,1.000,1.000");
+    assert_eq!(
+        lines[3],
+        "bench,tenth-second,1,7200000,7200000,7200000,100000.000,100000.000,100000.000"
+    );
+}
+
+#[test]
+fn benches_run_in_order() {
+    let order = RefCell::new(Vec::new());
+    let mut first = || order.borrow_mut().push("first");
+    let mut second = || order.borrow_mut().push("second");
+    let mut harness: Harness<_, 2> = Harness::new(Script::new(8_000_000, &[1; 6]), 2);
+    harness.add("first", &mut first).unwrap();
+    harness.add("second", &mut second).unwrap();
+    let lines = report(&mut harness);
+    drop(harness);
+
+    assert_eq!(*order.borrow(), ["first", "first", "second", "second"]);
+    assert!(lines[1].starts_with("bench,first,"));
+    assert!(lines[2].starts_with("bench,second,"));
+}
+
+#[test]
+fn zero_runs_still_run_once() {
+    let mut once = || {};
+    let mut harness: Harness<_, 1> = Harness::new(Script::new(8_000_000, &[3, 10]), 0);
+    harness.add("once", &mut once).unwrap();
+    assert_eq!(
+        report(&mut harness)[1],
+        "bench,once,1,7,7,7,0.875,0.875,0.875"
+    );
+}
+
+#[test]
+fn names_and_capacity() {
+    let mut closures = [|| {}; 6];
+    let mut closures = closures.iter_mut();
+    let mut harness: Harness<_, 1> = Harness::new(Script::new(8_000_000, &[]), 1);
+    for name in ["", "two words", "a,b", "tab\there"].iter() {
+        let run = closures.next().unwrap();
+        assert_eq!(harness.add(name, run), Err(Error::InvalidName));
+    }
+    harness.add("only", closures.next().unwrap()).unwrap();
+    assert_eq!(
+        harness.add("another", closures.next().unwrap()),
+        Err(Error::Full)
+    );
+}
+
+#[test]
+fn lines_parse_back() {
+    let mut a = || {};
+    let mut harness: Harness<_, 1> = Harness::new(Script::new(8_000_000, &[4, 4, 54, 64]), 2);
+    harness.add("a", &mut a).unwrap();
+    let lines = report(&mut harness);
+
+    assert_eq!(parse_line(&lines[0]), None);
+    assert_eq!(
+        parse_line(&lines[1]),
+        Some(Record {
+            name: "a",
+            runs: 2,
+            min_cycles: 50,
+            mean_cycles: 55,
+            max_cycles: 60,
+            min_us: 6.25,
+            mean_us: 6.875,
+            max_us: 7.5,
+        })
+    );
+}
+
+#[test]
+fn other_lines_do_not_parse() {
+    assert_eq!(parse_line(""), None);
+    assert_eq!(parse_line("Running benchmarks"), None);
+    assert_eq!(parse_line("bench,a,2,50,55,60,6.250,6.875"), None);
+    assert_eq!(parse_line("bench,a,2,50,55,60,6.250,6.875,7.500,8"), None);
+    assert_eq!(parse_line("bench,a,two,50,55,60,6.250,6.875,7.500"), None);
+    // Surrounding whitespace, like the `\r` of a serial log, is fine
+    assert!(parse_line("bench,a,2,50,55,60,6.250,6.875,7.500\r").is_some());
+}
@@ -0,0 +1,259 @@
+//! A small type-state GPIO layer, to show how a HAL keeps pins from being misused.
+//!
+//! Every pin starts out `Unconfigured`. It has to be turned into an `Input` or an `Output`
+//! before it can be read or driven, and the compiler enforces it: a `Pin<_, Unconfigured>`
+//! simply has no `set_high` method.
+//!
+//! ``` compile_fail
+//! # use gpio_typestate::{Pins, Register, Registers};
+//! # struct Nothing;
+//! # impl Registers for Nothing {
+//! #     fn read(&self, _: Register) -> u32 { 0 }
+//! #     fn write(&self, _: Register, _: u32) {}
+//! # }
+//! let pins = Pins::new(&Nothing);
+//! pins.p9.set_high(); // error: no method named `set_high` found
+//! ```
+//!
+//! The pins reach the hardware through the `Registers` trait, so that the layer can run on top
+//! of the PAC's register block on the board, or of a fake one on a computer, which is how the
+//! register writes below are checked:
+//!
+//! ```
+//! use std::cell::RefCell;
+//!
+//! use gpio_typestate::{Pins, Register, Registers};
+//!
+//! /// Keeps the registers in memory and logs every write.
+//! #[derive(Default)]
+//! struct FakeGpio {
+//!     moder: RefCell<u32>,
+//!     otyper: RefCell<u32>,
+//!     idr: RefCell<u32>,
+//!     odr: RefCell<u32>,
+//!     writes: RefCell<Vec<(Register, u32)>>,
+//! }
+//!
+//! impl Registers for FakeGpio {
+//!     fn read(&self, register: Register) -> u32 {
+//!         match register {
+//!             Register::Moder => *self.moder.borrow(),
+//!             Register::Otyper => *self.otyper.borrow(),
+//!             Register::Idr => *self.idr.borrow(),
+//!             Register::Odr => *self.odr.borrow(),
+//!             // Write-only, reads as zero
+//!             Register::Bsrr => 0,
+//!         }
+//!     }
+//!
+//!     fn write(&self, register: Register, value: u32) {
+//!         self.writes.borrow_mut().push((register, value));
+//!         match register {
+//!             Register::Moder => *self.moder.borrow_mut() = value,
+//!             Register::Otyper => *self.otyper.borrow_mut() = value,
+//!             Register::Idr => panic!("IDR is read-only"),
+//!             Register::Odr => *self.odr.borrow_mut() = value,
+//!             Register::Bsrr => {
+//!                 let mut odr = self.odr.borrow_mut();
+//!                 *odr = (*odr | value & 0xffff) & !(value >> 16);
+//!             }
+//!         }
+//!     }
+//! }
+//!
+//! let gpio = FakeGpio::default();
+//! // Some other pins are outputs already
+//! *gpio.moder.borrow_mut() = 0b01;
+//!
+//! let pins = Pins::new(&gpio);
+//! let mut led = pins.p9.into_push_pull_output();
+//! led.set_high();
+//! led.set_low();
+//! let button = pins.p0.into_input();
+//!
+//! assert_eq!(
+//!     *gpio.writes.borrow(),
+//!     [
+//!         // MODER9 = 0b01, the other pins untouched
+//!         (Register::Moder, 0b01 << 18 | 0b01),
+//!         // OT9 = 0, push-pull
+//!         (Register::Otyper, 0),
+//!         // BS9, then BR9
+//!         (Register::Bsrr, 1 << 9),
+//!         (Register::Bsrr, 1 << 25),
+//!         // MODER0 = 0b00
+//!         (Register::Moder, 0b01 << 18),
+//!     ]
+//! );
+//!
+//! *gpio.idr.borrow_mut() = 1;
+//! assert!(button.is_high());
+//! ```
+
+#![no_std]
+
+use core::marker::PhantomData;
+
+/// The GPIO registers the pins use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    /// Mode, 2 bits per pin: `0b00` input, `0b01` output.
+    Moder,
+    /// Output type, 1 bit per pin: 0 push-pull, 1 open-drain.
+    Otyper,
+    /// Input data, read-only.
+    Idr,
+    /// Output data.
+    Odr,
+    /// Bit set/reset, write-only: the lower half sets `ODR` bits, the upper half clears them.
+    Bsrr,
+}
+
+/// Access to the registers of a GPIO port.
+///
+/// Writes are whole registers, like the PAC's `write`; the pins do the read-modify-write.
+pub trait Registers {
+    fn read(&self, register: Register) -> u32;
+    fn write(&self, register: Register, value: u32);
+}
+
+/// Mode of a pin right after reset, as far as the type system is concerned: it may be
+/// anything, so it can't be used.
+pub struct Unconfigured;
+
+/// Digital input mode.
+pub struct Input;
+
+/// Push-pull output mode.
+pub struct Output;
+
+/// Pin `index` of the port whose registers are `R`, in `MODE`.
+pub struct Pin<'r, R, MODE> {
+    registers: &'r R,
+    index: u8,
+    _mode: PhantomData<MODE>,
+}
+
+impl<'r, R, MODE> Pin<'r, R, MODE>
+where
+    R: Registers,
+{
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    /// Configures the pin as a push-pull output.
+    pub fn into_push_pull_output(self) -> Pin<'r, R, Output> {
+        self.set_mode(0b01);
+        let otyper = self.registers.read(Register::Otyper);
+        self.registers
+            .write(Register::Otyper, otyper & !(1 << self.index));
+        self.into_mode()
+    }
+
+    /// Configures the pin as a digital input.
+    pub fn into_input(self) -> Pin<'r, R, Input> {
+        self.set_mode(0b00);
+        self.into_mode()
+    }
+
+    fn set_mode(&self, mode: u32) {
+        let shift = 2 * u32::from(self.index);
+        let moder = self.registers.read(Register::Moder);
+        self.registers
+            .write(Register::Moder, moder & !(0b11 << shift) | mode << shift);
+    }
+
+    fn into_mode<NEW>(self) -> Pin<'r, R, NEW> {
+        Pin {
+            registers: self.registers,
+            index: self.index,
+            _mode: PhantomData,
+        }
+    }
+}
+
+impl<R> Pin<'_, R, Output>
+where
+    R: Registers,
+{
+    /// Drives the pin high. `BSRR` makes it a single write, so there's no read-modify-write
+    /// that could race with another pin of the port.
+    pub fn set_high(&mut self) {
+        self.registers.write(Register::Bsrr, 1 << self.index);
+    }
+
+    pub fn set_low(&mut self) {
+        self.registers.write(Register::Bsrr, 1 << (self.index + 16));
+    }
+
+    /// Whether the pin is being driven high.
+    pub fn is_set_high(&self) -> bool {
+        self.registers.read(Register::Odr) & (1 << self.index) != 0
+    }
+
+    pub fn toggle(&mut self) {
+        if self.is_set_high() {
+            self.set_low()
+        } else {
+            self.set_high()
+        }
+    }
+}
+
+impl<R> Pin<'_, R, Input>
+where
+    R: Registers,
+{
+    pub fn is_high(&self) -> bool {
+        self.registers.read(Register::Idr) & (1 << self.index) != 0
+    }
+
+    pub fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+}
+
+macro_rules! pins {
+    ($($field:ident: $index:expr,)+) => {
+        /// The 16 pins of a port, all `Unconfigured`.
+        pub struct Pins<'r, R> {
+            $(pub $field: Pin<'r, R, Unconfigured>,)+
+        }
+
+        impl<'r, R> Pins<'r, R>
+        where
+            R: Registers,
+        {
+            /// Splits the port into its pins. The port has to be powered on already.
+            pub fn new(registers: &'r R) -> Self {
+                Pins {
+                    $($field: Pin {
+                        registers,
+                        index: $index,
+                        _mode: PhantomData,
+                    },)+
+                }
+            }
+        }
+    };
+}
+
+pins! {
+    p0: 0,
+    p1: 1,
+    p2: 2,
+    p3: 3,
+    p4: 4,
+    p5: 5,
+    p6: 6,
+    p7: 7,
+    p8: 8,
+    p9: 9,
+    p10: 10,
+    p11: 11,
+    p12: 12,
+    p13: 13,
+    p14: 14,
+    p15: 15,
+}
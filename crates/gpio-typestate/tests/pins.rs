@@ -0,0 +1,180 @@
+//! The pins on top of a fake GPIO port.
+
+use std::cell::{Cell, RefCell};
+
+use gpio_typestate::{Pins, Register, Registers};
+
+/// Keeps the registers in memory, like the hardware would, and logs every write.
+#[derive(Default)]
+struct FakeGpio {
+    moder: Cell<u32>,
+    otyper: Cell<u32>,
+    idr: Cell<u32>,
+    odr: Cell<u32>,
+    writes: RefCell<Vec<(Register, u32)>>,
+}
+
+impl Registers for FakeGpio {
+    fn read(&self, register: Register) -> u32 {
+        match register {
+            Register::Moder => self.moder.get(),
+            Register::Otyper => self.otyper.get(),
+            Register::Idr => self.idr.get(),
+            Register::Odr => self.odr.get(),
+            // Write-only, reads as zero
+            Register::Bsrr => 0,
+        }
+    }
+
+    fn write(&self, register: Register, value: u32) {
+        self.writes.borrow_mut().push((register, value));
+        match register {
+            Register::Moder => self.moder.set(value),
+            Register::Otyper => self.otyper.set(value),
+            Register::Idr => panic!("IDR is read-only"),
+            Register::Odr => self.odr.set(value),
+            Register::Bsrr => {
+                // Setting wins when both halves name the same pin
+                let odr = self.odr.get() & !(value >> 16);
+                self.odr.set(odr | value & 0xffff);
+            }
+        }
+    }
+}
+
+impl FakeGpio {
+    fn take_writes(&self) -> Vec<(Register, u32)> {
+        self.writes.borrow_mut().drain(..).collect()
+    }
+}
+
+#[test]
+fn toggle() {
+    let gpio = FakeGpio::default();
+    let pins = Pins::new(&gpio);
+    let mut led = pins.p9.into_push_pull_output();
+    gpio.take_writes();
+
+    assert!(!led.is_set_high());
+    led.toggle();
+    assert!(led.is_set_high());
+    assert_eq!(gpio.odr.get(), 1 << 9);
+    led.toggle();
+    assert!(!led.is_set_high());
+    assert_eq!(gpio.odr.get(), 0);
+    // One BSRR write each, never a read-modify-write of ODR
+    assert_eq!(
+        gpio.take_writes(),
+        [(Register::Bsrr, 1 << 9), (Register::Bsrr, 1 << 25)]
+    );
+}
+
+#[test]
+fn toggle_leaves_other_pins_alone() {
+    let gpio = FakeGpio::default();
+    gpio.odr.set(0b1010_0000_0000_0001);
+    let pins = Pins::new(&gpio);
+    let mut led = pins.p3.into_push_pull_output();
+    led.toggle();
+    assert_eq!(gpio.odr.get(), 0b1010_0000_0000_1001);
+    led.toggle();
+    assert_eq!(gpio.odr.get(), 0b1010_0000_0000_0001);
+}
+
+#[test]
+fn highest_pin() {
+    let gpio = FakeGpio::default();
+    let pins = Pins::new(&gpio);
+    let mut pin = pins.p15.into_push_pull_output();
+    assert_eq!(pin.index(), 15);
+    assert_eq!(gpio.moder.get(), 0b01 << 30);
+    gpio.take_writes();
+
+    pin.set_high();
+    pin.set_low();
+    assert_eq!(
+        gpio.take_writes(),
+        [(Register::Bsrr, 1 << 15), (Register::Bsrr, 1 << 31)]
+    );
+}
+
+#[test]
+fn output_back_to_input() {
+    let gpio = FakeGpio::default();
+    let pins = Pins::new(&gpio);
+    let mut pin = pins.p4.into_push_pull_output();
+    pin.set_high();
+    assert_eq!(gpio.moder.get(), 0b01 << 8);
+    gpio.take_writes();
+
+    let pin = pin.into_input();
+    assert_eq!(gpio.moder.get(), 0);
+    // Only MODER changes: OTYPER doesn't matter to an input, and ODR is kept for when the
+    // pin is an output again
+    assert_eq!(gpio.take_writes(), [(Register::Moder, 0)]);
+    assert_eq!(gpio.odr.get(), 1 << 4);
+
+    gpio.idr.set(1 << 4);
+    assert!(pin.is_high());
+    gpio.idr.set(!(1 << 4));
+    assert!(pin.is_low());
+
+    let pin = pin.into_push_pull_output();
+    assert_eq!(gpio.moder.get(), 0b01 << 8);
+    assert!(pin.is_set_high());
+}
+
+#[test]
+fn moder_of_other_pins_preserved() {
+    let gpio = FakeGpio::default();
+    // Every other pin in alternate function mode
+    gpio.moder.set(0xaaaa_aaaa);
+    let pins = Pins::new(&gpio);
+
+    pins.p0.into_input();
+    assert_eq!(gpio.moder.get(), 0xaaaa_aaa8);
+    pins.p7.into_push_pull_output();
+    assert_eq!(gpio.moder.get(), 0xaaaa_6aa8);
+    pins.p15.into_input();
+    assert_eq!(gpio.moder.get(), 0x2aaa_6aa8);
+}
+
+#[test]
+fn otyper_of_other_pins_preserved() {
+    let gpio = FakeGpio::default();
+    // Open-drain outputs, e.g. an I2C bus, on the other pins
+    gpio.otyper.set(0xffff);
+    let pins = Pins::new(&gpio);
+
+    pins.p2.into_push_pull_output();
+    assert_eq!(gpio.otyper.get(), 0xfffb);
+    pins.p12.into_push_pull_output();
+    assert_eq!(gpio.otyper.get(), 0xeffb);
+
+    // Inputs don't touch OTYPER at all
+    pins.p6.into_input();
+    assert_eq!(gpio.otyper.get(), 0xeffb);
+}
+
+#[test]
+fn input_reads_its_own_bit() {
+    let gpio = FakeGpio::default();
+    let pins = Pins::new(&gpio);
+    let button = pins.p0.into_input();
+    let other = pins.p1.into_input();
+
+    gpio.idr.set(0b10);
+    assert!(button.is_low());
+    assert!(other.is_high());
+    gpio.idr.set(0b01);
+    assert!(button.is_high());
+    assert!(other.is_low());
+}
+
+#[test]
+fn no_writes_until_configured() {
+    let gpio = FakeGpio::default();
+    let pins = Pins::new(&gpio);
+    assert_eq!(pins.p5.index(), 5);
+    assert!(gpio.take_writes().is_empty());
+}
@@ -0,0 +1,322 @@
+//! A driver for the HC-05 Bluetooth module's AT commands.
+//!
+//! Instead of typing `at+name=ferris` into a terminal, as the Bluetooth setup chapter does, a
+//! program can configure the module itself over the serial port it is connected to. The
+//! module only takes AT commands in AT mode: either hold its button down while powering it up,
+//! which makes it listen at 38400 bps, or drive its KEY pin high while it runs, see
+//! `Hc05::enter_at_mode`, which keeps the baud rate it was using.
+//!
+//! The driver works with any serial port that implements the `embedded-hal` serial traits, and
+//! with anything that can tell the time in milliseconds. `tests/driver.rs` runs it against a
+//! fake module that plays back scripted answers.
+
+#![no_std]
+
+use core::fmt::{self, Write as _};
+
+use embedded_hal::{digital::v2::OutputPin, serial};
+use heapless::String;
+use serial_config::{Parity, SerialConfig, StopBits};
+
+pub mod response;
+
+use response::{Line, LineBuffer};
+
+/// Longest line the driver sends or receives, without the line ending.
+pub const MAX_LINE_LEN: usize = 64;
+
+/// How long the driver waits for an answer by default, in milliseconds.
+pub const DEFAULT_TIMEOUT_MS: u32 = 1_000;
+
+/// A clock that counts milliseconds, used for timeouts. It may wrap around.
+pub trait Millis {
+    fn millis(&mut self) -> u32;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error<E> {
+    /// The serial port reported an error.
+    Serial(E),
+    /// Setting the KEY pin failed.
+    Pin,
+    /// The module didn't answer in time. Is it in AT mode, and at the expected baud rate?
+    Timeout,
+    /// The module answered `ERROR:(code)`. See the module's datasheet for the codes.
+    Module(u8),
+    /// The module said `OK`, but not what a query should return.
+    UnexpectedResponse,
+    /// An answer didn't fit into `MAX_LINE_LEN` bytes.
+    LineTooLong,
+    /// An argument is empty, too long or has characters the module can't take.
+    InvalidArgument,
+}
+
+/// What the module does once it is out of AT mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// Waits for connections, the default.
+    Slave,
+    /// Connects to other devices.
+    Master,
+    /// Waits for connections and sends everything it receives back.
+    SlaveLoop,
+}
+
+/// The module's connection state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Initialized,
+    Ready,
+    Pairable,
+    Paired,
+    Inquiring,
+    Connecting,
+    Connected,
+    Disconnected,
+    Unknown,
+}
+
+/// An HC-05 connected to serial port `S`, timing out with clock `C`.
+pub struct Hc05<S, C> {
+    serial: S,
+    clock: C,
+    timeout_ms: u32,
+    line: LineBuffer<MAX_LINE_LEN>,
+}
+
+impl<S, C, E> Hc05<S, C>
+where
+    S: serial::Read<u8, Error = E> + serial::Write<u8, Error = E>,
+    C: Millis,
+{
+    pub fn new(serial: S, clock: C) -> Self {
+        Hc05 {
+            serial,
+            clock,
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+            line: LineBuffer::new(),
+        }
+    }
+
+    /// Changes how long to wait for an answer.
+    pub fn set_timeout_ms(&mut self, timeout_ms: u32) {
+        self.timeout_ms = timeout_ms;
+    }
+
+    /// Gives the serial port and the clock back.
+    pub fn release(self) -> (S, C) {
+        (self.serial, self.clock)
+    }
+
+    /// Drives the module's KEY pin high, which makes it take AT commands at the baud rate it
+    /// already uses, and checks that it answers.
+    pub fn enter_at_mode<P>(&mut self, key: &mut P) -> Result<(), Error<E>>
+    where
+        P: OutputPin,
+    {
+        key.set_high().map_err(|_| Error::Pin)?;
+        self.check()
+    }
+
+    /// Drives the KEY pin low again and restarts the module, which then uses the settings it
+    /// was given.
+    pub fn leave_at_mode<P>(&mut self, key: &mut P) -> Result<(), Error<E>>
+    where
+        P: OutputPin,
+    {
+        key.set_low().map_err(|_| Error::Pin)?;
+        self.reset()
+    }
+
+    /// Checks that the module is there and in AT mode.
+    pub fn check(&mut self) -> Result<(), Error<E>> {
+        self.execute(format_args!(""))
+    }
+
+    /// Restarts the module.
+    pub fn reset(&mut self) -> Result<(), Error<E>> {
+        self.execute(format_args!("+RESET"))
+    }
+
+    /// Restores the factory settings.
+    pub fn restore_defaults(&mut self) -> Result<(), Error<E>> {
+        self.execute(format_args!("+ORGL"))
+    }
+
+    /// The firmware version, e.g. `2.0-20100601`.
+    pub fn version(&mut self) -> Result<String<MAX_LINE_LEN>, Error<E>> {
+        self.query("VERSION", |value| Some(String::from(value)))
+    }
+
+    /// The name other devices see when they look for the module.
+    pub fn name(&mut self) -> Result<String<MAX_LINE_LEN>, Error<E>> {
+        self.query("NAME", |value| Some(String::from(value)))
+    }
+
+    pub fn set_name(&mut self, name: &str) -> Result<(), Error<E>> {
+        check_argument(name, 32)?;
+        self.execute(format_args!("+NAME={}", name))
+    }
+
+    /// Sets the PIN, or passkey, asked for when pairing.
+    pub fn set_pin(&mut self, pin: &str) -> Result<(), Error<E>> {
+        check_argument(pin, 16)?;
+        self.execute(format_args!("+PSWD={}", pin))
+    }
+
+    pub fn role(&mut self) -> Result<Role, Error<E>> {
+        self.query("ROLE", |value| match value {
+            "0" => Some(Role::Slave),
+            "1" => Some(Role::Master),
+            "2" => Some(Role::SlaveLoop),
+            _ => None,
+        })
+    }
+
+    pub fn set_role(&mut self, role: Role) -> Result<(), Error<E>> {
+        let role = match role {
+            Role::Slave => 0,
+            Role::Master => 1,
+            Role::SlaveLoop => 2,
+        };
+        self.execute(format_args!("+ROLE={}", role))
+    }
+
+    /// The line settings the module uses outside of AT mode. Flow control isn't configurable
+    /// and always reads as `FlowControl::None`.
+    pub fn serial_config(&mut self) -> Result<SerialConfig, Error<E>> {
+        self.query("UART", |value| {
+            let mut fields = value.split(',');
+            let baud_rate = fields.next()?.trim().parse().ok()?;
+            let stop_bits = match fields.next()?.trim() {
+                "0" => StopBits::One,
+                "1" => StopBits::Two,
+                _ => return None,
+            };
+            let parity = match fields.next()?.trim() {
+                "0" => Parity::None,
+                "1" => Parity::Odd,
+                "2" => Parity::Even,
+                _ => return None,
+            };
+            Some(SerialConfig {
+                baud_rate,
+                parity,
+                stop_bits,
+                ..SerialConfig::default()
+            })
+        })
+    }
+
+    /// Changes the line settings the module uses outside of AT mode, which take effect once
+    /// it restarts. Flow control is ignored.
+    pub fn set_serial_config(&mut self, config: &SerialConfig) -> Result<(), Error<E>> {
+        let stop_bits = match config.stop_bits {
+            StopBits::One => 0,
+            StopBits::Two => 1,
+        };
+        let parity = match config.parity {
+            Parity::None => 0,
+            Parity::Odd => 1,
+            Parity::Even => 2,
+        };
+        self.execute(format_args!(
+            "+UART={},{},{}",
+            config.baud_rate, stop_bits, parity
+        ))
+    }
+
+    pub fn state(&mut self) -> Result<State, Error<E>> {
+        self.query("STATE", |value| {
+            Some(match value {
+                "INITIALIZED" => State::Initialized,
+                "READY" => State::Ready,
+                "PAIRABLE" => State::Pairable,
+                "PAIRED" => State::Paired,
+                "INQUIRING" => State::Inquiring,
+                "CONNECTING" => State::Connecting,
+                "CONNECTED" => State::Connected,
+                "DISCONNECTED" => State::Disconnected,
+                _ => State::Unknown,
+            })
+        })
+    }
+
+    /// Sends `AT+KEY?` and parses the `+KEY:value` line of the answer.
+    fn query<T>(
+        &mut self,
+        key: &str,
+        parse: impl FnOnce(&str) -> Option<T>,
+    ) -> Result<T, Error<E>> {
+        let mut parse = Some(parse);
+        let mut result = None;
+        self.command(format_args!("+{}?", key), |k, value| {
+            if k == key {
+                if let Some(parse) = parse.take() {
+                    result = parse(value.trim());
+                }
+            }
+        })?;
+        result.ok_or(Error::UnexpectedResponse)
+    }
+
+    /// Sends a command that is only answered with `OK`.
+    fn execute(&mut self, command: fmt::Arguments) -> Result<(), Error<E>> {
+        self.command(command, |_, _| {})
+    }
+
+    /// Sends `AT` followed by `command` and waits for the final `OK`, handing every
+    /// `+KEY:value` line in between to `on_value`.
+    fn command(
+        &mut self,
+        command: fmt::Arguments,
+        mut on_value: impl FnMut(&str, &str),
+    ) -> Result<(), Error<E>> {
+        let mut line: String<{ MAX_LINE_LEN + 2 }> = String::new();
+        write!(line, "AT{}\r\n", command).map_err(|_| Error::InvalidArgument)?;
+
+        // Whatever is left of an earlier answer doesn't belong to this one
+        while self.serial.read().is_ok() {}
+        self.line.clear();
+
+        for byte in line.bytes() {
+            nb::block!(self.serial.write(byte)).map_err(Error::Serial)?;
+        }
+
+        let start = self.clock.millis();
+        loop {
+            let byte = match self.serial.read() {
+                Ok(byte) => byte,
+                Err(nb::Error::WouldBlock) => {
+                    if self.clock.millis().wrapping_sub(start) >= self.timeout_ms {
+                        return Err(Error::Timeout);
+                    }
+                    continue;
+                }
+                Err(nb::Error::Other(e)) => return Err(Error::Serial(e)),
+            };
+
+            let line = match self.line.push(byte) {
+                Ok(Some(line)) => line,
+                Ok(None) => continue,
+                Err(_) => return Err(Error::LineTooLong),
+            };
+            match response::parse_line(line) {
+                Line::Ok => return Ok(()),
+                Line::Error(code) => return Err(Error::Module(code)),
+                Line::Value { key, value } => on_value(key, value),
+                Line::Other(_) => {}
+            }
+        }
+    }
+}
+
+/// Names and PINs can't have characters that end the command early.
+fn check_argument<E>(argument: &str, max_len: usize) -> Result<(), Error<E>> {
+    let valid = |c: char| (c.is_ascii_graphic() && c != ',') || c == ' ';
+    if argument.is_empty() || argument.len() > max_len || !argument.chars().all(valid) {
+        Err(Error::InvalidArgument)
+    } else {
+        Ok(())
+    }
+}
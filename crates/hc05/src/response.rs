@@ -0,0 +1,127 @@
+//! The lines the module answers AT commands with.
+//!
+//! Every command is answered with zero or more `+KEY:value` lines followed by either `OK` or
+//! `ERROR:(code)`, each line ending with `\r\n`.
+//!
+//! ```
+//! use hc05::response::{parse_line, Line};
+//!
+//! assert_eq!(parse_line("OK\r\n"), Line::Ok);
+//! assert_eq!(parse_line("ERROR:(1D)"), Line::Error(0x1D));
+//! assert_eq!(
+//!     parse_line("+UART:9600,0,0"),
+//!     Line::Value { key: "UART", value: "9600,0,0" }
+//! );
+//! assert_eq!(parse_line("garbage"), Line::Other("garbage"));
+//! ```
+
+use heapless::Vec;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Line<'l> {
+    /// The command succeeded.
+    Ok,
+    /// The command failed, with the module's error code.
+    Error(u8),
+    /// Part of the answer to a query, e.g. `+NAME:ferris`.
+    Value { key: &'l str, value: &'l str },
+    /// Anything else, like the noise the module sends while it starts up.
+    Other(&'l str),
+}
+
+/// Parses a line with or without its line ending.
+pub fn parse_line(line: &str) -> Line<'_> {
+    let line = line.trim();
+    if line == "OK" {
+        return Line::Ok;
+    }
+
+    // Some firmware versions put a space after the colon
+    if let Some(code) = line.strip_prefix("ERROR:") {
+        let code = code.trim().trim_start_matches('(').trim_end_matches(')');
+        if let Ok(code) = u8::from_str_radix(code, 16) {
+            return Line::Error(code);
+        }
+    }
+
+    if let Some((key, value)) = line.strip_prefix('+').and_then(|l| l.split_once(':')) {
+        return Line::Value { key, value };
+    }
+
+    Line::Other(line)
+}
+
+/// Puts received bytes together into lines of up to `N` bytes.
+///
+/// ```
+/// use hc05::response::LineBuffer;
+///
+/// let mut buffer: LineBuffer<16> = LineBuffer::new();
+/// let mut lines = Vec::new();
+/// for byte in b"+STATE:PAIRED\r\nOK\r\n" {
+///     if let Some(line) = buffer.push(*byte).unwrap() {
+///         lines.push(line.to_owned());
+///     }
+/// }
+/// assert_eq!(lines, ["+STATE:PAIRED", "OK"]);
+/// ```
+pub struct LineBuffer<const N: usize> {
+    bytes: Vec<u8, N>,
+    /// The line being received didn't fit, and is dropped up to its end.
+    overflowed: bool,
+    /// `bytes` holds a line that was returned, and is cleared with the next byte.
+    complete: bool,
+}
+
+/// A line didn't fit the `LineBuffer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineTooLong;
+
+impl<const N: usize> LineBuffer<N> {
+    pub const fn new() -> Self {
+        LineBuffer {
+            bytes: Vec::new(),
+            overflowed: false,
+            complete: false,
+        }
+    }
+
+    /// Adds a byte, returning the line once it is complete. Empty lines are skipped, and so
+    /// are lines that aren't valid UTF-8.
+    pub fn push(&mut self, byte: u8) -> Result<Option<&str>, LineTooLong> {
+        if self.complete {
+            self.clear();
+        }
+
+        match byte {
+            b'\r' => Ok(None),
+            b'\n' => {
+                self.complete = true;
+                if self.overflowed {
+                    return Err(LineTooLong);
+                }
+                let line = core::str::from_utf8(&self.bytes).ok();
+                Ok(line.filter(|line| !line.is_empty()))
+            }
+            _ => {
+                if self.bytes.push(byte).is_err() {
+                    self.overflowed = true;
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    /// Forgets the line received so far.
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+        self.overflowed = false;
+        self.complete = false;
+    }
+}
+
+impl<const N: usize> Default for LineBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
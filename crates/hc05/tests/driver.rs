@@ -0,0 +1,345 @@
+//! The driver against a fake module that plays back scripted answers.
+
+use std::collections::VecDeque;
+
+use embedded_hal::{digital::v2::OutputPin, serial};
+use hc05::{Error, Hc05, Millis, Role, State, DEFAULT_TIMEOUT_MS};
+use serial_config::{Parity, SerialConfig, StopBits};
+
+/// Answers every command with the next reply in the script, after checking it.
+struct FakeModule {
+    script: VecDeque<(&'static str, &'static str)>,
+    command: Vec<u8>,
+    reply: VecDeque<u8>,
+    /// Reads fail instead of returning a byte.
+    broken: bool,
+}
+
+impl FakeModule {
+    fn new(script: &[(&'static str, &'static str)]) -> FakeModule {
+        FakeModule {
+            script: script.iter().copied().collect(),
+            command: Vec::new(),
+            reply: VecDeque::new(),
+            broken: false,
+        }
+    }
+}
+
+impl serial::Write<u8> for FakeModule {
+    type Error = ();
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), ()> {
+        self.command.push(byte);
+        if self.command.ends_with(b"\r\n") {
+            let (command, reply) = self.script.pop_front().expect("unexpected command");
+            assert_eq!(
+                String::from_utf8_lossy(&self.command),
+                format!("{}\r\n", command)
+            );
+            self.command.clear();
+            self.reply.extend(reply.bytes());
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), ()> {
+        Ok(())
+    }
+}
+
+impl serial::Read<u8> for FakeModule {
+    type Error = ();
+
+    fn read(&mut self) -> nb::Result<u8, ()> {
+        if self.broken {
+            return Err(nb::Error::Other(()));
+        }
+        self.reply.pop_front().ok_or(nb::Error::WouldBlock)
+    }
+}
+
+/// A clock that moves a millisecond every time it is read.
+struct FakeClock(u32);
+
+impl Millis for FakeClock {
+    fn millis(&mut self) -> u32 {
+        self.0 = self.0.wrapping_add(1);
+        self.0
+    }
+}
+
+/// The KEY pin, which can be made to fail.
+struct FakePin {
+    high: bool,
+    broken: bool,
+}
+
+impl OutputPin for FakePin {
+    type Error = ();
+
+    fn set_low(&mut self) -> Result<(), ()> {
+        if self.broken {
+            return Err(());
+        }
+        self.high = false;
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), ()> {
+        if self.broken {
+            return Err(());
+        }
+        self.high = true;
+        Ok(())
+    }
+}
+
+fn hc05(script: &[(&'static str, &'static str)]) -> Hc05<FakeModule, FakeClock> {
+    Hc05::new(FakeModule::new(script), FakeClock(0))
+}
+
+/// Checks that the whole script was used up.
+fn done(hc05: Hc05<FakeModule, FakeClock>) {
+    let (module, _) = hc05.release();
+    assert!(module.script.is_empty(), "left over: {:?}", module.script);
+}
+
+#[test]
+fn check() {
+    let mut hc05 = hc05(&[("AT", "OK\r\n")]);
+    hc05.check().unwrap();
+    done(hc05);
+}
+
+#[test]
+fn version() {
+    let mut hc05 = hc05(&[("AT+VERSION?", "+VERSION:2.0-20100601\r\nOK\r\n")]);
+    assert_eq!(hc05.version().unwrap().as_str(), "2.0-20100601");
+    done(hc05);
+}
+
+#[test]
+fn name() {
+    let mut hc05 = hc05(&[
+        ("AT+NAME=ferris", "OK\r\n"),
+        ("AT+NAME?", "+NAME:ferris\r\nOK\r\n"),
+    ]);
+    hc05.set_name("ferris").unwrap();
+    assert_eq!(hc05.name().unwrap().as_str(), "ferris");
+    done(hc05);
+}
+
+#[test]
+fn value_after_a_space() {
+    // Some firmware versions put a space after the colon
+    let mut hc05 = hc05(&[("AT+NAME?", "+NAME: H-C-2010-06-01\r\nOK\r\n")]);
+    assert_eq!(hc05.name().unwrap().as_str(), "H-C-2010-06-01");
+    done(hc05);
+}
+
+#[test]
+fn pin() {
+    let mut hc05 = hc05(&[("AT+PSWD=4321", "OK\r\n")]);
+    hc05.set_pin("4321").unwrap();
+    done(hc05);
+}
+
+#[test]
+fn role() {
+    let mut hc05 = hc05(&[
+        ("AT+ROLE?", "+ROLE:0\r\nOK\r\n"),
+        ("AT+ROLE=1", "OK\r\n"),
+        ("AT+ROLE?", "+ROLE:1\r\nOK\r\n"),
+        ("AT+ROLE=2", "OK\r\n"),
+        ("AT+ROLE?", "+ROLE:2\r\nOK\r\n"),
+    ]);
+    assert_eq!(hc05.role().unwrap(), Role::Slave);
+    hc05.set_role(Role::Master).unwrap();
+    assert_eq!(hc05.role().unwrap(), Role::Master);
+    hc05.set_role(Role::SlaveLoop).unwrap();
+    assert_eq!(hc05.role().unwrap(), Role::SlaveLoop);
+    done(hc05);
+}
+
+#[test]
+fn serial_config() {
+    let config = SerialConfig {
+        baud_rate: 9_600,
+        parity: Parity::Even,
+        stop_bits: StopBits::Two,
+        ..SerialConfig::default()
+    };
+    let mut hc05 = hc05(&[
+        ("AT+UART=9600,1,2", "OK\r\n"),
+        ("AT+UART?", "+UART:9600,1,2\r\nOK\r\n"),
+        ("AT+UART=115200,0,0", "OK\r\n"),
+        ("AT+UART?", "+UART:115200,0,0\r\nOK\r\n"),
+    ]);
+    hc05.set_serial_config(&config).unwrap();
+    assert_eq!(hc05.serial_config().unwrap(), config);
+    hc05.set_serial_config(&SerialConfig::default()).unwrap();
+    assert_eq!(hc05.serial_config().unwrap(), SerialConfig::default());
+    done(hc05);
+}
+
+#[test]
+fn state() {
+    let mut hc05 = hc05(&[
+        ("AT+STATE?", "+STATE:INITIALIZED\r\nOK\r\n"),
+        ("AT+STATE?", "+STATE:CONNECTED\r\nOK\r\n"),
+        ("AT+STATE?", "+STATE:SNIFFING\r\nOK\r\n"),
+    ]);
+    assert_eq!(hc05.state().unwrap(), State::Initialized);
+    assert_eq!(hc05.state().unwrap(), State::Connected);
+    assert_eq!(hc05.state().unwrap(), State::Unknown);
+    done(hc05);
+}
+
+#[test]
+fn reset_and_restore_defaults() {
+    let mut hc05 = hc05(&[("AT+ORGL", "OK\r\n"), ("AT+RESET", "OK\r\n")]);
+    hc05.restore_defaults().unwrap();
+    hc05.reset().unwrap();
+    done(hc05);
+}
+
+#[test]
+fn error_response() {
+    let mut hc05 = hc05(&[("AT+NAME=x", "ERROR:(1D)\r\n")]);
+    assert_eq!(hc05.set_name("x"), Err(Error::Module(0x1D)));
+    done(hc05);
+}
+
+#[test]
+fn error_response_to_a_query() {
+    let mut hc05 = hc05(&[("AT+UART?", "ERROR:(0)\r\n")]);
+    assert_eq!(hc05.serial_config(), Err(Error::Module(0)));
+    done(hc05);
+}
+
+#[test]
+fn timeout() {
+    let mut hc05 = hc05(&[("AT+STATE?", "")]);
+    assert_eq!(hc05.state(), Err(Error::Timeout));
+    let (_, clock) = hc05.release();
+    // Gave up once the timeout was over, not before
+    assert!(clock.0 >= DEFAULT_TIMEOUT_MS, "{}", clock.0);
+    assert!(clock.0 <= DEFAULT_TIMEOUT_MS + 2, "{}", clock.0);
+}
+
+#[test]
+fn timeout_without_the_final_ok() {
+    let mut hc05 = hc05(&[("AT+ROLE?", "+ROLE:0\r\n")]);
+    assert_eq!(hc05.role(), Err(Error::Timeout));
+}
+
+#[test]
+fn timeout_across_the_clock_wrapping() {
+    let mut hc05 = Hc05::new(FakeModule::new(&[("AT", "")]), FakeClock(u32::MAX - 10));
+    hc05.set_timeout_ms(50);
+    assert_eq!(hc05.check(), Err(Error::Timeout));
+    let (_, clock) = hc05.release();
+    assert!((39..=42).contains(&clock.0), "{}", clock.0);
+}
+
+#[test]
+fn unexpected_response() {
+    let mut hc05 = hc05(&[
+        ("AT+ROLE?", "OK\r\n"),
+        ("AT+ROLE?", "+ROLE:7\r\nOK\r\n"),
+        ("AT+UART?", "+UART:fast\r\nOK\r\n"),
+    ]);
+    assert_eq!(hc05.role(), Err(Error::UnexpectedResponse));
+    assert_eq!(hc05.role(), Err(Error::UnexpectedResponse));
+    assert_eq!(hc05.serial_config(), Err(Error::UnexpectedResponse));
+    done(hc05);
+}
+
+#[test]
+fn other_lines_are_skipped() {
+    // The answer to another key and noise from starting up don't get in the way
+    let mut hc05 = hc05(&[("AT+ROLE?", "\0 junk\r\n+STATE:READY\r\n+ROLE:1\r\nOK\r\n")]);
+    assert_eq!(hc05.role().unwrap(), Role::Master);
+    done(hc05);
+}
+
+#[test]
+fn leftovers_are_not_the_answer() {
+    let mut hc05 = hc05(&[("AT+ROLE?", "+ROLE:2\r\nOK\r\n")]);
+    let (mut module, clock) = hc05.release();
+    module.reply.extend(b"OK\r\n+ROLE:0\r\n");
+    hc05 = Hc05::new(module, clock);
+    assert_eq!(hc05.role().unwrap(), Role::SlaveLoop);
+    done(hc05);
+}
+
+#[test]
+fn line_too_long() {
+    let long = format!("+NAME:{}\r\nOK\r\n", "x".repeat(hc05::MAX_LINE_LEN));
+    let mut hc05 = hc05(&[("AT+NAME?", Box::leak(long.into_boxed_str()))]);
+    assert_eq!(hc05.name(), Err(Error::LineTooLong));
+}
+
+#[test]
+fn serial_error() {
+    let mut module = FakeModule::new(&[("AT", "OK\r\n")]);
+    module.broken = true;
+    let mut hc05 = Hc05::new(module, FakeClock(0));
+    assert_eq!(hc05.check(), Err(Error::Serial(())));
+}
+
+#[test]
+fn invalid_arguments() {
+    // None of these are sent
+    let mut hc05 = hc05(&[]);
+    assert_eq!(hc05.set_name(""), Err(Error::InvalidArgument));
+    // Commas would end the name early
+    assert_eq!(hc05.set_name("a,b"), Err(Error::InvalidArgument));
+    assert_eq!(
+        hc05.set_name("line\r\nAT+ORGL"),
+        Err(Error::InvalidArgument)
+    );
+    assert_eq!(hc05.set_name("ferris°"), Err(Error::InvalidArgument));
+    assert_eq!(hc05.set_name(&"x".repeat(33)), Err(Error::InvalidArgument));
+    assert_eq!(hc05.set_pin(&"1".repeat(17)), Err(Error::InvalidArgument));
+    done(hc05);
+}
+
+#[test]
+fn longest_arguments() {
+    let mut hc05 = hc05(&[
+        ("AT+NAME=ferris the crab xxxxxxxxx", "OK\r\n"),
+        ("AT+PSWD=1234567890123456", "OK\r\n"),
+    ]);
+    hc05.set_name("ferris the crab xxxxxxxxx").unwrap();
+    hc05.set_pin("1234567890123456").unwrap();
+    done(hc05);
+}
+
+#[test]
+fn at_mode_with_the_key_pin() {
+    let mut key = FakePin {
+        high: false,
+        broken: false,
+    };
+    let mut hc05 = hc05(&[("AT", "OK\r\n"), ("AT+RESET", "OK\r\n")]);
+    hc05.enter_at_mode(&mut key).unwrap();
+    assert!(key.high);
+    hc05.leave_at_mode(&mut key).unwrap();
+    assert!(!key.high);
+    done(hc05);
+}
+
+#[test]
+fn key_pin_error() {
+    let mut key = FakePin {
+        high: false,
+        broken: true,
+    };
+    // Nothing is sent if the pin can't be set
+    let mut hc05 = hc05(&[]);
+    assert_eq!(hc05.enter_at_mode(&mut key), Err(Error::Pin));
+    assert_eq!(hc05.leave_at_mode(&mut key), Err(Error::Pin));
+    done(hc05);
+}
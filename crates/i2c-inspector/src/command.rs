@@ -0,0 +1,155 @@
+//! Parsing of the commands typed into the inspector.
+
+use core::fmt;
+
+/// Most bytes a single `read` or `write` command handles.
+pub const MAX_BYTES: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// `help`
+    Help,
+    /// `scan`
+    Scan,
+    /// `read <address> <register> [count]`
+    Read {
+        address: u8,
+        register: u8,
+        count: u8,
+    },
+    /// `write <address> <register> <byte>...`
+    Write {
+        address: u8,
+        register: u8,
+        data: [u8; MAX_BYTES],
+        len: usize,
+    },
+    /// `dump <address> [first] [last]`
+    Dump { address: u8, first: u8, last: u8 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// The line was blank.
+    Empty,
+    UnknownCommand,
+    MissingArgument,
+    TooManyArguments,
+    /// An argument is not a number between `0` and `255`.
+    InvalidNumber,
+    /// Not a 7-bit address.
+    InvalidAddress,
+    InvalidCount,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ParseError::Empty => "empty line",
+            ParseError::UnknownCommand => "unknown command, try `help`",
+            ParseError::MissingArgument => "missing argument",
+            ParseError::TooManyArguments => "too many arguments",
+            ParseError::InvalidNumber => "expected a number between 0 and 255",
+            ParseError::InvalidAddress => "expected a 7-bit address, 0x00 to 0x7F",
+            ParseError::InvalidCount => "can only read between 1 and 16 bytes at once",
+        })
+    }
+}
+
+impl Command {
+    /// Parses a line of input. Numbers can be given in decimal, hexadecimal (`0x1E`) or
+    /// binary (`0b0011110`).
+    pub fn parse(line: &str) -> Result<Command, ParseError> {
+        let mut words = line.split_whitespace();
+        let name = words.next().ok_or(ParseError::Empty)?;
+
+        let command = match name {
+            "help" | "?" => Command::Help,
+            "scan" => Command::Scan,
+            "read" | "r" => {
+                let address = address(words.next())?;
+                let register = number(words.next())?;
+                let count = match words.next() {
+                    Some(count) => number(Some(count))?,
+                    None => 1,
+                };
+                if count == 0 || count as usize > MAX_BYTES {
+                    return Err(ParseError::InvalidCount);
+                }
+                Command::Read {
+                    address,
+                    register,
+                    count,
+                }
+            }
+            "write" | "w" => {
+                let address = address(words.next())?;
+                let register = number(words.next())?;
+                let mut data = [0; MAX_BYTES];
+                let mut len = 0;
+                for word in &mut words {
+                    if len == MAX_BYTES {
+                        return Err(ParseError::TooManyArguments);
+                    }
+                    data[len] = number(Some(word))?;
+                    len += 1;
+                }
+                if len == 0 {
+                    return Err(ParseError::MissingArgument);
+                }
+                Command::Write {
+                    address,
+                    register,
+                    data,
+                    len,
+                }
+            }
+            "dump" | "d" => {
+                let address = address(words.next())?;
+                let first = match words.next() {
+                    Some(first) => number(Some(first))?,
+                    None => 0x00,
+                };
+                let last = match words.next() {
+                    Some(last) => number(Some(last))?,
+                    None => 0xFF,
+                };
+                Command::Dump {
+                    address,
+                    first,
+                    last,
+                }
+            }
+            _ => return Err(ParseError::UnknownCommand),
+        };
+
+        if words.next().is_some() {
+            return Err(ParseError::TooManyArguments);
+        }
+        Ok(command)
+    }
+}
+
+fn number(word: Option<&str>) -> Result<u8, ParseError> {
+    let word = word.ok_or(ParseError::MissingArgument)?;
+    let (digits, radix) = if let Some(hex) = word.strip_prefix("0x") {
+        (hex, 16)
+    } else if let Some(bin) = word.strip_prefix("0b") {
+        (bin, 2)
+    } else {
+        (word, 10)
+    };
+    // `from_str_radix` would also take a sign, as in `0x+1E`
+    if digits.starts_with('+') {
+        return Err(ParseError::InvalidNumber);
+    }
+    u8::from_str_radix(digits, radix).map_err(|_| ParseError::InvalidNumber)
+}
+
+fn address(word: Option<&str>) -> Result<u8, ParseError> {
+    let address = number(word)?;
+    if address > 0x7F {
+        return Err(ParseError::InvalidAddress);
+    }
+    Ok(address)
+}
@@ -0,0 +1,266 @@
+//! An I2C bus scanner and register inspector that is driven over a serial port.
+//!
+//! Bringing up a new sensor usually starts with the same questions: is it connected at all,
+//! at which address does it answer, and what do its registers contain? [`run`] answers them
+//! interactively. It scans the bus once at start up and then reads commands, one per line:
+//!
+//! ```text
+//! scan                              probe every 7-bit address
+//! read  <address> <register> [n]    read n (default 1) registers, one at a time
+//! write <address> <register> <b>... write bytes starting at a register
+//! dump  <address> [first] [last]    hex dump of a register range
+//! ```
+//!
+//! Everything is written against the blocking I2C and the serial traits of `embedded-hal`,
+//! so both the micro:bit and the F3 Discovery examples share this crate.
+
+#![no_std]
+
+use core::fmt::{self, Write as _};
+
+use embedded_hal::blocking::i2c::{Read, Write, WriteRead};
+use embedded_hal::serial;
+
+pub mod command;
+
+pub use command::{Command, ParseError, MAX_BYTES};
+
+/// Longest command line accepted.
+pub const MAX_LINE_LEN: usize = 64;
+
+const HELP: &str = "\
+commands (numbers in decimal, 0x.. hex or 0b.. binary):\r
+  scan                               probe every 7-bit address\r
+  read  <address> <register> [count] read up to 16 registers\r
+  write <address> <register> <byte>... write up to 16 bytes\r
+  dump  <address> [first] [last]     hex dump of a register range\r
+";
+
+/// Runs commands against an I2C bus.
+pub struct Inspector<I> {
+    i2c: I,
+}
+
+impl<I, E> Inspector<I>
+where
+    I: Read<Error = E> + Write<Error = E> + WriteRead<Error = E>,
+    E: fmt::Debug,
+{
+    pub fn new(i2c: I) -> Inspector<I> {
+        Inspector { i2c }
+    }
+
+    /// Gives the bus back.
+    pub fn release(self) -> I {
+        self.i2c
+    }
+
+    /// Whether a device acknowledges `address`.
+    ///
+    /// Probes with a one byte read, which every device has to answer and which, unlike a
+    /// write, can't change its state.
+    pub fn probe(&mut self, address: u8) -> bool {
+        self.i2c.read(address, &mut [0]).is_ok()
+    }
+
+    /// Probes all addresses that aren't reserved by the I2C specification and prints the
+    /// answers in the same grid `i2cdetect` uses.
+    pub fn scan<W: fmt::Write>(&mut self, out: &mut W) -> fmt::Result {
+        out.write_str("     0  1  2  3  4  5  6  7  8  9  a  b  c  d  e  f\r\n")?;
+        let mut found = 0;
+        for row in (0..0x80).step_by(16) {
+            write!(out, "{:02x}:", row)?;
+            for address in row..row + 16 {
+                if !(0x08..=0x77).contains(&address) {
+                    out.write_str("   ")?;
+                } else if self.probe(address) {
+                    write!(out, " {:02x}", address)?;
+                    found += 1;
+                } else {
+                    out.write_str(" --")?;
+                }
+            }
+            out.write_str("\r\n")?;
+        }
+        write!(out, "{} device(s) found\r\n", found)
+    }
+
+    /// Runs a command, printing its results or the bus error it ran into.
+    pub fn execute<W: fmt::Write>(&mut self, command: &Command, out: &mut W) -> fmt::Result {
+        match *command {
+            Command::Help => out.write_str(HELP),
+            Command::Scan => self.scan(out),
+            Command::Read {
+                address,
+                register,
+                count,
+            } => {
+                for offset in 0..count {
+                    let register = register.wrapping_add(offset);
+                    match self.read_register(address, register) {
+                        Ok(value) => write!(
+                            out,
+                            "0x{:02x}[0x{:02x}] = 0x{:02x} 0b{:08b}\r\n",
+                            address, register, value, value
+                        )?,
+                        Err(e) => return error(out, address, e),
+                    }
+                }
+                Ok(())
+            }
+            Command::Write {
+                address,
+                register,
+                ref data,
+                len,
+            } => {
+                let mut buffer = [0; MAX_BYTES + 1];
+                buffer[0] = register;
+                buffer[1..=len].copy_from_slice(&data[..len]);
+                match self.i2c.write(address, &buffer[..=len]) {
+                    Ok(()) => write!(out, "wrote {} byte(s)\r\n", len),
+                    Err(e) => error(out, address, e),
+                }
+            }
+            Command::Dump {
+                address,
+                first,
+                last,
+            } => self.dump(out, address, first, last),
+        }
+    }
+
+    fn dump<W: fmt::Write>(
+        &mut self,
+        out: &mut W,
+        address: u8,
+        first: u8,
+        last: u8,
+    ) -> fmt::Result {
+        if first > last {
+            return out.write_str("nothing to dump, first > last\r\n");
+        }
+        out.write_str("     0  1  2  3  4  5  6  7  8  9  a  b  c  d  e  f\r\n")?;
+        let mut failed = None;
+        for row in (first & 0xF0..=last).step_by(16) {
+            write!(out, "{:02x}:", row)?;
+            for register in row..=row.saturating_add(15) {
+                if register < first || register > last {
+                    out.write_str("   ")?;
+                    continue;
+                }
+                match self.read_register(address, register) {
+                    Ok(value) => write!(out, " {:02x}", value)?,
+                    Err(e) => {
+                        out.write_str(" XX")?;
+                        failed.get_or_insert(e);
+                    }
+                }
+            }
+            out.write_str("\r\n")?;
+        }
+        match failed {
+            Some(e) => error(out, address, e),
+            None => Ok(()),
+        }
+    }
+
+    fn read_register(&mut self, address: u8, register: u8) -> Result<u8, E> {
+        let mut value = [0];
+        self.i2c.write_read(address, &[register], &mut value)?;
+        Ok(value[0])
+    }
+}
+
+fn error<W: fmt::Write, E: fmt::Debug>(out: &mut W, address: u8, e: E) -> fmt::Result {
+    write!(out, "error talking to 0x{:02x}: {:?}\r\n", address, e)
+}
+
+/// Blocking `fmt::Write` on top of a non-blocking serial port.
+pub struct Console<S> {
+    serial: S,
+}
+
+impl<S> Console<S> {
+    pub fn new(serial: S) -> Console<S> {
+        Console { serial }
+    }
+
+    pub fn release(self) -> S {
+        self.serial
+    }
+}
+
+impl<S: serial::Write<u8>> fmt::Write for Console<S> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            nb::block!(self.serial.write(byte)).map_err(|_| fmt::Error)?;
+        }
+        nb::block!(self.serial.flush()).map_err(|_| fmt::Error)
+    }
+}
+
+impl<S: serial::Read<u8>> Console<S> {
+    /// Reads a line into `buffer`, echoing what is typed and handling backspace.
+    ///
+    /// Characters beyond the size of `buffer` are dropped.
+    pub fn read_line<'b>(&mut self, buffer: &'b mut [u8]) -> &'b str
+    where
+        S: serial::Write<u8>,
+    {
+        let mut len = 0;
+        loop {
+            // Framing or overrun errors only cost us a character
+            let byte = match nb::block!(self.serial.read()) {
+                Ok(byte) => byte,
+                Err(_) => continue,
+            };
+            match byte {
+                b'\r' | b'\n' => {
+                    self.write_str("\r\n").ok();
+                    break;
+                }
+                // Backspace and delete
+                0x08 | 0x7F if len > 0 => {
+                    len -= 1;
+                    self.write_str("\x08 \x08").ok();
+                }
+                b' '..=b'~' if len < buffer.len() => {
+                    buffer[len] = byte;
+                    len += 1;
+                    self.write_char(byte as char).ok();
+                }
+                _ => {}
+            }
+        }
+        // Only printable ASCII made it into the buffer
+        core::str::from_utf8(&buffer[..len]).unwrap_or("")
+    }
+}
+
+/// Scans the bus, then runs the commands typed into `serial` forever.
+pub fn run<I, E, S>(i2c: I, serial: S) -> !
+where
+    I: Read<Error = E> + Write<Error = E> + WriteRead<Error = E>,
+    E: fmt::Debug,
+    S: serial::Read<u8> + serial::Write<u8>,
+{
+    let mut inspector = Inspector::new(i2c);
+    let mut console = Console::new(serial);
+
+    console
+        .write_str("\r\nI2C inspector, type `help` for commands\r\n")
+        .ok();
+    inspector.scan(&mut console).ok();
+
+    let mut buffer = [0; MAX_LINE_LEN];
+    loop {
+        console.write_str("> ").ok();
+        let line = console.read_line(&mut buffer);
+        match Command::parse(line) {
+            Ok(command) => inspector.execute(&command, &mut console).ok(),
+            Err(ParseError::Empty) => None,
+            Err(e) => write!(console, "error: {}\r\n", e).ok(),
+        };
+    }
+}
@@ -0,0 +1,205 @@
+use i2c_inspector::{Command, ParseError, MAX_BYTES};
+
+fn read(address: u8, register: u8, count: u8) -> Command {
+    Command::Read {
+        address,
+        register,
+        count,
+    }
+}
+
+fn write(address: u8, register: u8, bytes: &[u8]) -> Command {
+    let mut data = [0; MAX_BYTES];
+    data[..bytes.len()].copy_from_slice(bytes);
+    Command::Write {
+        address,
+        register,
+        data,
+        len: bytes.len(),
+    }
+}
+
+#[test]
+fn help_and_scan() {
+    assert_eq!(Command::parse("help"), Ok(Command::Help));
+    assert_eq!(Command::parse("?"), Ok(Command::Help));
+    assert_eq!(Command::parse("scan"), Ok(Command::Scan));
+    assert_eq!(Command::parse("  scan \t"), Ok(Command::Scan));
+}
+
+#[test]
+fn read_command() {
+    assert_eq!(Command::parse("read 0x19 0x0f"), Ok(read(0x19, 0x0F, 1)));
+    assert_eq!(Command::parse("r 0x19 0x28 6"), Ok(read(0x19, 0x28, 6)));
+    assert_eq!(
+        Command::parse("read 0x1e 0x4f 16"),
+        Ok(read(0x1E, 0x4F, 16))
+    );
+}
+
+#[test]
+fn write_command() {
+    assert_eq!(
+        Command::parse("write 0x19 0x20 0x57"),
+        Ok(write(0x19, 0x20, &[0x57]))
+    );
+    assert_eq!(
+        Command::parse("w 0x1e 0x60 0 1 2"),
+        Ok(write(0x1E, 0x60, &[0, 1, 2]))
+    );
+    let all: Vec<String> = (0..MAX_BYTES).map(|b| b.to_string()).collect();
+    let expected: Vec<u8> = (0..MAX_BYTES as u8).collect();
+    assert_eq!(
+        Command::parse(&format!("write 0x19 0x20 {}", all.join(" "))),
+        Ok(write(0x19, 0x20, &expected))
+    );
+}
+
+#[test]
+fn dump_command() {
+    let dump = |address, first, last| Command::Dump {
+        address,
+        first,
+        last,
+    };
+    assert_eq!(Command::parse("dump 0x19"), Ok(dump(0x19, 0x00, 0xFF)));
+    assert_eq!(Command::parse("d 0x19 0x20"), Ok(dump(0x19, 0x20, 0xFF)));
+    assert_eq!(
+        Command::parse("dump 0x19 0x20 0x3f"),
+        Ok(dump(0x19, 0x20, 0x3F))
+    );
+}
+
+#[test]
+fn number_formats() {
+    for line in [
+        "read 30 15",
+        "read 0x1e 0x0f",
+        "read 0x1E 0x0F",
+        "read 0b0011110 0b1111",
+        "read 0b11110 15",
+    ]
+    .iter()
+    {
+        assert_eq!(Command::parse(line), Ok(read(0x1E, 0x0F, 1)), "{}", line);
+    }
+    assert_eq!(Command::parse("read 0 255"), Ok(read(0, 0xFF, 1)));
+    assert_eq!(Command::parse("read 0x7f 0xff"), Ok(read(0x7F, 0xFF, 1)));
+}
+
+#[test]
+fn invalid_numbers() {
+    for word in [
+        "256",
+        "0x100",
+        "0b100000000",
+        "-1",
+        "+1",
+        "0x+1",
+        "0x",
+        "0b",
+        "0b2",
+        "0xg",
+        "1e",
+        "x1",
+        "0o17",
+    ]
+    .iter()
+    {
+        assert_eq!(
+            Command::parse(&format!("read 0x19 {}", word)),
+            Err(ParseError::InvalidNumber),
+            "{}",
+            word
+        );
+    }
+}
+
+#[test]
+fn out_of_range_addresses() {
+    for line in [
+        "read 0x80 0",
+        "write 128 0 0",
+        "dump 0xff",
+        "read 0b10000000 0",
+    ]
+    .iter()
+    {
+        assert_eq!(
+            Command::parse(line),
+            Err(ParseError::InvalidAddress),
+            "{}",
+            line
+        );
+    }
+    // Not even a byte
+    assert_eq!(
+        Command::parse("read 0x180 0"),
+        Err(ParseError::InvalidNumber)
+    );
+}
+
+#[test]
+fn out_of_range_lengths() {
+    assert_eq!(
+        Command::parse("read 0x19 0x28 0"),
+        Err(ParseError::InvalidCount)
+    );
+    assert_eq!(
+        Command::parse("read 0x19 0x28 17"),
+        Err(ParseError::InvalidCount)
+    );
+    assert_eq!(
+        Command::parse("read 0x19 0x28 0x100"),
+        Err(ParseError::InvalidNumber)
+    );
+
+    let too_many: Vec<&str> = vec!["1"; MAX_BYTES + 1];
+    assert_eq!(
+        Command::parse(&format!("write 0x19 0x20 {}", too_many.join(" "))),
+        Err(ParseError::TooManyArguments)
+    );
+}
+
+#[test]
+fn missing_arguments() {
+    for line in ["read", "read 0x19", "write 0x19", "write 0x19 0x20", "dump"].iter() {
+        assert_eq!(
+            Command::parse(line),
+            Err(ParseError::MissingArgument),
+            "{}",
+            line
+        );
+    }
+}
+
+#[test]
+fn extra_arguments() {
+    for line in [
+        "help me",
+        "scan 0x19",
+        "read 0x19 0x28 6 7",
+        "dump 0x19 0 16 32",
+    ]
+    .iter()
+    {
+        assert_eq!(
+            Command::parse(line),
+            Err(ParseError::TooManyArguments),
+            "{}",
+            line
+        );
+    }
+}
+
+#[test]
+fn unknown_and_empty_lines() {
+    assert_eq!(Command::parse(""), Err(ParseError::Empty));
+    assert_eq!(Command::parse("   \t "), Err(ParseError::Empty));
+    assert_eq!(Command::parse("probe"), Err(ParseError::UnknownCommand));
+    // Commands are case sensitive
+    assert_eq!(
+        Command::parse("READ 0x19 0"),
+        Err(ParseError::UnknownCommand)
+    );
+}
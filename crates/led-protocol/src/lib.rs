@@ -0,0 +1,293 @@
+//! A line based protocol to control the F3's compass LEDs and read its sensors over a serial
+//! port, or over Bluetooth.
+//!
+//! Every request is a line of text, and the board answers every request with exactly one line,
+//! so a client knows its request arrived and was carried out. Being text, the protocol can also
+//! be typed into a terminal:
+//!
+//! ``` text
+//! > ping                  < pong
+//! > led 0 on              < ok            (LEDs are numbered clockwise from North, 0 to 7)
+//! > leds 0x55             < ok            (bit i is LED i)
+//! > roulette 50 3         < ok            (3 laps, 50 ms per LED; 0 laps runs until `stop`)
+//! > stop                  < ok
+//! > accel                 < accel -12 3 1010
+//! > mag                   < mag 210 -45 -330
+//! > led 9 on              < err argument
+//! ```
+//!
+//! Both the firmware and the host side use the types below:
+//!
+//! ```
+//! use led_protocol::{Request, Response};
+//!
+//! let request = Request::Led { index: 2, on: true };
+//! let line = request.to_string();
+//! assert_eq!(line, "led 2 on");
+//! assert_eq!(Request::parse(&line), Ok(request));
+//!
+//! let response = Response::Accel([-12, 3, 1010]);
+//! assert_eq!(Response::parse(&response.to_string()), Ok(response));
+//! ```
+
+#![no_std]
+
+use core::convert::TryFrom;
+use core::fmt;
+use core::str::SplitWhitespace;
+
+use heapless::Vec;
+
+/// Number of compass LEDs.
+pub const LEDS: u8 = 8;
+
+/// Longest line either side sends, without the line ending.
+pub const MAX_LINE_LEN: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Request {
+    /// Checks that the board is there.
+    Ping,
+    /// Turns one LED on or off.
+    Led { index: u8, on: bool },
+    /// Sets all the LEDs at once, bit `i` of the mask being LED `i`.
+    Leds(u8),
+    /// Lights one LED after the other, clockwise, `period_ms` each. `laps` 0 keeps going until
+    /// `Stop`.
+    Roulette { period_ms: u16, laps: u8 },
+    /// Stops the roulette and turns all the LEDs off.
+    Stop,
+    /// Reads the accelerometer.
+    Accel,
+    /// Reads the magnetometer.
+    Mag,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Response {
+    Ok,
+    Pong,
+    /// Raw accelerometer reading, X, Y and Z.
+    Accel([i16; 3]),
+    /// Raw magnetometer reading, X, Y and Z.
+    Mag([i16; 3]),
+    Error(Error),
+}
+
+/// Why a request failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The request isn't one of the above.
+    UnknownRequest,
+    /// An argument is missing, out of range or there are too many of them.
+    Argument,
+    /// The request didn't fit into `MAX_LINE_LEN` bytes.
+    TooLong,
+    /// The sensor couldn't be read.
+    Sensor,
+}
+
+impl Error {
+    fn as_str(self) -> &'static str {
+        match self {
+            Error::UnknownRequest => "unknown",
+            Error::Argument => "argument",
+            Error::TooLong => "too-long",
+            Error::Sensor => "sensor",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Request {
+    /// Parses a request line, with or without its line ending.
+    pub fn parse(line: &str) -> Result<Request, Error> {
+        let mut words = line.split_whitespace();
+        let request = match words.next() {
+            Some("ping") => Request::Ping,
+            Some("led") => {
+                let index = number(&mut words)?;
+                if index >= LEDS {
+                    return Err(Error::Argument);
+                }
+                let on = match words.next() {
+                    Some("on") => true,
+                    Some("off") => false,
+                    _ => return Err(Error::Argument),
+                };
+                Request::Led { index, on }
+            }
+            Some("leds") => Request::Leds(number(&mut words)?),
+            Some("roulette") => Request::Roulette {
+                period_ms: number(&mut words)?,
+                laps: number(&mut words)?,
+            },
+            Some("stop") => Request::Stop,
+            Some("accel") => Request::Accel,
+            Some("mag") => Request::Mag,
+            _ => return Err(Error::UnknownRequest),
+        };
+
+        if words.next().is_some() {
+            return Err(Error::Argument);
+        }
+        Ok(request)
+    }
+}
+
+impl fmt::Display for Request {
+    /// Formats the request as a line, without the line ending.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Request::Ping => f.write_str("ping"),
+            Request::Led { index, on } => {
+                write!(f, "led {} {}", index, if on { "on" } else { "off" })
+            }
+            Request::Leds(mask) => write!(f, "leds {:#04x}", mask),
+            Request::Roulette { period_ms, laps } => write!(f, "roulette {} {}", period_ms, laps),
+            Request::Stop => f.write_str("stop"),
+            Request::Accel => f.write_str("accel"),
+            Request::Mag => f.write_str("mag"),
+        }
+    }
+}
+
+impl Response {
+    /// Parses a response line, with or without its line ending.
+    pub fn parse(line: &str) -> Result<Response, Error> {
+        let mut words = line.split_whitespace();
+        let response = match words.next() {
+            Some("ok") => Response::Ok,
+            Some("pong") => Response::Pong,
+            Some("accel") => Response::Accel(vector(&mut words)?),
+            Some("mag") => Response::Mag(vector(&mut words)?),
+            Some("err") => {
+                let error = match words.next() {
+                    Some("unknown") => Error::UnknownRequest,
+                    Some("argument") => Error::Argument,
+                    Some("too-long") => Error::TooLong,
+                    Some("sensor") => Error::Sensor,
+                    _ => return Err(Error::Argument),
+                };
+                Response::Error(error)
+            }
+            _ => return Err(Error::UnknownRequest),
+        };
+
+        if words.next().is_some() {
+            return Err(Error::Argument);
+        }
+        Ok(response)
+    }
+}
+
+impl fmt::Display for Response {
+    /// Formats the response as a line, without the line ending.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Response::Ok => f.write_str("ok"),
+            Response::Pong => f.write_str("pong"),
+            Response::Accel([x, y, z]) => write!(f, "accel {} {} {}", x, y, z),
+            Response::Mag([x, y, z]) => write!(f, "mag {} {} {}", x, y, z),
+            Response::Error(e) => write!(f, "err {}", e),
+        }
+    }
+}
+
+/// Parses a decimal or `0x` prefixed hexadecimal number.
+fn number<T>(words: &mut SplitWhitespace) -> Result<T, Error>
+where
+    T: TryFrom<u32>,
+{
+    let word = words.next().ok_or(Error::Argument)?;
+    let number = match word.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).map_err(|_| Error::Argument)?,
+        None => word.parse().map_err(|_| Error::Argument)?,
+    };
+    T::try_from(number).map_err(|_| Error::Argument)
+}
+
+fn vector(words: &mut SplitWhitespace) -> Result<[i16; 3], Error> {
+    let mut vector = [0; 3];
+    for value in vector.iter_mut() {
+        let word = words.next().ok_or(Error::Argument)?;
+        *value = word.parse().map_err(|_| Error::Argument)?;
+    }
+    Ok(vector)
+}
+
+/// Puts received bytes together into lines of up to `MAX_LINE_LEN` bytes.
+///
+/// ```
+/// use led_protocol::{Error, LineBuffer};
+///
+/// let mut buffer = LineBuffer::new();
+/// let mut lines = Vec::new();
+/// for byte in b"ping\r\nled 1 on\n" {
+///     if let Some(line) = buffer.push(*byte) {
+///         lines.push(line.map(str::to_owned));
+///     }
+/// }
+/// assert_eq!(lines, [Ok("ping".to_owned()), Ok("led 1 on".to_owned())]);
+///
+/// for byte in [b'x'; 40].iter().chain(b"\n") {
+///     if let Some(line) = buffer.push(*byte) {
+///         assert_eq!(line, Err(Error::TooLong));
+///     }
+/// }
+/// ```
+pub struct LineBuffer {
+    bytes: Vec<u8, MAX_LINE_LEN>,
+    overflowed: bool,
+    /// `bytes` holds a line that was returned, and is cleared with the next byte.
+    complete: bool,
+}
+
+impl LineBuffer {
+    pub const fn new() -> LineBuffer {
+        LineBuffer {
+            bytes: Vec::new(),
+            overflowed: false,
+            complete: false,
+        }
+    }
+
+    /// Adds a byte, returning the line once it is complete. Empty lines are skipped.
+    pub fn push(&mut self, byte: u8) -> Option<Result<&str, Error>> {
+        if self.complete {
+            self.bytes.clear();
+            self.overflowed = false;
+            self.complete = false;
+        }
+
+        match byte {
+            b'\r' => None,
+            b'\n' if self.overflowed => {
+                self.complete = true;
+                Some(Err(Error::TooLong))
+            }
+            b'\n' if self.bytes.is_empty() => None,
+            b'\n' => {
+                self.complete = true;
+                Some(core::str::from_utf8(&self.bytes).map_err(|_| Error::UnknownRequest))
+            }
+            _ => {
+                if self.bytes.push(byte).is_err() {
+                    self.overflowed = true;
+                }
+                None
+            }
+        }
+    }
+}
+
+impl Default for LineBuffer {
+    fn default() -> LineBuffer {
+        LineBuffer::new()
+    }
+}
@@ -0,0 +1,362 @@
+//! LSM303AGR, the sensor on the micro:bit v2.
+//!
+//! ```
+//! use lsm303_regs::agr::{AccelRegister, CfgRegAM, MagMode, MagOdr, MagRegister};
+//! use lsm303_regs::Register;
+//!
+//! assert_eq!(AccelRegister::WHO_AM_I_A.reset(), Some(0x33));
+//! assert_eq!(MagRegister::WHO_AM_I_M.reset(), Some(0x40));
+//! assert_eq!(MagRegister::OUTX_L_REG_M.addr(), 0x68);
+//! assert_eq!(CfgRegAM::default().bits(), CfgRegAM::RESET);
+//!
+//! let cfg = CfgRegAM {
+//!     odr: MagOdr::Hz50,
+//!     mode: MagMode::Continuous,
+//!     ..CfgRegAM::default()
+//! };
+//! assert_eq!(cfg.bits(), 0b0000_1000);
+//! assert_eq!(CfgRegAM::from_bits(0b0000_1000), Ok(cfg));
+//! ```
+
+use crate::{bit, AccelScale, InvalidBits, Register, Status};
+
+/// 7-bit I2C address of the accelerometer.
+pub const ACCELEROMETER: u8 = 0b001_1001;
+/// 7-bit I2C address of the magnetometer.
+pub const MAGNETOMETER: u8 = 0b001_1110;
+
+/// Registers of the accelerometer.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccelRegister {
+    STATUS_REG_AUX_A = 0x07,
+    OUT_TEMP_L_A = 0x0C,
+    OUT_TEMP_H_A = 0x0D,
+    INT_COUNTER_REG_A = 0x0E,
+    WHO_AM_I_A = 0x0F,
+    TEMP_CFG_REG_A = 0x1F,
+    CTRL_REG1_A = 0x20,
+    CTRL_REG2_A = 0x21,
+    CTRL_REG3_A = 0x22,
+    CTRL_REG4_A = 0x23,
+    CTRL_REG5_A = 0x24,
+    CTRL_REG6_A = 0x25,
+    REFERENCE_A = 0x26,
+    STATUS_REG_A = 0x27,
+    OUT_X_L_A = 0x28,
+    OUT_X_H_A = 0x29,
+    OUT_Y_L_A = 0x2A,
+    OUT_Y_H_A = 0x2B,
+    OUT_Z_L_A = 0x2C,
+    OUT_Z_H_A = 0x2D,
+    FIFO_CTRL_REG_A = 0x2E,
+    FIFO_SRC_REG_A = 0x2F,
+    INT1_CFG_A = 0x30,
+    INT1_SRC_A = 0x31,
+    INT1_THS_A = 0x32,
+    INT1_DURATION_A = 0x33,
+    INT2_CFG_A = 0x34,
+    INT2_SRC_A = 0x35,
+    INT2_THS_A = 0x36,
+    INT2_DURATION_A = 0x37,
+    CLICK_CFG_A = 0x38,
+    CLICK_SRC_A = 0x39,
+    CLICK_THS_A = 0x3A,
+    TIME_LIMIT_A = 0x3B,
+    TIME_LATENCY_A = 0x3C,
+    TIME_WINDOW_A = 0x3D,
+    ACT_THS_A = 0x3E,
+    ACT_DUR_A = 0x3F,
+}
+
+impl AccelRegister {
+    pub const fn addr(self) -> u8 {
+        self as u8
+    }
+
+    /// Address to read this register and the ones that follow it in a single transfer.
+    pub const fn auto_increment(self) -> u8 {
+        self as u8 | crate::AUTO_INCREMENT
+    }
+
+    /// Value after power up, `None` for registers the sensor updates on its own.
+    pub fn reset(self) -> Option<u8> {
+        use AccelRegister::*;
+
+        match self {
+            WHO_AM_I_A => Some(0x33),
+            CTRL_REG1_A => Some(0b0000_0111),
+            STATUS_REG_AUX_A | OUT_TEMP_L_A | OUT_TEMP_H_A | INT_COUNTER_REG_A | STATUS_REG_A
+            | OUT_X_L_A | OUT_X_H_A | OUT_Y_L_A | OUT_Y_H_A | OUT_Z_L_A | OUT_Z_H_A
+            | FIFO_SRC_REG_A | INT1_SRC_A | INT2_SRC_A | CLICK_SRC_A => None,
+            _ => Some(0x00),
+        }
+    }
+}
+
+/// Registers of the magnetometer.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MagRegister {
+    OFFSET_X_REG_L_M = 0x45,
+    OFFSET_X_REG_H_M = 0x46,
+    OFFSET_Y_REG_L_M = 0x47,
+    OFFSET_Y_REG_H_M = 0x48,
+    OFFSET_Z_REG_L_M = 0x49,
+    OFFSET_Z_REG_H_M = 0x4A,
+    WHO_AM_I_M = 0x4F,
+    CFG_REG_A_M = 0x60,
+    CFG_REG_B_M = 0x61,
+    CFG_REG_C_M = 0x62,
+    INT_CTRL_REG_M = 0x63,
+    INT_SOURCE_REG_M = 0x64,
+    INT_THS_L_REG_M = 0x65,
+    INT_THS_H_REG_M = 0x66,
+    STATUS_REG_M = 0x67,
+    OUTX_L_REG_M = 0x68,
+    OUTX_H_REG_M = 0x69,
+    OUTY_L_REG_M = 0x6A,
+    OUTY_H_REG_M = 0x6B,
+    OUTZ_L_REG_M = 0x6C,
+    OUTZ_H_REG_M = 0x6D,
+}
+
+impl MagRegister {
+    pub const fn addr(self) -> u8 {
+        self as u8
+    }
+
+    /// Value after power up, `None` for registers the sensor updates on its own.
+    pub fn reset(self) -> Option<u8> {
+        use MagRegister::*;
+
+        match self {
+            WHO_AM_I_M => Some(0x40),
+            CFG_REG_A_M => Some(CfgRegAM::RESET),
+            INT_CTRL_REG_M => Some(0b1110_0000),
+            INT_SOURCE_REG_M | STATUS_REG_M | OUTX_L_REG_M | OUTX_H_REG_M | OUTY_L_REG_M
+            | OUTY_H_REG_M | OUTZ_L_REG_M | OUTZ_H_REG_M => None,
+            _ => Some(0x00),
+        }
+    }
+}
+
+/// Self test of the accelerometer, `ST` in `CTRL_REG4_A`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfTest {
+    Off = 0b00,
+    Mode0 = 0b01,
+    Mode1 = 0b10,
+}
+
+/// `CTRL_REG4_A`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CtrlReg4A {
+    /// `BDU`: don't update the output registers until both bytes have been read.
+    pub block_data_update: bool,
+    /// `BLE`: put the high byte at the lower address.
+    pub big_endian: bool,
+    pub scale: AccelScale,
+    /// `HR`: high resolution output mode.
+    pub high_resolution: bool,
+    pub self_test: SelfTest,
+    /// `SPI_ENABLE`: 3-wire SPI.
+    pub spi_3_wire: bool,
+}
+
+impl Default for CtrlReg4A {
+    fn default() -> CtrlReg4A {
+        CtrlReg4A {
+            block_data_update: false,
+            big_endian: false,
+            scale: AccelScale::G2,
+            high_resolution: false,
+            self_test: SelfTest::Off,
+            spi_3_wire: false,
+        }
+    }
+}
+
+impl Register for CtrlReg4A {
+    const ADDRESS: u8 = AccelRegister::CTRL_REG4_A as u8;
+    const RESET: u8 = 0x00;
+
+    fn bits(&self) -> u8 {
+        (self.block_data_update as u8) << 7
+            | (self.big_endian as u8) << 6
+            | (self.scale as u8) << 4
+            | (self.high_resolution as u8) << 3
+            | (self.self_test as u8) << 1
+            | self.spi_3_wire as u8
+    }
+
+    fn from_bits(bits: u8) -> Result<CtrlReg4A, InvalidBits> {
+        let self_test = match (bits >> 1) & 0b11 {
+            0b00 => SelfTest::Off,
+            0b01 => SelfTest::Mode0,
+            0b10 => SelfTest::Mode1,
+            _ => {
+                return Err(InvalidBits {
+                    address: Self::ADDRESS,
+                    bits,
+                })
+            }
+        };
+        Ok(CtrlReg4A {
+            block_data_update: bit(bits, 7),
+            big_endian: bit(bits, 6),
+            scale: AccelScale::from_bits(bits >> 4),
+            high_resolution: bit(bits, 3),
+            self_test,
+            spi_3_wire: bit(bits, 0),
+        })
+    }
+}
+
+/// Magnetometer output data rate, `ODR` in `CFG_REG_A_M`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MagOdr {
+    Hz10 = 0b00,
+    Hz20 = 0b01,
+    Hz50 = 0b10,
+    Hz100 = 0b11,
+}
+
+/// Magnetometer operating mode, `MD` in `CFG_REG_A_M`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MagMode {
+    Continuous = 0b00,
+    Single = 0b01,
+    Idle = 0b11,
+}
+
+/// `CFG_REG_A_M`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CfgRegAM {
+    /// `COMP_TEMP_EN`: temperature compensation, must be enabled for correct readings.
+    pub temperature_compensation: bool,
+    /// `REBOOT`: reloads the calibration from flash, clears itself.
+    pub reboot: bool,
+    /// `SOFT_RST`: resets the configuration and user registers, clears itself.
+    pub soft_reset: bool,
+    /// `LP`: low power mode.
+    pub low_power: bool,
+    pub odr: MagOdr,
+    pub mode: MagMode,
+}
+
+impl Default for CfgRegAM {
+    fn default() -> CfgRegAM {
+        CfgRegAM {
+            temperature_compensation: false,
+            reboot: false,
+            soft_reset: false,
+            low_power: false,
+            odr: MagOdr::Hz10,
+            mode: MagMode::Idle,
+        }
+    }
+}
+
+impl Register for CfgRegAM {
+    const ADDRESS: u8 = MagRegister::CFG_REG_A_M as u8;
+    const RESET: u8 = 0b0000_0011;
+
+    fn bits(&self) -> u8 {
+        (self.temperature_compensation as u8) << 7
+            | (self.reboot as u8) << 6
+            | (self.soft_reset as u8) << 5
+            | (self.low_power as u8) << 4
+            | (self.odr as u8) << 2
+            | self.mode as u8
+    }
+
+    fn from_bits(bits: u8) -> Result<CfgRegAM, InvalidBits> {
+        let odr = match (bits >> 2) & 0b11 {
+            0b00 => MagOdr::Hz10,
+            0b01 => MagOdr::Hz20,
+            0b10 => MagOdr::Hz50,
+            _ => MagOdr::Hz100,
+        };
+        let mode = match bits & 0b11 {
+            0b00 => MagMode::Continuous,
+            0b01 => MagMode::Single,
+            // Both `10` and `11` put the magnetometer in idle mode
+            _ => MagMode::Idle,
+        };
+        Ok(CfgRegAM {
+            temperature_compensation: bit(bits, 7),
+            reboot: bit(bits, 6),
+            soft_reset: bit(bits, 5),
+            low_power: bit(bits, 4),
+            odr,
+            mode,
+        })
+    }
+}
+
+/// `CFG_REG_C_M`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CfgRegCM {
+    /// `INT_MAG_PIN`: route the magnetometer interrupt to the INT_MAG pin.
+    pub int_mag_pin: bool,
+    /// `I2C_DIS`: disable the I2C interface.
+    pub i2c_disable: bool,
+    /// `BDU`: don't update the output registers until both bytes have been read.
+    pub block_data_update: bool,
+    /// `BLE`: put the high byte at the lower address.
+    pub big_endian: bool,
+    /// `Self_test`
+    pub self_test: bool,
+    /// `INT_MAG`: signal data ready on the DRDY pin.
+    pub data_ready_pin: bool,
+}
+
+impl Register for CfgRegCM {
+    const ADDRESS: u8 = MagRegister::CFG_REG_C_M as u8;
+    const RESET: u8 = 0x00;
+
+    fn bits(&self) -> u8 {
+        (self.int_mag_pin as u8) << 6
+            | (self.i2c_disable as u8) << 5
+            | (self.block_data_update as u8) << 4
+            | (self.big_endian as u8) << 3
+            | (self.self_test as u8) << 1
+            | self.data_ready_pin as u8
+    }
+
+    fn from_bits(bits: u8) -> Result<CfgRegCM, InvalidBits> {
+        // Bits 7 and 2 must be zero
+        if bits & 0b1000_0100 != 0 {
+            return Err(InvalidBits {
+                address: Self::ADDRESS,
+                bits,
+            });
+        }
+        Ok(CfgRegCM {
+            int_mag_pin: bit(bits, 6),
+            i2c_disable: bit(bits, 5),
+            block_data_update: bit(bits, 4),
+            big_endian: bit(bits, 3),
+            self_test: bit(bits, 1),
+            data_ready_pin: bit(bits, 0),
+        })
+    }
+}
+
+/// `STATUS_REG_M`, laid out like `STATUS_REG_A`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StatusRegM(pub Status);
+
+impl Register for StatusRegM {
+    const ADDRESS: u8 = MagRegister::STATUS_REG_M as u8;
+    const RESET: u8 = 0x00;
+
+    fn bits(&self) -> u8 {
+        self.0.bits()
+    }
+
+    fn from_bits(bits: u8) -> Result<StatusRegM, InvalidBits> {
+        Ok(StatusRegM(Status::from_bits(bits)))
+    }
+}
@@ -0,0 +1,359 @@
+//! LSM303DLHC, the sensor on the F3 Discovery.
+//!
+//! ```
+//! use lsm303_regs::dlhc::{CraRegM, MagOdr, MagRegister, MAGNETOMETER};
+//! use lsm303_regs::Register;
+//!
+//! assert_eq!(MAGNETOMETER, 0b001_1110);
+//! assert_eq!(MagRegister::IRA_REG_M.addr(), 0x0A);
+//! assert_eq!(MagRegister::IRA_REG_M.reset(), Some(0b0100_1000));
+//! assert_eq!(CraRegM::default().bits(), CraRegM::RESET);
+//!
+//! let cra = CraRegM {
+//!     temperature_enable: true,
+//!     odr: MagOdr::Hz220,
+//! };
+//! assert_eq!(cra.bits(), 0b1001_1100);
+//! assert_eq!(CraRegM::from_bits(0b1001_1100), Ok(cra));
+//! ```
+
+use crate::{bit, AccelScale, InvalidBits, Register};
+
+/// 7-bit I2C address of the accelerometer.
+pub const ACCELEROMETER: u8 = 0b001_1001;
+/// 7-bit I2C address of the magnetometer.
+pub const MAGNETOMETER: u8 = 0b001_1110;
+
+/// Registers of the accelerometer.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccelRegister {
+    CTRL_REG1_A = 0x20,
+    CTRL_REG2_A = 0x21,
+    CTRL_REG3_A = 0x22,
+    CTRL_REG4_A = 0x23,
+    CTRL_REG5_A = 0x24,
+    CTRL_REG6_A = 0x25,
+    REFERENCE_A = 0x26,
+    STATUS_REG_A = 0x27,
+    OUT_X_L_A = 0x28,
+    OUT_X_H_A = 0x29,
+    OUT_Y_L_A = 0x2A,
+    OUT_Y_H_A = 0x2B,
+    OUT_Z_L_A = 0x2C,
+    OUT_Z_H_A = 0x2D,
+    FIFO_CTRL_REG_A = 0x2E,
+    FIFO_SRC_REG_A = 0x2F,
+    INT1_CFG_A = 0x30,
+    INT1_SRC_A = 0x31,
+    INT1_THS_A = 0x32,
+    INT1_DURATION_A = 0x33,
+    INT2_CFG_A = 0x34,
+    INT2_SRC_A = 0x35,
+    INT2_THS_A = 0x36,
+    INT2_DURATION_A = 0x37,
+    CLICK_CFG_A = 0x38,
+    CLICK_SRC_A = 0x39,
+    CLICK_THS_A = 0x3A,
+    TIME_LIMIT_A = 0x3B,
+    TIME_LATENCY_A = 0x3C,
+    TIME_WINDOW_A = 0x3D,
+}
+
+impl AccelRegister {
+    pub const fn addr(self) -> u8 {
+        self as u8
+    }
+
+    /// Address to read this register and the ones that follow it in a single transfer.
+    pub const fn auto_increment(self) -> u8 {
+        self as u8 | crate::AUTO_INCREMENT
+    }
+
+    /// Value after power up, `None` for registers the sensor updates on its own.
+    pub fn reset(self) -> Option<u8> {
+        use AccelRegister::*;
+
+        match self {
+            CTRL_REG1_A => Some(0b0000_0111),
+            STATUS_REG_A | OUT_X_L_A | OUT_X_H_A | OUT_Y_L_A | OUT_Y_H_A | OUT_Z_L_A
+            | OUT_Z_H_A | FIFO_SRC_REG_A | INT1_SRC_A | INT2_SRC_A | CLICK_SRC_A => None,
+            _ => Some(0x00),
+        }
+    }
+}
+
+/// Registers of the magnetometer. Note that the output registers are ordered X, Z, Y and
+/// that the high byte comes first.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MagRegister {
+    CRA_REG_M = 0x00,
+    CRB_REG_M = 0x01,
+    MR_REG_M = 0x02,
+    OUT_X_H_M = 0x03,
+    OUT_X_L_M = 0x04,
+    OUT_Z_H_M = 0x05,
+    OUT_Z_L_M = 0x06,
+    OUT_Y_H_M = 0x07,
+    OUT_Y_L_M = 0x08,
+    SR_REG_M = 0x09,
+    IRA_REG_M = 0x0A,
+    IRB_REG_M = 0x0B,
+    IRC_REG_M = 0x0C,
+    TEMP_OUT_H_M = 0x31,
+    TEMP_OUT_L_M = 0x32,
+}
+
+impl MagRegister {
+    pub const fn addr(self) -> u8 {
+        self as u8
+    }
+
+    /// Value after power up, `None` for registers the sensor updates on its own.
+    pub fn reset(self) -> Option<u8> {
+        use MagRegister::*;
+
+        match self {
+            CRA_REG_M => Some(CraRegM::RESET),
+            CRB_REG_M => Some(CrbRegM::RESET),
+            MR_REG_M => Some(MrRegM::RESET),
+            // The identification registers spell "H43" in ASCII
+            IRA_REG_M => Some(b'H'),
+            IRB_REG_M => Some(b'4'),
+            IRC_REG_M => Some(b'3'),
+            _ => None,
+        }
+    }
+}
+
+/// `CTRL_REG4_A`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CtrlReg4A {
+    /// `BDU`: don't update the output registers until both bytes have been read.
+    pub block_data_update: bool,
+    /// `BLE`: put the high byte at the lower address.
+    pub big_endian: bool,
+    pub scale: AccelScale,
+    /// `HR`: high resolution output mode.
+    pub high_resolution: bool,
+    /// `SIM`: 3-wire SPI.
+    pub spi_3_wire: bool,
+}
+
+impl Default for CtrlReg4A {
+    fn default() -> CtrlReg4A {
+        CtrlReg4A {
+            block_data_update: false,
+            big_endian: false,
+            scale: AccelScale::G2,
+            high_resolution: false,
+            spi_3_wire: false,
+        }
+    }
+}
+
+impl Register for CtrlReg4A {
+    const ADDRESS: u8 = AccelRegister::CTRL_REG4_A as u8;
+    const RESET: u8 = 0x00;
+
+    fn bits(&self) -> u8 {
+        (self.block_data_update as u8) << 7
+            | (self.big_endian as u8) << 6
+            | (self.scale as u8) << 4
+            | (self.high_resolution as u8) << 3
+            | self.spi_3_wire as u8
+    }
+
+    fn from_bits(bits: u8) -> Result<CtrlReg4A, InvalidBits> {
+        // Bits 2 and 1 must be zero
+        if bits & 0b0000_0110 != 0 {
+            return Err(InvalidBits {
+                address: Self::ADDRESS,
+                bits,
+            });
+        }
+        Ok(CtrlReg4A {
+            block_data_update: bit(bits, 7),
+            big_endian: bit(bits, 6),
+            scale: AccelScale::from_bits(bits >> 4),
+            high_resolution: bit(bits, 3),
+            spi_3_wire: bit(bits, 0),
+        })
+    }
+}
+
+/// Magnetometer output data rate, `DO` in `CRA_REG_M`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MagOdr {
+    Hz0_75 = 0b000,
+    Hz1_5 = 0b001,
+    Hz3 = 0b010,
+    Hz7_5 = 0b011,
+    Hz15 = 0b100,
+    Hz30 = 0b101,
+    Hz75 = 0b110,
+    Hz220 = 0b111,
+}
+
+impl MagOdr {
+    fn from_bits(bits: u8) -> MagOdr {
+        match bits & 0b111 {
+            0b000 => MagOdr::Hz0_75,
+            0b001 => MagOdr::Hz1_5,
+            0b010 => MagOdr::Hz3,
+            0b011 => MagOdr::Hz7_5,
+            0b100 => MagOdr::Hz15,
+            0b101 => MagOdr::Hz30,
+            0b110 => MagOdr::Hz75,
+            _ => MagOdr::Hz220,
+        }
+    }
+}
+
+/// `CRA_REG_M`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CraRegM {
+    /// `TEMP_EN`: enables the temperature sensor.
+    pub temperature_enable: bool,
+    pub odr: MagOdr,
+}
+
+impl Default for CraRegM {
+    fn default() -> CraRegM {
+        CraRegM {
+            temperature_enable: false,
+            odr: MagOdr::Hz15,
+        }
+    }
+}
+
+impl Register for CraRegM {
+    const ADDRESS: u8 = MagRegister::CRA_REG_M as u8;
+    const RESET: u8 = 0b0001_0000;
+
+    fn bits(&self) -> u8 {
+        (self.temperature_enable as u8) << 7 | (self.odr as u8) << 2
+    }
+
+    fn from_bits(bits: u8) -> Result<CraRegM, InvalidBits> {
+        if bits & 0b0110_0011 != 0 {
+            return Err(InvalidBits {
+                address: Self::ADDRESS,
+                bits,
+            });
+        }
+        Ok(CraRegM {
+            temperature_enable: bit(bits, 7),
+            odr: MagOdr::from_bits(bits >> 2),
+        })
+    }
+}
+
+/// Magnetometer input range, `GN` in `CRB_REG_M`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MagGain {
+    /// ±1.3 gauss
+    Gauss1_3 = 0b001,
+    /// ±1.9 gauss
+    Gauss1_9 = 0b010,
+    /// ±2.5 gauss
+    Gauss2_5 = 0b011,
+    /// ±4.0 gauss
+    Gauss4_0 = 0b100,
+    /// ±4.7 gauss
+    Gauss4_7 = 0b101,
+    /// ±5.6 gauss
+    Gauss5_6 = 0b110,
+    /// ±8.1 gauss
+    Gauss8_1 = 0b111,
+}
+
+/// `CRB_REG_M`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrbRegM {
+    pub gain: MagGain,
+}
+
+impl Default for CrbRegM {
+    fn default() -> CrbRegM {
+        CrbRegM {
+            gain: MagGain::Gauss1_3,
+        }
+    }
+}
+
+impl Register for CrbRegM {
+    const ADDRESS: u8 = MagRegister::CRB_REG_M as u8;
+    const RESET: u8 = 0b0010_0000;
+
+    fn bits(&self) -> u8 {
+        (self.gain as u8) << 5
+    }
+
+    fn from_bits(bits: u8) -> Result<CrbRegM, InvalidBits> {
+        let gain = match bits {
+            0b0010_0000 => MagGain::Gauss1_3,
+            0b0100_0000 => MagGain::Gauss1_9,
+            0b0110_0000 => MagGain::Gauss2_5,
+            0b1000_0000 => MagGain::Gauss4_0,
+            0b1010_0000 => MagGain::Gauss4_7,
+            0b1100_0000 => MagGain::Gauss5_6,
+            0b1110_0000 => MagGain::Gauss8_1,
+            _ => {
+                return Err(InvalidBits {
+                    address: Self::ADDRESS,
+                    bits,
+                })
+            }
+        };
+        Ok(CrbRegM { gain })
+    }
+}
+
+/// Magnetometer operating mode, `MD` in `MR_REG_M`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MagMode {
+    Continuous = 0b00,
+    Single = 0b01,
+    Sleep = 0b11,
+}
+
+/// `MR_REG_M`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MrRegM {
+    pub mode: MagMode,
+}
+
+impl Default for MrRegM {
+    fn default() -> MrRegM {
+        MrRegM {
+            mode: MagMode::Sleep,
+        }
+    }
+}
+
+impl Register for MrRegM {
+    const ADDRESS: u8 = MagRegister::MR_REG_M as u8;
+    const RESET: u8 = 0b0000_0011;
+
+    fn bits(&self) -> u8 {
+        self.mode as u8
+    }
+
+    fn from_bits(bits: u8) -> Result<MrRegM, InvalidBits> {
+        let mode = match bits {
+            0b00 => MagMode::Continuous,
+            0b01 => MagMode::Single,
+            // Both `10` and `11` put the magnetometer to sleep
+            0b10 | 0b11 => MagMode::Sleep,
+            _ => {
+                return Err(InvalidBits {
+                    address: Self::ADDRESS,
+                    bits,
+                })
+            }
+        };
+        Ok(MrRegM { mode })
+    }
+}
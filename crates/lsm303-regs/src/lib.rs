@@ -0,0 +1,221 @@
+//! Register maps of the LSM303DLHC (F3 Discovery) and LSM303AGR (micro:bit v2).
+//!
+//! Every chip module provides the 7-bit I2C addresses of the accelerometer and the
+//! magnetometer, one enum per device listing the register addresses with their reset values,
+//! and typed views of the configuration registers the chapters use. The typed views
+//! implement [`Register`], which converts them from and to the raw byte that goes over the
+//! bus:
+//!
+//! ```
+//! use lsm303_regs::{agr::AccelRegister, AccelOdr, CtrlReg1A, Register};
+//!
+//! let ctrl = CtrlReg1A {
+//!     odr: AccelOdr::Hz50,
+//!     ..CtrlReg1A::default()
+//! };
+//! assert_eq!(CtrlReg1A::ADDRESS, AccelRegister::CTRL_REG1_A.addr());
+//! assert_eq!(ctrl.bits(), 0b0100_0111);
+//! assert_eq!(CtrlReg1A::from_bits(0b0100_0111), Ok(ctrl));
+//! ```
+//!
+//! Names follow the datasheets, so they can be looked up there.
+
+#![no_std]
+
+pub mod agr;
+pub mod dlhc;
+
+/// Setting this bit in a register address makes the accelerometer increment the address
+/// after every byte, so several registers can be read in one transfer.
+pub const AUTO_INCREMENT: u8 = 0x80;
+
+/// A register with a typed view of its bits.
+pub trait Register: Sized {
+    /// Address of the register inside its device.
+    const ADDRESS: u8;
+    /// Value of the register after power up.
+    const RESET: u8;
+
+    fn bits(&self) -> u8;
+
+    /// Decodes the raw value, failing on bit patterns the datasheet reserves.
+    fn from_bits(bits: u8) -> Result<Self, InvalidBits>;
+}
+
+/// A register held a bit pattern that the datasheet marks as reserved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidBits {
+    pub address: u8,
+    pub bits: u8,
+}
+
+fn bit(bits: u8, n: u8) -> bool {
+    bits & (1 << n) != 0
+}
+
+/// Accelerometer output data rate, `ODR` in `CTRL_REG1_A`. Both chips share it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccelOdr {
+    PowerDown = 0b0000,
+    Hz1 = 0b0001,
+    Hz10 = 0b0010,
+    Hz25 = 0b0011,
+    Hz50 = 0b0100,
+    Hz100 = 0b0101,
+    Hz200 = 0b0110,
+    Hz400 = 0b0111,
+    /// 1.620 kHz, low power mode only.
+    Khz1_620LowPower = 0b1000,
+    /// 1.344 kHz in normal (and high resolution) mode, 5.376 kHz in low power mode.
+    Khz1_344 = 0b1001,
+}
+
+impl AccelOdr {
+    fn from_bits(bits: u8) -> Option<AccelOdr> {
+        Some(match bits {
+            0b0000 => AccelOdr::PowerDown,
+            0b0001 => AccelOdr::Hz1,
+            0b0010 => AccelOdr::Hz10,
+            0b0011 => AccelOdr::Hz25,
+            0b0100 => AccelOdr::Hz50,
+            0b0101 => AccelOdr::Hz100,
+            0b0110 => AccelOdr::Hz200,
+            0b0111 => AccelOdr::Hz400,
+            0b1000 => AccelOdr::Khz1_620LowPower,
+            0b1001 => AccelOdr::Khz1_344,
+            _ => return None,
+        })
+    }
+}
+
+/// Accelerometer full scale, `FS` in `CTRL_REG4_A`. Both chips share it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccelScale {
+    G2 = 0b00,
+    G4 = 0b01,
+    G8 = 0b10,
+    G16 = 0b11,
+}
+
+impl AccelScale {
+    fn from_bits(bits: u8) -> AccelScale {
+        match bits & 0b11 {
+            0b00 => AccelScale::G2,
+            0b01 => AccelScale::G4,
+            0b10 => AccelScale::G8,
+            _ => AccelScale::G16,
+        }
+    }
+}
+
+/// `CTRL_REG1_A`, at the same address and with the same layout on both chips.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CtrlReg1A {
+    pub odr: AccelOdr,
+    /// `LPen`: low power mode, 8 bit resolution.
+    pub low_power: bool,
+    pub z_enable: bool,
+    pub y_enable: bool,
+    pub x_enable: bool,
+}
+
+impl Default for CtrlReg1A {
+    fn default() -> CtrlReg1A {
+        CtrlReg1A {
+            odr: AccelOdr::PowerDown,
+            low_power: false,
+            z_enable: true,
+            y_enable: true,
+            x_enable: true,
+        }
+    }
+}
+
+impl Register for CtrlReg1A {
+    const ADDRESS: u8 = 0x20;
+    const RESET: u8 = 0b0000_0111;
+
+    fn bits(&self) -> u8 {
+        (self.odr as u8) << 4
+            | (self.low_power as u8) << 3
+            | (self.z_enable as u8) << 2
+            | (self.y_enable as u8) << 1
+            | self.x_enable as u8
+    }
+
+    fn from_bits(bits: u8) -> Result<CtrlReg1A, InvalidBits> {
+        let odr = AccelOdr::from_bits(bits >> 4).ok_or(InvalidBits {
+            address: Self::ADDRESS,
+            bits,
+        })?;
+        Ok(CtrlReg1A {
+            odr,
+            low_power: bit(bits, 3),
+            z_enable: bit(bits, 2),
+            y_enable: bit(bits, 1),
+            x_enable: bit(bits, 0),
+        })
+    }
+}
+
+/// `STATUS_REG_A`, and `STATUS_REG_M` on the LSM303AGR: which axes have new data and which
+/// ones were overwritten before being read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Status {
+    pub zyx_overrun: bool,
+    pub z_overrun: bool,
+    pub y_overrun: bool,
+    pub x_overrun: bool,
+    pub zyx_data_available: bool,
+    pub z_data_available: bool,
+    pub y_data_available: bool,
+    pub x_data_available: bool,
+}
+
+impl Status {
+    fn bits(&self) -> u8 {
+        [
+            self.x_data_available,
+            self.y_data_available,
+            self.z_data_available,
+            self.zyx_data_available,
+            self.x_overrun,
+            self.y_overrun,
+            self.z_overrun,
+            self.zyx_overrun,
+        ]
+        .iter()
+        .enumerate()
+        .fold(0, |bits, (n, set)| bits | (*set as u8) << n)
+    }
+
+    fn from_bits(bits: u8) -> Status {
+        Status {
+            zyx_overrun: bit(bits, 7),
+            z_overrun: bit(bits, 6),
+            y_overrun: bit(bits, 5),
+            x_overrun: bit(bits, 4),
+            zyx_data_available: bit(bits, 3),
+            z_data_available: bit(bits, 2),
+            y_data_available: bit(bits, 1),
+            x_data_available: bit(bits, 0),
+        }
+    }
+}
+
+/// `STATUS_REG_A`, the same on both chips.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StatusRegA(pub Status);
+
+impl Register for StatusRegA {
+    const ADDRESS: u8 = 0x27;
+    const RESET: u8 = 0x00;
+
+    fn bits(&self) -> u8 {
+        self.0.bits()
+    }
+
+    fn from_bits(bits: u8) -> Result<StatusRegA, InvalidBits> {
+        Ok(StatusRegA(Status::from_bits(bits)))
+    }
+}
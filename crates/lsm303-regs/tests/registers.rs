@@ -0,0 +1,524 @@
+//! The register maps checked against the tables of the datasheets.
+
+use core::fmt::Debug;
+
+use lsm303_regs::{
+    agr, dlhc, AccelOdr, AccelScale, CtrlReg1A, InvalidBits, Register, Status, StatusRegA,
+    AUTO_INCREMENT,
+};
+
+/// Sets a field to one of its values, and the bits that should give.
+type Field<R> = (fn(&mut R), u8);
+
+/// Sets one field, starting from a value that encodes to zero, and checks the bits it ends
+/// up in. Listing every value of a field checks both its position and its width.
+fn check_fields<R>(zero: R, fields: &[Field<R>])
+where
+    R: Register + Copy + Debug + PartialEq,
+{
+    assert_eq!(zero.bits(), 0, "{:?}", zero);
+    for (i, (set, bits)) in fields.iter().enumerate() {
+        let mut register = zero;
+        set(&mut register);
+        assert_eq!(
+            register.bits(),
+            *bits,
+            "field #{} of {:?} ({:#010b})",
+            i,
+            register,
+            R::ADDRESS
+        );
+        assert_eq!(R::from_bits(*bits), Ok(register));
+    }
+}
+
+/// Decodes every possible byte. `valid` says which ones the datasheet allows and
+/// `canonical` how they are encoded again, which only differs for aliases.
+fn check_round_trip<R>(valid: fn(u8) -> bool, canonical: fn(u8) -> u8)
+where
+    R: Register + Debug + PartialEq,
+{
+    for bits in 0..=255u8 {
+        match R::from_bits(bits) {
+            Ok(register) => {
+                assert!(valid(bits), "{:#010b} decoded to {:?}", bits, register);
+                assert_eq!(register.bits(), canonical(bits), "{:?}", register);
+                assert_eq!(R::from_bits(register.bits()), Ok(register));
+            }
+            Err(error) => {
+                assert!(!valid(bits), "{:#010b} was rejected", bits);
+                assert_eq!(
+                    error,
+                    InvalidBits {
+                        address: R::ADDRESS,
+                        bits
+                    }
+                );
+            }
+        }
+    }
+}
+
+fn check_reset<R: Register + Default + Debug + PartialEq>(reset: Option<u8>) {
+    assert_eq!(R::default().bits(), R::RESET, "{:#04x}", R::ADDRESS);
+    assert_eq!(R::from_bits(R::RESET), Ok(R::default()));
+    assert_eq!(reset, Some(R::RESET), "{:#04x}", R::ADDRESS);
+}
+
+const ACCEL_ODRS: [(AccelOdr, u8); 10] = [
+    (AccelOdr::PowerDown, 0b0000),
+    (AccelOdr::Hz1, 0b0001),
+    (AccelOdr::Hz10, 0b0010),
+    (AccelOdr::Hz25, 0b0011),
+    (AccelOdr::Hz50, 0b0100),
+    (AccelOdr::Hz100, 0b0101),
+    (AccelOdr::Hz200, 0b0110),
+    (AccelOdr::Hz400, 0b0111),
+    (AccelOdr::Khz1_620LowPower, 0b1000),
+    (AccelOdr::Khz1_344, 0b1001),
+];
+
+const ACCEL_SCALES: [(AccelScale, u8); 4] = [
+    (AccelScale::G2, 0b00),
+    (AccelScale::G4, 0b01),
+    (AccelScale::G8, 0b10),
+    (AccelScale::G16, 0b11),
+];
+
+#[test]
+fn i2c_addresses() {
+    assert_eq!(agr::ACCELEROMETER, 0x19);
+    assert_eq!(agr::MAGNETOMETER, 0x1E);
+    assert_eq!(dlhc::ACCELEROMETER, 0x19);
+    assert_eq!(dlhc::MAGNETOMETER, 0x1E);
+    assert_eq!(AUTO_INCREMENT, 0x80);
+}
+
+#[test]
+fn agr_accel_registers() {
+    use agr::AccelRegister::*;
+
+    // Name, address and reset value from the register map of the LSM303AGR datasheet
+    let table = [
+        (STATUS_REG_AUX_A, 0x07, None),
+        (OUT_TEMP_L_A, 0x0C, None),
+        (OUT_TEMP_H_A, 0x0D, None),
+        (INT_COUNTER_REG_A, 0x0E, None),
+        (WHO_AM_I_A, 0x0F, Some(0x33)),
+        (TEMP_CFG_REG_A, 0x1F, Some(0x00)),
+        (CTRL_REG1_A, 0x20, Some(0x07)),
+        (CTRL_REG2_A, 0x21, Some(0x00)),
+        (CTRL_REG3_A, 0x22, Some(0x00)),
+        (CTRL_REG4_A, 0x23, Some(0x00)),
+        (CTRL_REG5_A, 0x24, Some(0x00)),
+        (CTRL_REG6_A, 0x25, Some(0x00)),
+        (REFERENCE_A, 0x26, Some(0x00)),
+        (STATUS_REG_A, 0x27, None),
+        (OUT_X_L_A, 0x28, None),
+        (OUT_X_H_A, 0x29, None),
+        (OUT_Y_L_A, 0x2A, None),
+        (OUT_Y_H_A, 0x2B, None),
+        (OUT_Z_L_A, 0x2C, None),
+        (OUT_Z_H_A, 0x2D, None),
+        (FIFO_CTRL_REG_A, 0x2E, Some(0x00)),
+        (FIFO_SRC_REG_A, 0x2F, None),
+        (INT1_CFG_A, 0x30, Some(0x00)),
+        (INT1_SRC_A, 0x31, None),
+        (INT1_THS_A, 0x32, Some(0x00)),
+        (INT1_DURATION_A, 0x33, Some(0x00)),
+        (INT2_CFG_A, 0x34, Some(0x00)),
+        (INT2_SRC_A, 0x35, None),
+        (INT2_THS_A, 0x36, Some(0x00)),
+        (INT2_DURATION_A, 0x37, Some(0x00)),
+        (CLICK_CFG_A, 0x38, Some(0x00)),
+        (CLICK_SRC_A, 0x39, None),
+        (CLICK_THS_A, 0x3A, Some(0x00)),
+        (TIME_LIMIT_A, 0x3B, Some(0x00)),
+        (TIME_LATENCY_A, 0x3C, Some(0x00)),
+        (TIME_WINDOW_A, 0x3D, Some(0x00)),
+        (ACT_THS_A, 0x3E, Some(0x00)),
+        (ACT_DUR_A, 0x3F, Some(0x00)),
+    ];
+    for (register, addr, reset) in table.iter() {
+        assert_eq!(register.addr(), *addr, "{:?}", register);
+        assert_eq!(register.auto_increment(), addr | 0x80, "{:?}", register);
+        assert_eq!(register.reset(), *reset, "{:?}", register);
+    }
+}
+
+#[test]
+fn agr_mag_registers() {
+    use agr::MagRegister::*;
+
+    let table = [
+        (OFFSET_X_REG_L_M, 0x45, Some(0x00)),
+        (OFFSET_X_REG_H_M, 0x46, Some(0x00)),
+        (OFFSET_Y_REG_L_M, 0x47, Some(0x00)),
+        (OFFSET_Y_REG_H_M, 0x48, Some(0x00)),
+        (OFFSET_Z_REG_L_M, 0x49, Some(0x00)),
+        (OFFSET_Z_REG_H_M, 0x4A, Some(0x00)),
+        (WHO_AM_I_M, 0x4F, Some(0x40)),
+        (CFG_REG_A_M, 0x60, Some(0x03)),
+        (CFG_REG_B_M, 0x61, Some(0x00)),
+        (CFG_REG_C_M, 0x62, Some(0x00)),
+        (INT_CTRL_REG_M, 0x63, Some(0xE0)),
+        (INT_SOURCE_REG_M, 0x64, None),
+        (INT_THS_L_REG_M, 0x65, Some(0x00)),
+        (INT_THS_H_REG_M, 0x66, Some(0x00)),
+        (STATUS_REG_M, 0x67, None),
+        (OUTX_L_REG_M, 0x68, None),
+        (OUTX_H_REG_M, 0x69, None),
+        (OUTY_L_REG_M, 0x6A, None),
+        (OUTY_H_REG_M, 0x6B, None),
+        (OUTZ_L_REG_M, 0x6C, None),
+        (OUTZ_H_REG_M, 0x6D, None),
+    ];
+    for (register, addr, reset) in table.iter() {
+        assert_eq!(register.addr(), *addr, "{:?}", register);
+        assert_eq!(register.reset(), *reset, "{:?}", register);
+    }
+}
+
+#[test]
+fn dlhc_accel_registers() {
+    use dlhc::AccelRegister::*;
+
+    // Name, address and reset value from the register map of the LSM303DLHC datasheet
+    let table = [
+        (CTRL_REG1_A, 0x20, Some(0x07)),
+        (CTRL_REG2_A, 0x21, Some(0x00)),
+        (CTRL_REG3_A, 0x22, Some(0x00)),
+        (CTRL_REG4_A, 0x23, Some(0x00)),
+        (CTRL_REG5_A, 0x24, Some(0x00)),
+        (CTRL_REG6_A, 0x25, Some(0x00)),
+        (REFERENCE_A, 0x26, Some(0x00)),
+        (STATUS_REG_A, 0x27, None),
+        (OUT_X_L_A, 0x28, None),
+        (OUT_X_H_A, 0x29, None),
+        (OUT_Y_L_A, 0x2A, None),
+        (OUT_Y_H_A, 0x2B, None),
+        (OUT_Z_L_A, 0x2C, None),
+        (OUT_Z_H_A, 0x2D, None),
+        (FIFO_CTRL_REG_A, 0x2E, Some(0x00)),
+        (FIFO_SRC_REG_A, 0x2F, None),
+        (INT1_CFG_A, 0x30, Some(0x00)),
+        (INT1_SRC_A, 0x31, None),
+        (INT1_THS_A, 0x32, Some(0x00)),
+        (INT1_DURATION_A, 0x33, Some(0x00)),
+        (INT2_CFG_A, 0x34, Some(0x00)),
+        (INT2_SRC_A, 0x35, None),
+        (INT2_THS_A, 0x36, Some(0x00)),
+        (INT2_DURATION_A, 0x37, Some(0x00)),
+        (CLICK_CFG_A, 0x38, Some(0x00)),
+        (CLICK_SRC_A, 0x39, None),
+        (CLICK_THS_A, 0x3A, Some(0x00)),
+        (TIME_LIMIT_A, 0x3B, Some(0x00)),
+        (TIME_LATENCY_A, 0x3C, Some(0x00)),
+        (TIME_WINDOW_A, 0x3D, Some(0x00)),
+    ];
+    for (register, addr, reset) in table.iter() {
+        assert_eq!(register.addr(), *addr, "{:?}", register);
+        assert_eq!(register.auto_increment(), addr | 0x80, "{:?}", register);
+        assert_eq!(register.reset(), *reset, "{:?}", register);
+    }
+}
+
+#[test]
+fn dlhc_mag_registers() {
+    use dlhc::MagRegister::*;
+
+    let table = [
+        (CRA_REG_M, 0x00, Some(0x10)),
+        (CRB_REG_M, 0x01, Some(0x20)),
+        (MR_REG_M, 0x02, Some(0x03)),
+        (OUT_X_H_M, 0x03, None),
+        (OUT_X_L_M, 0x04, None),
+        (OUT_Z_H_M, 0x05, None),
+        (OUT_Z_L_M, 0x06, None),
+        (OUT_Y_H_M, 0x07, None),
+        (OUT_Y_L_M, 0x08, None),
+        (SR_REG_M, 0x09, None),
+        (IRA_REG_M, 0x0A, Some(0x48)),
+        (IRB_REG_M, 0x0B, Some(0x34)),
+        (IRC_REG_M, 0x0C, Some(0x33)),
+        (TEMP_OUT_H_M, 0x31, None),
+        (TEMP_OUT_L_M, 0x32, None),
+    ];
+    for (register, addr, reset) in table.iter() {
+        assert_eq!(register.addr(), *addr, "{:?}", register);
+        assert_eq!(register.reset(), *reset, "{:?}", register);
+    }
+}
+
+#[test]
+fn typed_registers_match_the_maps() {
+    assert_eq!(CtrlReg1A::ADDRESS, agr::AccelRegister::CTRL_REG1_A.addr());
+    assert_eq!(CtrlReg1A::ADDRESS, dlhc::AccelRegister::CTRL_REG1_A.addr());
+    assert_eq!(StatusRegA::ADDRESS, agr::AccelRegister::STATUS_REG_A.addr());
+    assert_eq!(
+        StatusRegA::ADDRESS,
+        dlhc::AccelRegister::STATUS_REG_A.addr()
+    );
+    assert_eq!(
+        agr::CtrlReg4A::ADDRESS,
+        agr::AccelRegister::CTRL_REG4_A.addr()
+    );
+    assert_eq!(agr::CfgRegAM::ADDRESS, agr::MagRegister::CFG_REG_A_M.addr());
+    assert_eq!(agr::CfgRegCM::ADDRESS, agr::MagRegister::CFG_REG_C_M.addr());
+    assert_eq!(
+        agr::StatusRegM::ADDRESS,
+        agr::MagRegister::STATUS_REG_M.addr()
+    );
+    assert_eq!(
+        dlhc::CtrlReg4A::ADDRESS,
+        dlhc::AccelRegister::CTRL_REG4_A.addr()
+    );
+    assert_eq!(dlhc::CraRegM::ADDRESS, dlhc::MagRegister::CRA_REG_M.addr());
+    assert_eq!(dlhc::CrbRegM::ADDRESS, dlhc::MagRegister::CRB_REG_M.addr());
+    assert_eq!(dlhc::MrRegM::ADDRESS, dlhc::MagRegister::MR_REG_M.addr());
+}
+
+#[test]
+fn reset_values() {
+    check_reset::<CtrlReg1A>(agr::AccelRegister::CTRL_REG1_A.reset());
+    check_reset::<CtrlReg1A>(dlhc::AccelRegister::CTRL_REG1_A.reset());
+    check_reset::<agr::CtrlReg4A>(agr::AccelRegister::CTRL_REG4_A.reset());
+    check_reset::<agr::CfgRegAM>(agr::MagRegister::CFG_REG_A_M.reset());
+    check_reset::<agr::CfgRegCM>(agr::MagRegister::CFG_REG_C_M.reset());
+    check_reset::<dlhc::CtrlReg4A>(dlhc::AccelRegister::CTRL_REG4_A.reset());
+    check_reset::<dlhc::CraRegM>(dlhc::MagRegister::CRA_REG_M.reset());
+    check_reset::<dlhc::CrbRegM>(dlhc::MagRegister::CRB_REG_M.reset());
+    check_reset::<dlhc::MrRegM>(dlhc::MagRegister::MR_REG_M.reset());
+
+    // The status registers are updated by the sensor, they only start out empty
+    assert_eq!(StatusRegA::default().bits(), StatusRegA::RESET);
+    assert_eq!(agr::StatusRegM::default().bits(), agr::StatusRegM::RESET);
+}
+
+#[test]
+fn ctrl_reg1_a_fields() {
+    let zero = CtrlReg1A {
+        odr: AccelOdr::PowerDown,
+        low_power: false,
+        z_enable: false,
+        y_enable: false,
+        x_enable: false,
+    };
+    check_fields(
+        zero,
+        &[
+            (|r| r.low_power = true, 1 << 3),
+            (|r| r.z_enable = true, 1 << 2),
+            (|r| r.y_enable = true, 1 << 1),
+            (|r| r.x_enable = true, 1 << 0),
+        ],
+    );
+    for (odr, bits) in ACCEL_ODRS.iter() {
+        let register = CtrlReg1A { odr: *odr, ..zero };
+        assert_eq!(register.bits(), bits << 4, "{:?}", odr);
+        assert_eq!(CtrlReg1A::from_bits(bits << 4), Ok(register));
+    }
+    // ODR values from 0b1010 on are reserved
+    check_round_trip::<CtrlReg1A>(|bits| bits >> 4 <= 0b1001, |bits| bits);
+}
+
+#[test]
+fn status_fields() {
+    let fields: [Field<Status>; 8] = [
+        (|s| s.zyx_overrun = true, 1 << 7),
+        (|s| s.z_overrun = true, 1 << 6),
+        (|s| s.y_overrun = true, 1 << 5),
+        (|s| s.x_overrun = true, 1 << 4),
+        (|s| s.zyx_data_available = true, 1 << 3),
+        (|s| s.z_data_available = true, 1 << 2),
+        (|s| s.y_data_available = true, 1 << 1),
+        (|s| s.x_data_available = true, 1 << 0),
+    ];
+    for (set, bits) in fields.iter() {
+        let mut status = Status::default();
+        set(&mut status);
+        assert_eq!(StatusRegA(status).bits(), *bits, "{:?}", status);
+        assert_eq!(agr::StatusRegM(status).bits(), *bits, "{:?}", status);
+        assert_eq!(StatusRegA::from_bits(*bits), Ok(StatusRegA(status)));
+    }
+    check_round_trip::<StatusRegA>(|_| true, |bits| bits);
+    check_round_trip::<agr::StatusRegM>(|_| true, |bits| bits);
+}
+
+#[test]
+fn agr_ctrl_reg4_a_fields() {
+    use agr::{CtrlReg4A, SelfTest};
+
+    let zero = CtrlReg4A::default();
+    check_fields(
+        zero,
+        &[
+            (|r| r.block_data_update = true, 1 << 7),
+            (|r| r.big_endian = true, 1 << 6),
+            (|r| r.high_resolution = true, 1 << 3),
+            (|r| r.self_test = SelfTest::Mode0, 0b01 << 1),
+            (|r| r.self_test = SelfTest::Mode1, 0b10 << 1),
+            (|r| r.spi_3_wire = true, 1 << 0),
+        ],
+    );
+    for (scale, bits) in ACCEL_SCALES.iter() {
+        let register = CtrlReg4A {
+            scale: *scale,
+            ..zero
+        };
+        assert_eq!(register.bits(), bits << 4, "{:?}", scale);
+        assert_eq!(CtrlReg4A::from_bits(bits << 4), Ok(register));
+    }
+    // Self test `11` is not allowed
+    check_round_trip::<CtrlReg4A>(|bits| (bits >> 1) & 0b11 != 0b11, |bits| bits);
+}
+
+#[test]
+fn agr_cfg_reg_a_m_fields() {
+    use agr::{CfgRegAM, MagMode, MagOdr};
+
+    let zero = CfgRegAM {
+        mode: MagMode::Continuous,
+        ..CfgRegAM::default()
+    };
+    check_fields(
+        zero,
+        &[
+            (|r| r.temperature_compensation = true, 1 << 7),
+            (|r| r.reboot = true, 1 << 6),
+            (|r| r.soft_reset = true, 1 << 5),
+            (|r| r.low_power = true, 1 << 4),
+            (|r| r.odr = MagOdr::Hz10, 0b00 << 2),
+            (|r| r.odr = MagOdr::Hz20, 0b01 << 2),
+            (|r| r.odr = MagOdr::Hz50, 0b10 << 2),
+            (|r| r.odr = MagOdr::Hz100, 0b11 << 2),
+            (|r| r.mode = MagMode::Single, 0b01),
+            (|r| r.mode = MagMode::Idle, 0b11),
+        ],
+    );
+    // Mode `10` is idle as well, which is written back as `11`
+    check_round_trip::<CfgRegAM>(
+        |_| true,
+        |bits| {
+            if bits & 0b11 == 0b10 {
+                bits | 0b01
+            } else {
+                bits
+            }
+        },
+    );
+}
+
+#[test]
+fn agr_cfg_reg_c_m_fields() {
+    use agr::CfgRegCM;
+
+    check_fields(
+        CfgRegCM::default(),
+        &[
+            (|r| r.int_mag_pin = true, 1 << 6),
+            (|r| r.i2c_disable = true, 1 << 5),
+            (|r| r.block_data_update = true, 1 << 4),
+            (|r| r.big_endian = true, 1 << 3),
+            (|r| r.self_test = true, 1 << 1),
+            (|r| r.data_ready_pin = true, 1 << 0),
+        ],
+    );
+    // Bits 7 and 2 have to stay zero
+    check_round_trip::<CfgRegCM>(|bits| bits & 0b1000_0100 == 0, |bits| bits);
+}
+
+#[test]
+fn dlhc_ctrl_reg4_a_fields() {
+    use dlhc::CtrlReg4A;
+
+    let zero = CtrlReg4A::default();
+    check_fields(
+        zero,
+        &[
+            (|r| r.block_data_update = true, 1 << 7),
+            (|r| r.big_endian = true, 1 << 6),
+            (|r| r.high_resolution = true, 1 << 3),
+            (|r| r.spi_3_wire = true, 1 << 0),
+        ],
+    );
+    for (scale, bits) in ACCEL_SCALES.iter() {
+        let register = CtrlReg4A {
+            scale: *scale,
+            ..zero
+        };
+        assert_eq!(register.bits(), bits << 4, "{:?}", scale);
+        assert_eq!(CtrlReg4A::from_bits(bits << 4), Ok(register));
+    }
+    // Bits 2 and 1 have to stay zero
+    check_round_trip::<CtrlReg4A>(|bits| bits & 0b0000_0110 == 0, |bits| bits);
+}
+
+#[test]
+fn dlhc_cra_reg_m_fields() {
+    use dlhc::{CraRegM, MagOdr};
+
+    let zero = CraRegM {
+        temperature_enable: false,
+        odr: MagOdr::Hz0_75,
+    };
+    check_fields(
+        zero,
+        &[
+            (|r| r.temperature_enable = true, 1 << 7),
+            (|r| r.odr = MagOdr::Hz1_5, 0b001 << 2),
+            (|r| r.odr = MagOdr::Hz3, 0b010 << 2),
+            (|r| r.odr = MagOdr::Hz7_5, 0b011 << 2),
+            (|r| r.odr = MagOdr::Hz15, 0b100 << 2),
+            (|r| r.odr = MagOdr::Hz30, 0b101 << 2),
+            (|r| r.odr = MagOdr::Hz75, 0b110 << 2),
+            (|r| r.odr = MagOdr::Hz220, 0b111 << 2),
+        ],
+    );
+    // Bits 6, 5, 1 and 0 have to stay zero
+    check_round_trip::<CraRegM>(|bits| bits & 0b0110_0011 == 0, |bits| bits);
+}
+
+#[test]
+fn dlhc_crb_reg_m_fields() {
+    use dlhc::{CrbRegM, MagGain};
+
+    let gains = [
+        (MagGain::Gauss1_3, 0b001),
+        (MagGain::Gauss1_9, 0b010),
+        (MagGain::Gauss2_5, 0b011),
+        (MagGain::Gauss4_0, 0b100),
+        (MagGain::Gauss4_7, 0b101),
+        (MagGain::Gauss5_6, 0b110),
+        (MagGain::Gauss8_1, 0b111),
+    ];
+    for (gain, bits) in gains.iter() {
+        let register = CrbRegM { gain: *gain };
+        assert_eq!(register.bits(), bits << 5, "{:?}", gain);
+        assert_eq!(CrbRegM::from_bits(bits << 5), Ok(register));
+    }
+    // Gain `000` isn't defined and the low 5 bits have to stay zero
+    check_round_trip::<CrbRegM>(|bits| bits & 0b0001_1111 == 0 && bits != 0, |bits| bits);
+}
+
+#[test]
+fn dlhc_mr_reg_m_fields() {
+    use dlhc::{MagMode, MrRegM};
+
+    let modes = [
+        (MagMode::Continuous, 0b00),
+        (MagMode::Single, 0b01),
+        (MagMode::Sleep, 0b11),
+    ];
+    for (mode, bits) in modes.iter() {
+        let register = MrRegM { mode: *mode };
+        assert_eq!(register.bits(), *bits, "{:?}", mode);
+        assert_eq!(MrRegM::from_bits(*bits), Ok(register));
+    }
+    // Mode `10` is sleep as well, which is written back as `11`, the upper bits have to
+    // stay zero
+    check_round_trip::<MrRegM>(
+        |bits| bits & 0b1111_1100 == 0,
+        |bits| if bits == 0b10 { 0b11 } else { bits },
+    );
+}
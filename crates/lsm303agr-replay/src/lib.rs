@@ -0,0 +1,407 @@
+//! An LSM303AGR that lives on the host.
+//!
+//! [`Lsm303agrReplay`] implements the blocking I2C traits of `embedded-hal` and answers like
+//! the accelerometer (address `0x19`) and magnetometer (address `0x1E`) of a real LSM303AGR
+//! would. Instead of measuring anything it plays back two streams of samples, one per sensor,
+//! which can be recorded data (for example the CSV written by `sensor-log`) or generated by a
+//! closure. Hand it to `Lsm303agr::new_with_i2c` in place of the `Twim` peripheral and the
+//! sensor handling of the chapters can run in a `cargo test` on your computer.
+//!
+//! Samples are given in the same units the `lsm303agr` driver reports: milli-g for the
+//! accelerometer and nanotesla for the magnetometer. They are converted to raw register
+//! values using the operating mode and full scale currently configured in `CTRL_REG1_A` and
+//! `CTRL_REG4_A`, so the driver sees exactly the resolution the real sensor would give it.
+//!
+//! ```
+//! use lsm303agr::{AccelOutputDataRate, Lsm303agr};
+//! use lsm303agr_replay::Lsm303agrReplay;
+//!
+//! // A board lying flat on the table while the magnetic field slowly turns around
+//! let accel = core::iter::repeat([0, 0, 1000]);
+//! let mag = (0..360).map(|deg: i32| [deg * 100, -deg * 100, -40_000]);
+//! let mut sensor = Lsm303agr::new_with_i2c(Lsm303agrReplay::new(accel, mag));
+//! sensor.init().unwrap();
+//! sensor.set_accel_odr(AccelOutputDataRate::Hz10).unwrap();
+//! while !sensor.accel_status().unwrap().xyz_new_data {}
+//! let data = sensor.accel_data().unwrap();
+//! assert_eq!((data.x, data.y, data.z), (0, 0, 1000));
+//! ```
+//!
+//! The tests of this crate run the `lsm303agr` driver against it, together with the compass
+//! calibration and the punch detection of the chapters.
+
+#![no_std]
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+pub const ACCEL_ADDR: u8 = 0b001_1001;
+pub const MAG_ADDR: u8 = 0b001_1110;
+
+const AUTO_INCREMENT: u8 = 0x80;
+
+// Accelerometer registers
+const WHO_AM_I_A: u8 = 0x0F;
+const CTRL_REG1_A: u8 = 0x20;
+const CTRL_REG4_A: u8 = 0x23;
+const STATUS_REG_A: u8 = 0x27;
+const OUT_X_L_A: u8 = 0x28;
+const OUT_Z_H_A: u8 = 0x2D;
+
+// Magnetometer registers
+const WHO_AM_I_M: u8 = 0x4F;
+const CFG_REG_A_M: u8 = 0x60;
+const STATUS_REG_M: u8 = 0x67;
+const OUTX_L_REG_M: u8 = 0x68;
+const OUTZ_H_REG_M: u8 = 0x6D;
+
+const ACCEL_REGISTERS: usize = 0x40;
+const MAG_REGISTERS: usize = 0x70;
+
+// STATUS_REG_A / STATUS_REG_M
+const ZYXDA: u8 = 1 << 3;
+const ZYXOR: u8 = 1 << 7;
+
+// CTRL_REG1_A
+const LPEN: u8 = 1 << 3;
+// CTRL_REG4_A
+const HR: u8 = 1 << 3;
+
+// CFG_REG_A_M operating modes
+const MD_MASK: u8 = 0b11;
+const MD_CONTINUOUS: u8 = 0b00;
+const MD_SINGLE: u8 = 0b01;
+const MD_IDLE: u8 = 0b11;
+
+/// Magnetometer sensitivity in nanotesla per digit.
+const MAG_NT_PER_DIGIT: i32 = 150;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// Nothing answers on this address.
+    Nack(u8),
+    /// The transfer addressed a register that doesn't exist.
+    InvalidRegister { address: u8, register: u8 },
+    /// A write transfer did not contain a register address.
+    MissingRegister,
+}
+
+/// Accelerometer resolution, selected by `LPen` in `CTRL_REG1_A` and `HR` in `CTRL_REG4_A`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccelMode {
+    LowPower,
+    Normal,
+    HighResolution,
+}
+
+impl AccelMode {
+    fn bits(self) -> u32 {
+        match self {
+            AccelMode::LowPower => 8,
+            AccelMode::Normal => 10,
+            AccelMode::HighResolution => 12,
+        }
+    }
+}
+
+/// Emulated LSM303AGR, see the crate documentation.
+pub struct Lsm303agrReplay<A, M> {
+    accel_samples: A,
+    mag_samples: M,
+    accel: [u8; ACCEL_REGISTERS],
+    mag: [u8; MAG_REGISTERS],
+    accel_pending: Option<[i32; 3]>,
+    mag_pending: Option<[i32; 3]>,
+    accel_overrun: bool,
+    mag_overrun: bool,
+    accel_count: usize,
+    mag_count: usize,
+}
+
+impl<A, M> Lsm303agrReplay<A, M>
+where
+    A: Iterator<Item = [i32; 3]>,
+    M: Iterator<Item = [i32; 3]>,
+{
+    /// Creates a sensor in its power-on state that will play back `accel` (in milli-g) and
+    /// `mag` (in nanotesla).
+    ///
+    /// Each sensor reports new data as soon as the previous sample has been read and stops
+    /// reporting new data when its stream runs out. Use `Iterator::cycle` to loop a recording.
+    pub fn new(accel: A, mag: M) -> Self {
+        let mut replay = Lsm303agrReplay {
+            accel_samples: accel,
+            mag_samples: mag,
+            accel: [0; ACCEL_REGISTERS],
+            mag: [0; MAG_REGISTERS],
+            accel_pending: None,
+            mag_pending: None,
+            accel_overrun: false,
+            mag_overrun: false,
+            accel_count: 0,
+            mag_count: 0,
+        };
+        replay.reset();
+        replay
+    }
+
+    /// Restores the register reset values. The sample streams are not rewound.
+    pub fn reset(&mut self) {
+        self.accel = [0; ACCEL_REGISTERS];
+        self.mag = [0; MAG_REGISTERS];
+        self.accel[WHO_AM_I_A as usize] = 0x33;
+        self.accel[CTRL_REG1_A as usize] = 0x07;
+        self.mag[WHO_AM_I_M as usize] = 0x40;
+        self.mag[CFG_REG_A_M as usize] = 0x03;
+    }
+
+    /// Value of an accelerometer register, as last written or latched.
+    pub fn accel_register(&self, register: u8) -> u8 {
+        self.accel[register as usize]
+    }
+
+    /// Value of a magnetometer register, as last written or latched.
+    pub fn mag_register(&self, register: u8) -> u8 {
+        self.mag[register as usize]
+    }
+
+    /// Number of accelerometer samples the driver has read so far.
+    pub fn accel_samples_read(&self) -> usize {
+        self.accel_count
+    }
+
+    /// Number of magnetometer samples the driver has read so far.
+    pub fn mag_samples_read(&self) -> usize {
+        self.mag_count
+    }
+
+    /// Makes the next sample of each stream available immediately, marking an overrun if
+    /// the previous one was never read. Use this to emulate a driver that polls too slowly.
+    pub fn tick(&mut self) {
+        if self.accel_enabled() {
+            let next = self.accel_samples.next();
+            if next.is_some() {
+                self.accel_overrun |= self.accel_pending.is_some();
+                self.accel_pending = next;
+            }
+        }
+        if self.mag_enabled() {
+            let next = self.mag_samples.next();
+            if next.is_some() {
+                self.mag_overrun |= self.mag_pending.is_some();
+                self.mag_pending = next;
+            }
+        }
+    }
+
+    /// Current accelerometer resolution.
+    pub fn accel_mode(&self) -> AccelMode {
+        if self.accel[CTRL_REG1_A as usize] & LPEN != 0 {
+            AccelMode::LowPower
+        } else if self.accel[CTRL_REG4_A as usize] & HR != 0 {
+            AccelMode::HighResolution
+        } else {
+            AccelMode::Normal
+        }
+    }
+
+    /// Current accelerometer full scale in g.
+    pub fn accel_full_scale(&self) -> u8 {
+        match (self.accel[CTRL_REG4_A as usize] >> 4) & 0b11 {
+            0b00 => 2,
+            0b01 => 4,
+            0b10 => 8,
+            _ => 16,
+        }
+    }
+
+    fn accel_enabled(&self) -> bool {
+        self.accel[CTRL_REG1_A as usize] >> 4 != 0
+    }
+
+    fn mag_enabled(&self) -> bool {
+        self.mag[CFG_REG_A_M as usize] & MD_MASK <= MD_SINGLE
+    }
+
+    /// Milli-g per digit, following table 3 of the datasheet.
+    fn accel_sensitivity(&self) -> i32 {
+        match (self.accel_mode(), self.accel_full_scale()) {
+            (AccelMode::HighResolution, 2) => 1,
+            (AccelMode::HighResolution, 4) => 2,
+            (AccelMode::HighResolution, 8) => 4,
+            (AccelMode::HighResolution, _) => 12,
+            (AccelMode::Normal, 2) => 4,
+            (AccelMode::Normal, 4) => 8,
+            (AccelMode::Normal, 8) => 16,
+            (AccelMode::Normal, _) => 48,
+            (AccelMode::LowPower, 2) => 16,
+            (AccelMode::LowPower, 4) => 32,
+            (AccelMode::LowPower, 8) => 64,
+            (AccelMode::LowPower, _) => 192,
+        }
+    }
+
+    fn accel_status(&mut self) -> u8 {
+        if self.accel_pending.is_none() && self.accel_enabled() {
+            self.accel_pending = self.accel_samples.next();
+        }
+        status(self.accel_pending.is_some(), self.accel_overrun)
+    }
+
+    fn mag_status(&mut self) -> u8 {
+        if self.mag_pending.is_none() && self.mag_enabled() {
+            self.mag_pending = self.mag_samples.next();
+        }
+        status(self.mag_pending.is_some(), self.mag_overrun)
+    }
+
+    fn latch_accel(&mut self) {
+        if let Some(sample) = self.accel_pending.take() {
+            let bits = self.accel_mode().bits();
+            let sensitivity = self.accel_sensitivity();
+            for (axis, value) in sample.iter().enumerate() {
+                let digits = clamp(value / sensitivity, bits);
+                // Output is left justified in the 16 bit register pair
+                let raw = (digits << (16 - bits)) as i16;
+                store_le(&mut self.accel, OUT_X_L_A as usize + 2 * axis, raw);
+            }
+            self.accel_overrun = false;
+            self.accel_count += 1;
+        }
+    }
+
+    fn latch_mag(&mut self) {
+        if let Some(sample) = self.mag_pending.take() {
+            for (axis, value) in sample.iter().enumerate() {
+                let raw = clamp(value / MAG_NT_PER_DIGIT, 16) as i16;
+                store_le(&mut self.mag, OUTX_L_REG_M as usize + 2 * axis, raw);
+            }
+            self.mag_overrun = false;
+            self.mag_count += 1;
+
+            // A single measurement puts the magnetometer back to idle
+            let cfg = &mut self.mag[CFG_REG_A_M as usize];
+            if *cfg & MD_MASK == MD_SINGLE {
+                *cfg = (*cfg & !MD_MASK) | MD_IDLE;
+            }
+        }
+    }
+
+    fn read(&mut self, address: u8, register: u8, buffer: &mut [u8]) -> Result<(), Error> {
+        let start = register & !AUTO_INCREMENT;
+        let len = buffer.len();
+        match address {
+            ACCEL_ADDR => {
+                check(address, start, len, ACCEL_REGISTERS)?;
+                if start == STATUS_REG_A {
+                    self.accel[STATUS_REG_A as usize] = self.accel_status();
+                }
+                if overlaps(start, len, OUT_X_L_A, OUT_Z_H_A) {
+                    self.latch_accel();
+                }
+                let start = start as usize;
+                buffer.copy_from_slice(&self.accel[start..start + buffer.len()]);
+            }
+            MAG_ADDR => {
+                check(address, start, len, MAG_REGISTERS)?;
+                if start == STATUS_REG_M {
+                    self.mag[STATUS_REG_M as usize] = self.mag_status();
+                }
+                if overlaps(start, len, OUTX_L_REG_M, OUTZ_H_REG_M) {
+                    self.latch_mag();
+                }
+                let start = start as usize;
+                buffer.copy_from_slice(&self.mag[start..start + buffer.len()]);
+            }
+            _ => return Err(Error::Nack(address)),
+        }
+        Ok(())
+    }
+}
+
+impl<A, M> Write for Lsm303agrReplay<A, M>
+where
+    A: Iterator<Item = [i32; 3]>,
+    M: Iterator<Item = [i32; 3]>,
+{
+    type Error = Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Error> {
+        let (register, values) = bytes.split_first().ok_or(Error::MissingRegister)?;
+        let start = register & !AUTO_INCREMENT;
+        let registers: &mut [u8] = match address {
+            ACCEL_ADDR => &mut self.accel,
+            MAG_ADDR => &mut self.mag,
+            _ => return Err(Error::Nack(address)),
+        };
+        check(address, start, values.len(), registers.len())?;
+
+        let start = start as usize;
+        registers[start..start + values.len()].copy_from_slice(values);
+
+        // Changing the mode drops the sample that was measured with the previous settings
+        if address == MAG_ADDR
+            && start == CFG_REG_A_M as usize
+            && self.mag[CFG_REG_A_M as usize] & MD_MASK == MD_CONTINUOUS
+        {
+            self.mag_pending = None;
+        }
+        Ok(())
+    }
+}
+
+impl<A, M> WriteRead for Lsm303agrReplay<A, M>
+where
+    A: Iterator<Item = [i32; 3]>,
+    M: Iterator<Item = [i32; 3]>,
+{
+    type Error = Error;
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Error> {
+        match bytes {
+            [register] => self.read(address, *register, buffer),
+            [] => Err(Error::MissingRegister),
+            [register, ..] => Err(Error::InvalidRegister {
+                address,
+                register: *register,
+            }),
+        }
+    }
+}
+
+fn status(new_data: bool, overrun: bool) -> u8 {
+    let mut status = 0;
+    if new_data {
+        status |= ZYXDA;
+    }
+    if overrun {
+        status |= ZYXOR;
+    }
+    status
+}
+
+/// Rejects transfers that run past the last register, however long they are.
+fn check(address: u8, start: u8, len: usize, registers: usize) -> Result<(), Error> {
+    if start as usize + len > registers {
+        Err(Error::InvalidRegister {
+            address,
+            register: start,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+fn overlaps(start: u8, len: usize, first: u8, last: u8) -> bool {
+    len > 0 && start <= last && start as usize + len > first as usize
+}
+
+fn clamp(digits: i32, bits: u32) -> i32 {
+    let max = (1 << (bits - 1)) - 1;
+    digits.max(-max - 1).min(max)
+}
+
+fn store_le(registers: &mut [u8], index: usize, value: i16) {
+    let [low, high] = value.to_le_bytes();
+    registers[index] = low;
+    registers[index + 1] = high;
+}
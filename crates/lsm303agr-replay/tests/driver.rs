@@ -0,0 +1,221 @@
+//! The `lsm303agr` driver, and the code of the chapters built on it, against the replay.
+
+use compass_calibration::{calibrate, Collector, Vector};
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use lsm303agr::{AccelOutputDataRate, AccelScale, Lsm303agr, MagOutputDataRate};
+use lsm303agr_replay::{AccelMode, Error, Lsm303agrReplay, ACCEL_ADDR, MAG_ADDR};
+use punch::{DetectorConfig, Event, Meter};
+use test_support::{punch, random_rotation, rotate, round, Random, EARTH, GRAVITY};
+
+const CTRL_REG1_A: u8 = 0x20;
+const CTRL_REG4_A: u8 = 0x23;
+const CFG_REG_A_M: u8 = 0x60;
+const CFG_REG_C_M: u8 = 0x62;
+
+const HARD_IRON: [i32; 3] = [12_000, -7_000, 3_000];
+
+fn still() -> impl Iterator<Item = [i32; 3]> {
+    core::iter::repeat([0, 0, 1000])
+}
+
+#[test]
+fn init_configures_the_sensor() {
+    let mut sensor = Lsm303agr::new_with_i2c(Lsm303agrReplay::new(still(), still()));
+    sensor.init().unwrap();
+    assert!(sensor.accelerometer_id().unwrap().is_correct());
+    assert!(sensor.magnetometer_id().unwrap().is_correct());
+    sensor.set_accel_odr(AccelOutputDataRate::Hz50).unwrap();
+    sensor.set_mag_odr(MagOutputDataRate::Hz10).unwrap();
+    let sensor = sensor.into_mag_continuous().ok().unwrap();
+
+    let replay = sensor.destroy();
+    // ODR 50 Hz with all axes enabled
+    assert_eq!(replay.accel_register(CTRL_REG1_A), 0b0100_0111);
+    // Block data update
+    assert_ne!(replay.accel_register(CTRL_REG4_A) & 0x80, 0);
+    assert_ne!(replay.mag_register(CFG_REG_C_M) & 0x10, 0);
+    // Continuous mode
+    assert_eq!(replay.mag_register(CFG_REG_A_M) & 0b11, 0b00);
+    assert_eq!(replay.accel_mode(), AccelMode::Normal);
+}
+
+#[test]
+fn driver_reads_the_samples() {
+    let accel = vec![[0, 0, 1000], [-500, 252, 980], [1996, -2000, 0]];
+    let mag = vec![[150, -300, 45_000], [-20_000, 1_500, -40_050]];
+    let mut sensor = Lsm303agr::new_with_i2c(Lsm303agrReplay::new(
+        accel.clone().into_iter(),
+        mag.clone().into_iter(),
+    ));
+    sensor.init().unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz100).unwrap();
+    sensor.set_mag_odr(MagOutputDataRate::Hz10).unwrap();
+    let mut sensor = sensor.into_mag_continuous().ok().unwrap();
+
+    for expected in accel.iter() {
+        assert!(sensor.accel_status().unwrap().xyz_new_data);
+        let data = sensor.accel_data().unwrap();
+        // Normal mode at +-2 g resolves 4 mg
+        for (actual, expected) in [data.x, data.y, data.z].iter().zip(expected.iter()) {
+            assert!(
+                (actual - expected).abs() < 4,
+                "{:?} != {:?}",
+                data,
+                expected
+            );
+        }
+    }
+    assert!(!sensor.accel_status().unwrap().xyz_new_data);
+
+    for expected in mag.iter() {
+        assert!(sensor.mag_status().unwrap().xyz_new_data);
+        let data = sensor.mag_data().unwrap();
+        for (actual, expected) in [data.x, data.y, data.z].iter().zip(expected.iter()) {
+            assert!(
+                (actual - expected).abs() < 150,
+                "{:?} != {:?}",
+                data,
+                expected
+            );
+        }
+    }
+    assert!(!sensor.mag_status().unwrap().xyz_new_data);
+
+    let replay = sensor.destroy();
+    assert_eq!(replay.accel_samples_read(), accel.len());
+    assert_eq!(replay.mag_samples_read(), mag.len());
+}
+
+#[test]
+fn scale_limits_the_range() {
+    let accel = vec![[30_000, -30_000, 1000]];
+    let mut sensor = Lsm303agr::new_with_i2c(Lsm303agrReplay::new(accel.into_iter(), still()));
+    sensor.init().unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz400).unwrap();
+    sensor.set_accel_scale(AccelScale::G16).unwrap();
+
+    assert!(sensor.accel_status().unwrap().xyz_new_data);
+    let data = sensor.accel_data().unwrap();
+    // Clamped to what fits into the 10 bits of normal mode at 48 mg per digit
+    assert!(data.x > 20_000 && data.x <= 511 * 48, "{:?}", data);
+    assert!(data.y < -20_000 && data.y >= -512 * 48, "{:?}", data);
+    assert!((data.z - 1000).abs() < 48, "{:?}", data);
+}
+
+#[test]
+fn rejects_transfers_past_the_last_register() {
+    let mut replay = Lsm303agrReplay::new(still(), still());
+
+    let mut buffer = [0; 300];
+    assert_eq!(
+        replay.write_read(ACCEL_ADDR, &[0x80 | 0x28], &mut buffer),
+        Err(Error::InvalidRegister {
+            address: ACCEL_ADDR,
+            register: 0x28
+        })
+    );
+    // 256 bytes would wrap around to an empty transfer if the length were truncated to a u8
+    let mut buffer = [0; 256];
+    assert!(replay.write_read(MAG_ADDR, &[0x68], &mut buffer).is_err());
+
+    let mut bytes = [0; 257];
+    bytes[0] = CFG_REG_A_M;
+    assert!(replay.write(MAG_ADDR, &bytes).is_err());
+    assert_eq!(replay.mag_register(CFG_REG_A_M), 0x03);
+}
+
+#[test]
+fn compass_calibration() {
+    // A board being turned around in every direction, with a magnet stuck to it
+    let mut random = Random::new(3);
+    let mut accel = Vec::new();
+    let mut mag = Vec::new();
+    for _ in 0..5_000 {
+        let r = random_rotation(&mut random, core::f32::consts::PI);
+        let m = round(rotate(&r, EARTH));
+        accel.push(round(rotate(&r, GRAVITY)));
+        mag.push([
+            m[0] + HARD_IRON[0],
+            m[1] + HARD_IRON[1],
+            m[2] + HARD_IRON[2],
+        ]);
+    }
+
+    let mut sensor =
+        Lsm303agr::new_with_i2c(Lsm303agrReplay::new(accel.into_iter(), mag.into_iter()));
+    sensor.init().unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz10).unwrap();
+    sensor.set_mag_odr(MagOutputDataRate::Hz10).unwrap();
+    let mut sensor = sensor.into_mag_continuous().ok().unwrap();
+
+    // What `calc_calibration` of the LED compass chapter does, minus the display
+    let mut collector = Collector::new();
+    while !collector.is_complete() {
+        assert!(
+            sensor.accel_status().unwrap().xyz_new_data,
+            "out of samples"
+        );
+        let a = sensor.accel_data().unwrap();
+        assert!(sensor.mag_status().unwrap().xyz_new_data);
+        let m = sensor.mag_data().unwrap();
+        if collector.update(Vector::new(a.x, a.y, a.z)) {
+            collector.add(Vector::new(m.x, m.y, m.z));
+        }
+    }
+    let calibration = calibrate(collector.samples().unwrap(), 1);
+
+    // The calibration works in the east, north, up frame
+    let center = calibration.center();
+    let expected = [-HARD_IRON[1], -HARD_IRON[0], HARD_IRON[2]];
+    for (actual, expected) in [center.x, center.y, center.z].iter().zip(expected.iter()) {
+        assert!(
+            (actual - expected).abs() < 2_000,
+            "{:?} != {:?}",
+            center,
+            expected
+        );
+    }
+    assert!(
+        calibration.quality().is_good(),
+        "{:?}",
+        calibration.quality()
+    );
+}
+
+/// 400 Hz samples of a board lying on the table that is hit once, peaking at about 6 g.
+fn punch_trace() -> Vec<[i32; 3]> {
+    let mut trace = vec![[10, -20, 1000]; 200];
+    trace.extend(punch(6_000));
+    trace.resize(400, [10, -20, 1000]);
+    trace
+}
+
+#[test]
+fn punch_o_meter() {
+    let trace = punch_trace();
+    let mut sensor = Lsm303agr::new_with_i2c(Lsm303agrReplay::new(trace.into_iter(), still()));
+    sensor.init().unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz400).unwrap();
+    sensor.set_accel_scale(AccelScale::G16).unwrap();
+
+    let mut meter = Meter::new(DetectorConfig::default());
+    let mut events = Vec::new();
+    let mut timestamp_us: u32 = 0;
+    while sensor.accel_status().unwrap().xyz_new_data {
+        let data = sensor.accel_data().unwrap();
+        if let Some(event) = meter.update(timestamp_us, [data.x, data.y, data.z]) {
+            events.push(event);
+        }
+        timestamp_us += 2_500;
+    }
+
+    assert_eq!(events.len(), 2, "{:?}", events);
+    assert_eq!(events[0], Event::Started);
+    match events[1] {
+        Event::Finished(impact) => {
+            assert!(impact.peak_g > 5.5 && impact.peak_g < 6.5, "{:?}", impact);
+            assert!(impact.duration_us < 50_000, "{:?}", impact);
+        }
+        event => panic!("unexpected {:?}", event),
+    }
+}
@@ -0,0 +1,179 @@
+//! Interrupt driven sampling of the LSM303AGR on the micro:bit v2.
+//!
+//! Instead of spinning on the status registers, the accelerometer is told to pull its
+//! interrupt line low whenever a new reading is available. On the micro:bit v2 that line is
+//! wired to P0.25, which we watch with a GPIOTE channel. The `GPIOTE` interrupt handler only
+//! takes note of the edge; the I2C transfers happen in [`Sampler::accel`], outside of any
+//! interrupt, which sleeps with `wfi` until there is something to read.
+//!
+//! The handler has to be installed by the application, since an interrupt handler in a library
+//! is only linked in if something else in its object file happens to be used:
+//!
+//! ``` ignore
+//! use microbit::pac::interrupt;
+//!
+//! #[interrupt]
+//! fn GPIOTE() {
+//!     lsm303agr_sampler::handle_interrupt();
+//! }
+//! ```
+
+#![no_std]
+
+use core::cell::{Cell, RefCell};
+
+use cortex_m::interrupt::Mutex;
+use embedded_hal::blocking::i2c::Write;
+use embedded_hal::digital::v2::InputPin;
+use lsm303agr::{interface::I2cInterface, mode::MagContinuous, Lsm303agr, Measurement};
+use microbit::{
+    hal::{
+        gpio::{p0::P0_25, Disconnected, Input, Pin, PullUp},
+        gpiote::Gpiote,
+        twim::{self, Twim},
+    },
+    pac::{Interrupt, GPIOTE, NVIC, TWIM0},
+};
+
+/// The sensor on the internal I2C bus, with the magnetometer in `MODE`.
+pub type Sensor<MODE> = Lsm303agr<I2cInterface<Twim<TWIM0>>, MODE>;
+
+/// What the sensor reports when a transfer fails.
+pub type Error = lsm303agr::Error<twim::Error, ()>;
+
+const ACCELEROMETER_ADDR: u8 = 0b0011001;
+const CTRL_REG3_A: u8 = 0x22;
+const CTRL_REG6_A: u8 = 0x25;
+/// Route the accelerometer data ready signal to the interrupt pin.
+const I1_ZYXDA: u8 = 1 << 4;
+/// Drive the interrupt pin low while the interrupt is active.
+const H_LACTIVE: u8 = 1 << 1;
+
+/// The GPIOTE peripheral, shared with the interrupt handler.
+static GPIOTE_HANDLE: Mutex<RefCell<Option<Gpiote>>> = Mutex::new(RefCell::new(None));
+/// Set by the interrupt handler on every falling edge of the data ready line.
+static DATA_READY: Mutex<Cell<bool>> = Mutex::new(Cell::new(false));
+
+/// Enables the data ready interrupt of the accelerometer.
+///
+/// Call this before handing the bus to `Lsm303agr::new_with_i2c`.
+pub fn configure_data_ready(i2c: &mut Twim<TWIM0>) -> Result<(), twim::Error> {
+    i2c.write(ACCELEROMETER_ADDR, &[CTRL_REG3_A, I1_ZYXDA])?;
+    i2c.write(ACCELEROMETER_ADDR, &[CTRL_REG6_A, H_LACTIVE])
+}
+
+/// Body of the `GPIOTE` interrupt handler, see the crate documentation.
+pub fn handle_interrupt() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(gpiote) = GPIOTE_HANDLE.borrow(cs).borrow().as_ref() {
+            if gpiote.channel0().is_event_triggered() {
+                DATA_READY.borrow(cs).set(true);
+            }
+            gpiote.reset_events();
+        }
+    });
+}
+
+/// Reads the sensor whenever it signals new data.
+///
+/// Only one `Sampler` can exist since it owns the `GPIOTE` peripheral.
+pub struct Sampler<MODE> {
+    sensor: Sensor<MODE>,
+    pin: Pin<Input<PullUp>>,
+}
+
+impl<MODE> Sampler<MODE> {
+    /// Takes over the sensor and starts watching its interrupt line. The sensor must already
+    /// be configured, including its output data rates, and [`configure_data_ready`] must have
+    /// been called.
+    pub fn new(
+        mut sensor: Sensor<MODE>,
+        gpiote: GPIOTE,
+        pin: P0_25<Disconnected>,
+    ) -> Result<Sampler<MODE>, Error> {
+        let pin = pin.into_pullup_input().degrade();
+        let gpiote = Gpiote::new(gpiote);
+        gpiote
+            .channel0()
+            .input_pin(&pin)
+            .hi_to_lo()
+            .enable_interrupt();
+
+        // The line only has an edge once it was released, which happens when the
+        // pending reading is taken.
+        sensor.accel_data()?;
+        gpiote.reset_events();
+
+        cortex_m::interrupt::free(|cs| {
+            GPIOTE_HANDLE.borrow(cs).replace(Some(gpiote));
+            DATA_READY.borrow(cs).set(false);
+        });
+
+        // Safe, the handler only touches state behind a critical section
+        unsafe {
+            NVIC::unmask(Interrupt::GPIOTE);
+        }
+
+        Ok(Sampler { sensor, pin })
+    }
+
+    /// Blocks until the next accelerometer reading and returns it.
+    pub fn accel(&mut self) -> Result<Measurement, Error> {
+        self.wait();
+        self.sensor.accel_data()
+    }
+
+    /// Throws away the reading that is waiting to be taken, if any.
+    pub fn clear(&mut self) -> Result<(), Error> {
+        cortex_m::interrupt::free(|cs| DATA_READY.borrow(cs).set(false));
+        self.sensor.accel_data().map(|_| ())
+    }
+
+    /// Sleeps until the accelerometer has new data.
+    fn wait(&self) {
+        loop {
+            // Checking for data and going to sleep happens with interrupts disabled, so an
+            // edge in between can not be missed: a pending interrupt still wakes up `wfi`,
+            // and the handler runs once we leave the critical section.
+            let ready = cortex_m::interrupt::free(|cs| {
+                // The line itself stays low until the data is read, which also covers a
+                // reading whose edge was consumed by `clear` or a failed transfer
+                let edge = DATA_READY.borrow(cs).replace(false);
+                let ready = edge || self.pin.is_low() == Ok(true);
+                if !ready {
+                    cortex_m::asm::wfi();
+                }
+                ready
+            });
+            if ready {
+                return;
+            }
+        }
+    }
+}
+
+impl Sampler<MagContinuous> {
+    /// Blocks until the next magnetometer reading and returns it.
+    ///
+    /// The magnetometer has no interrupt line of its own on the micro:bit, so its status is
+    /// checked every time the accelerometer has new data. Its output data rate must
+    /// therefore not be higher than the accelerometer's, and accelerometer readings that
+    /// arrive while waiting are dropped.
+    pub fn mag(&mut self) -> Result<Measurement, Error> {
+        loop {
+            if self.sensor.mag_status()?.xyz_new_data {
+                return self.sensor.mag_data();
+            }
+            self.wait();
+            self.sensor.accel_data()?;
+        }
+    }
+
+    /// Blocks until the next accelerometer reading and returns it together with the newest
+    /// magnetometer reading. With both sensors at the same output data rate that is a new
+    /// reading of each on every call.
+    pub fn accel_and_mag(&mut self) -> Result<(Measurement, Measurement), Error> {
+        let accel = self.accel()?;
+        Ok((accel, self.sensor.mag_data()?))
+    }
+}
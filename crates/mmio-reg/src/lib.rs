@@ -0,0 +1,333 @@
+//! Typed access to memory mapped registers, the step between `*(0x4800_1018 as *mut u32) = ..`
+//! and a full PAC.
+//!
+//! A `Reg` knows three things the raw pointer doesn't:
+//!
+//! - that every access has to be volatile, so the compiler doesn't merge or drop any of them;
+//! - whether the register can be read, written or both, as the `RO`, `WO` and `RW` markers;
+//! - that its address is aligned and inside the memory map, which `Reg::new` checks.
+//!
+//! Creating a `Reg` is `unsafe`, as only the caller knows what sits at the address. Using one is
+//! safe. `Bsrr` builds on it to set and reset GPIO pins by number.
+//!
+//! The accesses go through the `Memory` trait: `Volatile` on the microcontroller, and a
+//! `MemoryMock` on a computer, which logs them, so that what the code does to the registers can
+//! be checked without a board:
+//!
+//! ```
+//! use mmio_reg::{Bsrr, Event, MemoryMock, Reg, RW, WO};
+//!
+//! const GPIOE: core::ops::Range<usize> = 0x4800_1000..0x4800_1400;
+//! const GPIOE_ODR: usize = 0x4800_1014;
+//! const GPIOE_BSRR: usize = 0x4800_1018;
+//!
+//! let memory: MemoryMock<16> = MemoryMock::new(&[GPIOE]);
+//! // Writes to BSRR show up in ODR, like on the hardware
+//! memory.set_reset(GPIOE_BSRR, GPIOE_ODR);
+//!
+//! let (odr, mut bsrr) = unsafe {
+//!     let odr: Reg<u32, RW, _> = Reg::new(GPIOE_ODR, &memory).unwrap();
+//!     let bsrr: Reg<u32, WO, _> = Reg::new(GPIOE_BSRR, &memory).unwrap();
+//!     (odr, Bsrr::new(bsrr))
+//! };
+//!
+//! bsrr.set(9);
+//! assert_eq!(odr.read(), 1 << 9);
+//! bsrr.set(11);
+//! bsrr.reset(9);
+//! bsrr.reset(11);
+//! assert_eq!(odr.read(), 0);
+//!
+//! // Four writes, none of them merged
+//! let writes = memory
+//!     .events()
+//!     .into_iter()
+//!     .filter_map(|event| match event {
+//!         Event::Write { address: GPIOE_BSRR, value } => Some(value),
+//!         _ => None,
+//!     })
+//!     .collect::<Vec<_>>();
+//! assert_eq!(writes, [1 << 9, 1 << 11, 1 << (9 + 16), 1 << (11 + 16)]);
+//! ```
+//!
+//! Read-only registers can't be written, and write-only ones can't be read:
+//!
+//! ``` compile_fail
+//! # use mmio_reg::{Reg, Volatile, WO};
+//! let bsrr: Reg<u32, WO> = unsafe { Reg::new(0x4800_1018, Volatile::new(&[])).unwrap() };
+//! bsrr.read(); // error: `WO` isn't `Readable`
+//! ```
+
+#![no_std]
+
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::Range;
+use core::ptr;
+
+mod mock;
+
+pub use mock::{Event, MemoryMock};
+
+/// Read-only register.
+pub struct RO;
+/// Write-only register.
+pub struct WO;
+/// Read-write register.
+pub struct RW;
+
+/// Access markers that allow reading.
+pub trait Readable {}
+/// Access markers that allow writing.
+pub trait Writable {}
+
+impl Readable for RO {}
+impl Readable for RW {}
+impl Writable for WO {}
+impl Writable for RW {}
+
+/// The widths a register can have.
+pub trait Word: Copy {
+    fn to_u32(self) -> u32;
+    /// Truncates `value` to the width of the word.
+    fn from_u32(value: u32) -> Self;
+}
+
+impl Word for u8 {
+    fn to_u32(self) -> u32 {
+        u32::from(self)
+    }
+
+    fn from_u32(value: u32) -> Self {
+        value as u8
+    }
+}
+
+impl Word for u16 {
+    fn to_u32(self) -> u32 {
+        u32::from(self)
+    }
+
+    fn from_u32(value: u32) -> Self {
+        value as u16
+    }
+}
+
+impl Word for u32 {
+    fn to_u32(self) -> u32 {
+        self
+    }
+
+    fn from_u32(value: u32) -> Self {
+        value
+    }
+}
+
+/// Where the registers live.
+pub trait Memory {
+    /// Whether the `size` bytes at `address` are mapped to something.
+    fn is_mapped(&self, address: usize, size: usize) -> bool;
+
+    /// # Safety
+    ///
+    /// `address` has to be mapped, aligned, and readable with an access of `T`'s width.
+    unsafe fn read<T: Word>(&self, address: usize) -> T;
+
+    /// # Safety
+    ///
+    /// `address` has to be mapped, aligned, and writable with an access of `T`'s width.
+    unsafe fn write<T: Word>(&self, address: usize, value: T);
+}
+
+impl<M> Memory for &M
+where
+    M: Memory,
+{
+    fn is_mapped(&self, address: usize, size: usize) -> bool {
+        (**self).is_mapped(address, size)
+    }
+
+    unsafe fn read<T: Word>(&self, address: usize) -> T {
+        (**self).read(address)
+    }
+
+    unsafe fn write<T: Word>(&self, address: usize, value: T) {
+        (**self).write(address, value)
+    }
+}
+
+/// The actual memory, accessed with `read_volatile` and `write_volatile`.
+#[derive(Clone, Copy)]
+pub struct Volatile {
+    map: &'static [Range<usize>],
+}
+
+impl Volatile {
+    /// `map` lists the address ranges where there's something to access.
+    pub const fn new(map: &'static [Range<usize>]) -> Volatile {
+        Volatile { map }
+    }
+}
+
+impl Memory for Volatile {
+    fn is_mapped(&self, address: usize, size: usize) -> bool {
+        in_map(self.map, address, size)
+    }
+
+    unsafe fn read<T: Word>(&self, address: usize) -> T {
+        ptr::read_volatile(address as *const T)
+    }
+
+    unsafe fn write<T: Word>(&self, address: usize, value: T) {
+        ptr::write_volatile(address as *mut T, value)
+    }
+}
+
+/// Whether the `size` bytes at `address` all fall in one of the ranges of `map`.
+fn in_map(map: &[Range<usize>], address: usize, size: usize) -> bool {
+    // The end of the last bytes of the address space doesn't fit in a `usize`
+    map.iter().any(|range| {
+        range.start <= address
+            && address
+                .checked_add(size)
+                .is_some_and(|end| end <= range.end)
+    })
+}
+
+/// Why `Reg::new` refused an address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressError {
+    /// The address isn't a multiple of the register's size. The processor raises a fault.
+    Misaligned,
+    /// Nothing is mapped there. The bus raises a fault, like with `0x4800_1800`.
+    NotMapped,
+}
+
+/// A `T` wide register at a fixed address, with access `A`.
+pub struct Reg<T, A, M = Volatile> {
+    address: usize,
+    memory: M,
+    _marker: PhantomData<(T, A)>,
+}
+
+impl<T, A, M> Reg<T, A, M>
+where
+    T: Word,
+    M: Memory,
+{
+    /// Checks that `address` can hold a register of `T` and wraps it.
+    ///
+    /// ```
+    /// use mmio_reg::{AddressError, Reg, Volatile, RW};
+    ///
+    /// static MAP: [core::ops::Range<usize>; 1] = [0x4800_0000..0x4800_1800];
+    /// let memory = Volatile::new(&MAP);
+    ///
+    /// let new = |address| unsafe { Reg::<u32, RW>::new(address, memory) };
+    ///
+    /// assert!(new(0x4800_1014).is_ok());
+    /// assert_eq!(new(0x4800_1015).err(), Some(AddressError::Misaligned));
+    /// assert_eq!(new(0x4800_1800).err(), Some(AddressError::NotMapped));
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// The address has to be a register of `T`'s width that allows the accesses of `A`, and
+    /// accessing it can't break any other code, e.g. a driver that expects it to keep its value.
+    pub unsafe fn new(address: usize, memory: M) -> Result<Self, AddressError> {
+        let size = mem::size_of::<T>();
+        if !address.is_multiple_of(size) {
+            return Err(AddressError::Misaligned);
+        }
+        if !memory.is_mapped(address, size) {
+            return Err(AddressError::NotMapped);
+        }
+
+        Ok(Reg {
+            address,
+            memory,
+            _marker: PhantomData,
+        })
+    }
+
+    pub fn address(&self) -> usize {
+        self.address
+    }
+}
+
+impl<T, A, M> Reg<T, A, M>
+where
+    T: Word,
+    A: Readable,
+    M: Memory,
+{
+    pub fn read(&self) -> T {
+        // `new` checked the address, and `A` that the register can be read
+        unsafe { self.memory.read(self.address) }
+    }
+}
+
+impl<T, A, M> Reg<T, A, M>
+where
+    T: Word,
+    A: Writable,
+    M: Memory,
+{
+    pub fn write(&mut self, value: T) {
+        // `new` checked the address, and `A` that the register can be written
+        unsafe { self.memory.write(self.address, value) }
+    }
+}
+
+impl<T, M> Reg<T, RW, M>
+where
+    T: Word,
+    M: Memory,
+{
+    /// Reads the register, and writes back what `f` makes of the value.
+    pub fn modify<F>(&mut self, f: F)
+    where
+        F: FnOnce(T) -> T,
+    {
+        let value = self.read();
+        self.write(f(value));
+    }
+}
+
+/// A GPIO bit set/reset register: writing a 1 to bit `n` drives pin `n` high, to bit `n + 16`
+/// drives it low, and zeros leave the pins alone. That makes it a single write to change some
+/// pins without touching the others, no read-modify-write of `ODR` needed.
+pub struct Bsrr<M = Volatile> {
+    reg: Reg<u32, WO, M>,
+}
+
+impl<M> Bsrr<M>
+where
+    M: Memory,
+{
+    pub fn new(reg: Reg<u32, WO, M>) -> Self {
+        Bsrr { reg }
+    }
+
+    /// Drives pin `pin` high. Panics if `pin` is over 15.
+    pub fn set(&mut self, pin: u8) {
+        assert!(pin < 16);
+        self.reg.write(1 << pin);
+    }
+
+    /// Drives pin `pin` low. Panics if `pin` is over 15.
+    pub fn reset(&mut self, pin: u8) {
+        assert!(pin < 16);
+        self.reg.write(1 << (pin + 16));
+    }
+
+    /// Drives the pins of `set` high and those of `reset` low, in one write. A pin in both
+    /// ends up high.
+    pub fn set_reset(&mut self, set: u16, reset: u16) {
+        self.reg.write(u32::from(reset) << 16 | u32::from(set));
+    }
+
+    pub fn free(self) -> Reg<u32, WO, M> {
+        self.reg
+    }
+}
@@ -0,0 +1,123 @@
+//! A memory that lives in a map, to run register code on a computer.
+
+use core::cell::{Cell, RefCell};
+use core::mem;
+use core::ops::Range;
+
+use heapless::{LinearMap, Vec};
+
+use crate::{in_map, Memory, Word};
+
+/// An access to a `MemoryMock`, with the value read or written as a `u32`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Read { address: usize, value: u32 },
+    Write { address: usize, value: u32 },
+}
+
+/// Memory that reads back what was written, zero where nothing was, and logs every access.
+///
+/// It holds up to `N` words and logs up to `N` accesses, and panics beyond that.
+pub struct MemoryMock<const N: usize> {
+    map: &'static [Range<usize>],
+    /// Values of the 32-bit words, by address.
+    words: RefCell<LinearMap<usize, u32, N>>,
+    events: RefCell<Vec<Event, N>>,
+    /// `BSRR` and `ODR` addresses of a GPIO port, see `set_reset`.
+    set_reset: Cell<Option<(usize, usize)>>,
+}
+
+impl<const N: usize> MemoryMock<N> {
+    /// A memory with something at the addresses of `map`.
+    pub fn new(map: &'static [Range<usize>]) -> Self {
+        MemoryMock {
+            map,
+            words: RefCell::new(LinearMap::new()),
+            events: RefCell::new(Vec::new()),
+            set_reset: Cell::new(None),
+        }
+    }
+
+    /// Makes writes to `bsrr` set and reset bits of `odr` instead of being stored, like those
+    /// to a GPIO port's `BSRR` register. `bsrr` then reads as zero.
+    pub fn set_reset(&self, bsrr: usize, odr: usize) {
+        self.set_reset.set(Some((bsrr, odr)));
+    }
+
+    /// The accesses so far, oldest first.
+    pub fn events(&self) -> Vec<Event, N> {
+        self.events.borrow().clone()
+    }
+
+    fn log(&self, event: Event) {
+        self.events
+            .borrow_mut()
+            .push(event)
+            .expect("MemoryMock: event log full");
+    }
+
+    fn load(&self, address: usize) -> u32 {
+        self.words.borrow().get(&address).copied().unwrap_or(0)
+    }
+
+    fn store(&self, address: usize, word: u32) {
+        self.words
+            .borrow_mut()
+            .insert(address, word)
+            .expect("MemoryMock: out of words");
+    }
+}
+
+impl<const N: usize> Memory for MemoryMock<N> {
+    fn is_mapped(&self, address: usize, size: usize) -> bool {
+        in_map(self.map, address, size)
+    }
+
+    unsafe fn read<T: Word>(&self, address: usize) -> T {
+        let word = match self.set_reset.get() {
+            Some((bsrr, _)) if address & !0b11 == bsrr => 0,
+            _ => self.load(address & !0b11),
+        };
+        let value = T::from_u32(word >> shift(address));
+
+        self.log(Event::Read {
+            address,
+            value: value.to_u32(),
+        });
+        value
+    }
+
+    unsafe fn write<T: Word>(&self, address: usize, value: T) {
+        self.log(Event::Write {
+            address,
+            value: value.to_u32(),
+        });
+
+        let shift = shift(address);
+        let value = value.to_u32() << shift;
+        match self.set_reset.get() {
+            Some((bsrr, odr)) if address & !0b11 == bsrr => {
+                // Setting wins over resetting
+                let word = self.load(odr) & !(value >> 16) | value & 0xffff;
+                self.store(odr, word);
+            }
+            _ => {
+                let mask = mask::<T>() << shift;
+                let word = self.load(address & !0b11) & !mask | value;
+                self.store(address & !0b11, word);
+            }
+        }
+    }
+}
+
+/// Position of the byte at `address` in its 32-bit word, in bits.
+fn shift(address: usize) -> u32 {
+    (address as u32 & 0b11) * 8
+}
+
+fn mask<T>() -> u32 {
+    match mem::size_of::<T>() {
+        4 => !0,
+        size => (1 << (8 * size)) - 1,
+    }
+}
@@ -0,0 +1,248 @@
+//! Registers on top of the memory mock.
+
+use core::ops::Range;
+
+use mmio_reg::AddressError::{self, Misaligned, NotMapped};
+use mmio_reg::{Bsrr, Event, MemoryMock, Reg, RO, RW, WO};
+
+const PERIPHERAL: Range<usize> = 0x4000_0000..0x4000_0400;
+const MAP: [Range<usize>; 1] = [PERIPHERAL];
+
+const REG: usize = 0x4000_0010;
+const ODR: usize = 0x4000_0014;
+const BSRR: usize = 0x4000_0018;
+
+fn memory() -> MemoryMock<32> {
+    MemoryMock::new(&MAP)
+}
+
+fn reg<T, A>(address: usize, memory: &MemoryMock<32>) -> Reg<T, A, &MemoryMock<32>>
+where
+    T: mmio_reg::Word,
+{
+    unsafe { Reg::new(address, memory).unwrap() }
+}
+
+/// Why `Reg::new` refused the address, if it did.
+fn error<T>(result: Result<T, AddressError>) -> Option<AddressError> {
+    result.err()
+}
+
+#[test]
+fn reads_back_what_was_written() {
+    let memory = memory();
+    let mut r: Reg<u32, RW, _> = reg(REG, &memory);
+    assert_eq!(r.read(), 0);
+    r.write(0x1122_3344);
+    assert_eq!(r.read(), 0x1122_3344);
+    assert_eq!(
+        memory.events(),
+        [
+            Event::Read {
+                address: REG,
+                value: 0
+            },
+            Event::Write {
+                address: REG,
+                value: 0x1122_3344
+            },
+            Event::Read {
+                address: REG,
+                value: 0x1122_3344
+            },
+        ]
+    );
+}
+
+#[test]
+fn byte_lanes() {
+    let memory = memory();
+    reg::<u32, WO>(REG, &memory).write(0x1122_3344);
+    let bytes: Vec<u8> = (0..4)
+        .map(|lane| reg::<u8, RO>(REG + lane, &memory).read())
+        .collect();
+    // Little endian, like the Cortex-M
+    assert_eq!(bytes, [0x44, 0x33, 0x22, 0x11]);
+
+    reg::<u8, WO>(REG + 2, &memory).write(0xff);
+    assert_eq!(reg::<u32, RO>(REG, &memory).read(), 0x11ff_3344);
+    reg::<u8, WO>(REG + 3, &memory).write(0);
+    assert_eq!(reg::<u32, RO>(REG, &memory).read(), 0x00ff_3344);
+}
+
+#[test]
+fn halfword_lanes() {
+    let memory = memory();
+    reg::<u32, WO>(REG, &memory).write(0x1122_3344);
+    assert_eq!(reg::<u16, RO>(REG, &memory).read(), 0x3344);
+    assert_eq!(reg::<u16, RO>(REG + 2, &memory).read(), 0x1122);
+
+    reg::<u16, WO>(REG + 2, &memory).write(0xabcd);
+    assert_eq!(reg::<u32, RO>(REG, &memory).read(), 0xabcd_3344);
+    reg::<u16, WO>(REG, &memory).write(0);
+    assert_eq!(reg::<u32, RO>(REG, &memory).read(), 0xabcd_0000);
+
+    // The events have the address and the value of the access, not of the word
+    assert!(memory.events().contains(&Event::Write {
+        address: REG + 2,
+        value: 0xabcd
+    }));
+    assert!(memory.events().contains(&Event::Read {
+        address: REG + 2,
+        value: 0x1122
+    }));
+}
+
+#[test]
+fn words_are_separate() {
+    let memory = memory();
+    reg::<u32, WO>(REG, &memory).write(!0);
+    reg::<u8, WO>(REG + 4, &memory).write(0x5a);
+    assert_eq!(reg::<u32, RO>(REG, &memory).read(), !0);
+    assert_eq!(reg::<u32, RO>(REG + 4, &memory).read(), 0x5a);
+    assert_eq!(reg::<u32, RO>(REG - 4, &memory).read(), 0);
+}
+
+#[test]
+fn modify() {
+    let memory = memory();
+    let mut r: Reg<u32, RW, _> = reg(REG, &memory);
+    r.write(0b1010);
+    r.modify(|value| value & !0b10 | 0b1_0000);
+    assert_eq!(r.read(), 0b1_1000);
+    // One read and one write, nothing else
+    assert_eq!(
+        memory.events()[1..3],
+        [
+            Event::Read {
+                address: REG,
+                value: 0b1010
+            },
+            Event::Write {
+                address: REG,
+                value: 0b1_1000
+            },
+        ]
+    );
+}
+
+#[test]
+fn modify_a_byte() {
+    let memory = memory();
+    reg::<u32, WO>(REG, &memory).write(0x1122_3344);
+    let mut r: Reg<u8, RW, _> = reg(REG + 1, &memory);
+    r.modify(|value| value + 1);
+    assert_eq!(reg::<u32, RO>(REG, &memory).read(), 0x1122_3444);
+}
+
+#[test]
+fn set_reset() {
+    let memory = memory();
+    memory.set_reset(BSRR, ODR);
+    let odr: Reg<u32, RO, _> = reg(ODR, &memory);
+    let mut bsrr = Bsrr::new(reg(BSRR, &memory));
+
+    bsrr.set(0);
+    bsrr.set(15);
+    assert_eq!(odr.read(), 1 << 15 | 1);
+    bsrr.reset(0);
+    assert_eq!(odr.read(), 1 << 15);
+    bsrr.set_reset(0b0110, 1 << 15);
+    assert_eq!(odr.read(), 0b0110);
+
+    // BSRR itself reads as zero and doesn't keep what was written
+    assert_eq!(reg::<u32, RO>(BSRR, &memory).read(), 0);
+}
+
+#[test]
+fn set_wins_over_reset() {
+    let memory = memory();
+    memory.set_reset(BSRR, ODR);
+    let odr: Reg<u32, RO, _> = reg(ODR, &memory);
+    let mut bsrr = Bsrr::new(reg(BSRR, &memory));
+
+    bsrr.set_reset(0b11, 0b11);
+    assert_eq!(odr.read(), 0b11);
+    bsrr.set_reset(0b01, 0b11);
+    assert_eq!(odr.read(), 0b01);
+}
+
+#[test]
+fn set_reset_halves() {
+    let memory = memory();
+    memory.set_reset(BSRR, ODR);
+    let odr: Reg<u32, RO, _> = reg(ODR, &memory);
+
+    // The lower half sets, the upper half resets
+    reg::<u16, WO>(BSRR, &memory).write(0b1001);
+    assert_eq!(odr.read(), 0b1001);
+    reg::<u16, WO>(BSRR + 2, &memory).write(0b0001);
+    assert_eq!(odr.read(), 0b1000);
+}
+
+#[test]
+fn misaligned() {
+    let memory = memory();
+    unsafe {
+        assert_eq!(
+            error(Reg::<u32, RW, _>::new(REG + 1, &memory)),
+            Some(Misaligned)
+        );
+        assert_eq!(
+            error(Reg::<u32, RW, _>::new(REG + 2, &memory)),
+            Some(Misaligned)
+        );
+        assert_eq!(
+            error(Reg::<u16, RW, _>::new(REG + 1, &memory)),
+            Some(Misaligned)
+        );
+        assert_eq!(error(Reg::<u16, RW, _>::new(REG + 2, &memory)), None);
+        assert_eq!(error(Reg::<u8, RW, _>::new(REG + 3, &memory)), None);
+        // Checked before whether there's anything at the address
+        assert_eq!(error(Reg::<u32, RW, _>::new(1, &memory)), Some(Misaligned));
+    }
+    assert!(memory.events().is_empty());
+}
+
+#[test]
+fn not_mapped() {
+    let memory = memory();
+    let (start, end) = (PERIPHERAL.start, PERIPHERAL.end);
+    unsafe {
+        assert_eq!(error(Reg::<u32, RW, _>::new(end, &memory)), Some(NotMapped));
+        assert_eq!(
+            error(Reg::<u8, RW, _>::new(start - 1, &memory)),
+            Some(NotMapped)
+        );
+        assert_eq!(error(Reg::<u32, RW, _>::new(0, &memory)), Some(NotMapped));
+        assert_eq!(error(Reg::<u32, RW, _>::new(end - 4, &memory)), None);
+        assert_eq!(error(Reg::<u8, RW, _>::new(end - 1, &memory)), None);
+    }
+}
+
+#[test]
+fn not_mapped_across_the_end() {
+    const SHORT: Range<usize> = 0x4000_0000..0x4000_0002;
+    let memory: MemoryMock<4> = MemoryMock::new(&[SHORT]);
+    unsafe {
+        assert!(Reg::<u16, RW, _>::new(0x4000_0000, &memory).is_ok());
+        assert_eq!(
+            error(Reg::<u32, RW, _>::new(0x4000_0000, &memory)),
+            Some(NotMapped)
+        );
+    }
+}
+
+#[test]
+fn end_of_the_address_space() {
+    const TOP: Range<usize> = usize::MAX - 0xff..usize::MAX;
+    let memory: MemoryMock<4> = MemoryMock::new(&[TOP]);
+    unsafe {
+        assert!(Reg::<u32, RW, _>::new(usize::MAX - 7, &memory).is_ok());
+        // Its end would be `usize::MAX + 1`
+        assert_eq!(
+            error(Reg::<u32, RW, _>::new(usize::MAX - 3, &memory)),
+            Some(NotMapped)
+        );
+    }
+}
@@ -0,0 +1,30 @@
+//! Punch detection, independent of any board.
+//!
+//! Both the micro:bit and the F3 Discovery punch-o-meters feed their accelerometer samples,
+//! in milli-g, into a [`Meter`] and only differ in how they show the result. Nothing in here
+//! touches hardware, so the same code can also run on the host against recorded traces,
+//! e.g. the CSV files written by `sensor-log`.
+
+#![no_std]
+
+pub mod meter;
+pub mod output;
+pub mod signal;
+
+pub use meter::{Event, Meter, State};
+pub use output::{bar_graph, pitch_hz, MATRIX_LEDS};
+pub use signal::{DetectorConfig, Impact};
+
+/// Turns a peak acceleration into a level between `0` and `steps`, `full_scale_g` being the
+/// top of the scale, e.g. the number of LEDs to light up for it.
+pub fn level(peak_g: f32, full_scale_g: f32, steps: usize) -> usize {
+    let level = peak_g / full_scale_g * steps as f32;
+    if level <= 0.0 {
+        0
+    } else if level >= steps as f32 {
+        steps
+    } else {
+        // Show at least the first step for anything that was measured
+        (level as usize).max(1)
+    }
+}
@@ -0,0 +1,83 @@
+//! The punch-o-meter as a state machine.
+//!
+//! The [`Meter`] is fed one accelerometer sample at a time, together with the time it was
+//! taken at, and hands them to the [`ImpactDetector`] of the `signal` module.
+
+use crate::signal::{Detection, DetectorConfig, Impact, ImpactDetector};
+
+/// Where the punch-o-meter currently is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum State {
+    /// Waiting for an impact.
+    Armed,
+    /// A punch is in progress.
+    Measuring { started_at_us: u32 },
+    /// The punch is over. Stays here until [`Meter::rearm`] is called.
+    Result(Impact),
+}
+
+/// What happened while processing a sample.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    /// An impact started.
+    Started,
+    /// The impact is over.
+    Finished(Impact),
+    /// The impact went on for too long to be a punch, back to waiting for one.
+    Dropped,
+}
+
+pub struct Meter {
+    detector: ImpactDetector,
+    state: State,
+}
+
+impl Meter {
+    pub const fn new(config: DetectorConfig) -> Meter {
+        Meter {
+            detector: ImpactDetector::new(config),
+            state: State::Armed,
+        }
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    pub fn detector(&self) -> &ImpactDetector {
+        &self.detector
+    }
+
+    /// Goes back to waiting for the next punch.
+    pub fn rearm(&mut self) {
+        self.detector.reset();
+        self.state = State::Armed;
+    }
+
+    /// Processes one sample, `accel` being the acceleration on all three axes in milli-g.
+    ///
+    /// `timestamp_us` may wrap around; only differences between timestamps are used.
+    pub fn update(&mut self, timestamp_us: u32, accel: [i32; 3]) -> Option<Event> {
+        if let State::Result(_) = self.state {
+            return None;
+        }
+
+        match self.detector.update(timestamp_us, accel) {
+            Detection::Idle | Detection::Ongoing => None,
+            Detection::Onset => {
+                self.state = State::Measuring {
+                    started_at_us: timestamp_us,
+                };
+                Some(Event::Started)
+            }
+            Detection::Done(impact) => {
+                self.state = State::Result(impact);
+                Some(Event::Finished(impact))
+            }
+            Detection::Dropped => {
+                self.state = State::Armed;
+                Some(Event::Dropped)
+            }
+        }
+    }
+}
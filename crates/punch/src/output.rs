@@ -0,0 +1,27 @@
+//! Turning a finished impact into something to look at and listen to.
+//!
+//! The micro:bit punch-o-meter shows the result as a bar graph on its 5x5 LED matrix and
+//! beeps with a pitch that rises with the level.
+
+/// Number of LEDs on the micro:bit LED matrix, i.e. the steps of its bar graph.
+pub const MATRIX_LEDS: usize = 25;
+
+/// Pitch of the beep for the lowest level, in Hz.
+const BASE_PITCH_HZ: u32 = 440;
+/// How much every level raises the pitch, in Hz.
+const PITCH_STEP_HZ: u32 = 40;
+
+/// Lights `leds` LEDs, filling the matrix row by row from the bottom. Anything above
+/// [`MATRIX_LEDS`] lights the whole matrix.
+pub fn bar_graph(leds: usize) -> [[u8; 5]; 5] {
+    let mut matrix = [[0; 5]; 5];
+    for i in 0..leds.min(MATRIX_LEDS) {
+        matrix[4 - i / 5][i % 5] = 1;
+    }
+    matrix
+}
+
+/// Pitch of the beep for a level, in Hz: the harder the punch, the higher the pitch.
+pub fn pitch_hz(level: usize) -> u32 {
+    BASE_PITCH_HZ + PITCH_STEP_HZ * level as u32
+}
@@ -0,0 +1,251 @@
+//! Separating a punch from gravity.
+//!
+//! The accelerometer always measures `1g` of gravity on top of whatever the board is doing,
+//! split over the three axes depending on how it is held. Gravity changes slowly (when the
+//! board is tilted), a punch changes the acceleration within a few milliseconds. A low-pass
+//! filter therefore gives a good estimate of gravity, and subtracting that estimate leaves
+//! the *linear* acceleration caused by the punch.
+
+use libm::sqrtf;
+
+/// Exponential moving average of the acceleration, i.e. a first order low-pass filter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GravityFilter {
+    alpha: f32,
+    gravity: Option<[f32; 3]>,
+}
+
+impl GravityFilter {
+    /// `alpha` is the weight of every new sample, between 0 and 1. The smaller it is, the
+    /// slower the estimate follows changes of orientation.
+    pub const fn new(alpha: f32) -> GravityFilter {
+        GravityFilter {
+            alpha,
+            gravity: None,
+        }
+    }
+
+    /// Current gravity estimate in g, if any sample was seen.
+    pub fn gravity(&self) -> Option<[f32; 3]> {
+        self.gravity
+    }
+
+    /// Folds `accel`, in g, into the estimate.
+    pub fn update(&mut self, accel: [f32; 3]) {
+        let gravity = match self.gravity {
+            // Start from the first sample, a board at rest measures nothing but gravity
+            None => accel,
+            Some(gravity) => {
+                let mut next = gravity;
+                for (g, a) in next.iter_mut().zip(accel.iter()) {
+                    *g += self.alpha * (a - *g);
+                }
+                next
+            }
+        };
+        self.gravity = Some(gravity);
+    }
+
+    /// Throws the estimate away and starts over from `accel`, in g.
+    pub fn seed(&mut self, accel: [f32; 3]) {
+        self.gravity = Some(accel);
+    }
+
+    /// `accel`, in g, with the gravity estimate subtracted.
+    pub fn linear(&self, accel: [f32; 3]) -> [f32; 3] {
+        let gravity = self.gravity.unwrap_or([0.0; 3]);
+        [
+            accel[0] - gravity[0],
+            accel[1] - gravity[1],
+            accel[2] - gravity[2],
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DetectorConfig {
+    /// Weight of new samples in the gravity estimate.
+    pub gravity_alpha: f32,
+    /// Weight of new samples in the estimate of the noise level.
+    pub noise_alpha: f32,
+    /// How many times the noise level the linear acceleration has to exceed to be an impact.
+    pub sensitivity: f32,
+    /// Lower bound of the threshold in g, so that a perfectly still board doesn't trigger
+    /// on the least vibration.
+    pub min_threshold_g: f32,
+    /// An impact ends once the linear acceleration falls below this fraction of the
+    /// threshold it had to cross.
+    pub release_ratio: f32,
+    /// Longest impact, in microseconds. Anything longer is the board being moved around and
+    /// is dropped without a detection.
+    pub max_duration_us: u32,
+}
+
+impl Default for DetectorConfig {
+    fn default() -> DetectorConfig {
+        DetectorConfig {
+            gravity_alpha: 0.02,
+            noise_alpha: 0.05,
+            sensitivity: 8.0,
+            min_threshold_g: 0.5,
+            release_ratio: 0.5,
+            max_duration_us: 500_000,
+        }
+    }
+}
+
+/// A finished impact.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Impact {
+    /// Largest linear acceleration during the impact, in g.
+    pub peak_g: f32,
+    /// Time from crossing the threshold to falling back below the release level.
+    pub duration_us: u32,
+}
+
+/// What [`ImpactDetector::update`] found out about a sample.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Detection {
+    /// Nothing going on.
+    Idle,
+    /// The sample started a new impact.
+    Onset,
+    /// An impact is in progress.
+    Ongoing,
+    /// The impact is over.
+    Done(Impact),
+    /// What looked like an impact lasted longer than `max_duration_us`, so it wasn't one.
+    Dropped,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Idle,
+    Impact {
+        started_at_us: u32,
+        last_above_us: u32,
+        threshold_g: f32,
+        peak_g: f32,
+    },
+}
+
+/// Finds impacts in a stream of accelerometer samples.
+///
+/// The threshold adapts to how noisy the signal is: it is kept at `sensitivity` times an
+/// average of the linear acceleration seen while nothing is happening. Holding the board
+/// in a shaking hand raises it, lying on a table lowers it.
+pub struct ImpactDetector {
+    config: DetectorConfig,
+    gravity: GravityFilter,
+    noise_g: f32,
+    state: State,
+}
+
+impl ImpactDetector {
+    pub const fn new(config: DetectorConfig) -> ImpactDetector {
+        ImpactDetector {
+            gravity: GravityFilter::new(config.gravity_alpha),
+            config,
+            noise_g: 0.0,
+            state: State::Idle,
+        }
+    }
+
+    /// Threshold the linear acceleration currently has to cross, in g.
+    pub fn threshold(&self) -> f32 {
+        let adaptive = self.noise_g * self.config.sensitivity;
+        if adaptive > self.config.min_threshold_g {
+            adaptive
+        } else {
+            self.config.min_threshold_g
+        }
+    }
+
+    pub fn gravity(&self) -> &GravityFilter {
+        &self.gravity
+    }
+
+    /// Abandons the impact in progress, if any, keeping the gravity and noise estimates.
+    pub fn reset(&mut self) {
+        self.state = State::Idle;
+    }
+
+    /// Processes one sample, `accel` being the acceleration on all three axes in milli-g.
+    ///
+    /// `timestamp_us` may wrap around; only differences between timestamps are used.
+    pub fn update(&mut self, timestamp_us: u32, accel: [i32; 3]) -> Detection {
+        let accel = [
+            accel[0] as f32 / 1000.0,
+            accel[1] as f32 / 1000.0,
+            accel[2] as f32 / 1000.0,
+        ];
+        if self.gravity.gravity().is_none() {
+            self.gravity.update(accel);
+        }
+        let linear_g = norm(self.gravity.linear(accel));
+
+        match self.state {
+            State::Idle => {
+                let threshold_g = self.threshold();
+                if linear_g > threshold_g {
+                    self.state = State::Impact {
+                        started_at_us: timestamp_us,
+                        last_above_us: timestamp_us,
+                        threshold_g,
+                        peak_g: linear_g,
+                    };
+                    Detection::Onset
+                } else {
+                    // Only learn from quiet samples, an impact would drag both estimates
+                    // towards itself
+                    self.gravity.update(accel);
+                    self.noise_g += self.config.noise_alpha * (linear_g - self.noise_g);
+                    Detection::Idle
+                }
+            }
+            State::Impact {
+                started_at_us,
+                mut last_above_us,
+                threshold_g,
+                mut peak_g,
+            } => {
+                if linear_g > peak_g {
+                    peak_g = linear_g;
+                }
+                let released = linear_g < threshold_g * self.config.release_ratio;
+                if !released {
+                    last_above_us = timestamp_us;
+                }
+                let duration_us = last_above_us.wrapping_sub(started_at_us);
+
+                if released {
+                    self.state = State::Idle;
+                    Detection::Done(Impact {
+                        peak_g,
+                        duration_us,
+                    })
+                } else if duration_us >= self.config.max_duration_us {
+                    // The board was most likely tilted, which the estimate didn't follow while
+                    // it was frozen. Start over from where it is now, or the new orientation
+                    // would look like one impact after the other.
+                    self.gravity.seed(accel);
+                    self.state = State::Idle;
+                    Detection::Dropped
+                } else {
+                    self.state = State::Impact {
+                        started_at_us,
+                        last_above_us,
+                        threshold_g,
+                        peak_g,
+                    };
+                    Detection::Ongoing
+                }
+            }
+        }
+    }
+}
+
+/// Euclidean length of a vector.
+pub fn norm(v: [f32; 3]) -> f32 {
+    sqrtf(v[0] * v[0] + v[1] * v[1] + v[2] * v[2])
+}
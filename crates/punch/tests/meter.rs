@@ -0,0 +1,149 @@
+//! The punch-o-meter fed with synthetic 400 Hz traces, as the micro:bit samples them.
+
+use punch::{bar_graph, level, pitch_hz, DetectorConfig, Event, Impact, Meter, State};
+use test_support::{punch, rest, Random};
+
+const SAMPLE_PERIOD_US: u32 = 2_500;
+
+/// Feeds `trace` to `meter`, starting at `start_us`, and collects the events with the time
+/// they happened at.
+fn play(meter: &mut Meter, start_us: u32, trace: &[[i32; 3]]) -> Vec<(u32, Event)> {
+    let mut events = Vec::new();
+    for (i, accel) in trace.iter().enumerate() {
+        let timestamp_us = start_us.wrapping_add(i as u32 * SAMPLE_PERIOD_US);
+        if let Some(event) = meter.update(timestamp_us, *accel) {
+            events.push((timestamp_us, event));
+        }
+    }
+    events
+}
+
+fn finished(events: &[(u32, Event)]) -> Impact {
+    assert_eq!(events.len(), 2, "{:?}", events);
+    assert_eq!(events[0].1, Event::Started);
+    match events[1].1 {
+        Event::Finished(impact) => impact,
+        event => panic!("unexpected {:?}", event),
+    }
+}
+
+#[test]
+fn measures_a_punch() {
+    let mut random = Random::new(1);
+    let mut trace = rest(&mut random, 400);
+    trace.extend(punch(6_000));
+    trace.extend(rest(&mut random, 400));
+
+    let mut meter = Meter::new(DetectorConfig::default());
+    let events = play(&mut meter, 0, &trace);
+    let impact = finished(&events);
+
+    assert!(impact.peak_g > 5.5 && impact.peak_g < 6.5, "{:?}", impact);
+    assert!(impact.duration_us < 50_000, "{:?}", impact);
+    // Already the first sample of the punch crosses the threshold
+    assert_eq!(events[0].0, 400 * SAMPLE_PERIOD_US);
+    assert_eq!(meter.state(), State::Result(impact));
+}
+
+#[test]
+fn resting_board_stays_armed() {
+    let mut random = Random::new(2);
+    let trace = rest(&mut random, 4_000);
+
+    let mut meter = Meter::new(DetectorConfig::default());
+    assert_eq!(play(&mut meter, 0, &trace), vec![]);
+    assert_eq!(meter.state(), State::Armed);
+}
+
+#[test]
+fn shows_the_result_until_rearmed() {
+    let mut random = Random::new(3);
+    let mut trace = rest(&mut random, 400);
+    trace.extend(punch(4_000));
+    trace.extend(rest(&mut random, 100));
+
+    let mut meter = Meter::new(DetectorConfig::default());
+    let first = finished(&play(&mut meter, 0, &trace));
+
+    // Punches while the result is shown are ignored
+    let mut more = punch(8_000);
+    more.extend(rest(&mut random, 100));
+    assert_eq!(play(&mut meter, 1_000_000, &more), vec![]);
+    assert_eq!(meter.state(), State::Result(first));
+
+    meter.rearm();
+    assert_eq!(meter.state(), State::Armed);
+    let second = finished(&play(&mut meter, 2_000_000, &more));
+    assert!(
+        second.peak_g > first.peak_g * 1.5,
+        "{:?} {:?}",
+        first,
+        second
+    );
+}
+
+#[test]
+fn timestamps_may_wrap_around() {
+    let mut random = Random::new(4);
+    let mut trace = rest(&mut random, 400);
+    trace.extend(punch(6_000));
+    trace.extend(rest(&mut random, 100));
+
+    // The punch happens right when the clock wraps
+    let start_us = 0u32.wrapping_sub(400 * SAMPLE_PERIOD_US);
+    let mut meter = Meter::new(DetectorConfig::default());
+    let impact = finished(&play(&mut meter, start_us, &trace));
+    assert!(impact.duration_us < 50_000, "{:?}", impact);
+}
+
+#[test]
+fn harder_punches_light_more_leds() {
+    let mut previous = 0;
+    for &peak_mg in [2_000, 4_000, 8_000, 14_000].iter() {
+        let mut random = Random::new(5);
+        let mut trace = rest(&mut random, 400);
+        trace.extend(punch(peak_mg));
+        trace.extend(rest(&mut random, 100));
+
+        let mut meter = Meter::new(DetectorConfig::default());
+        let impact = finished(&play(&mut meter, 0, &trace));
+        let leds = level(impact.peak_g, 16.0, 25);
+        assert!(leds > previous, "{}mg lights {} LEDs", peak_mg, leds);
+        assert!(pitch_hz(leds) > pitch_hz(previous));
+        previous = leds;
+    }
+}
+
+#[test]
+fn level_is_clamped() {
+    assert_eq!(level(0.0, 16.0, 25), 0);
+    assert_eq!(level(-1.0, 16.0, 25), 0);
+    // Anything measured lights at least one LED
+    assert_eq!(level(0.1, 16.0, 25), 1);
+    assert_eq!(level(8.0, 16.0, 25), 12);
+    assert_eq!(level(16.0, 16.0, 25), 25);
+    assert_eq!(level(40.0, 16.0, 25), 25);
+}
+
+#[test]
+fn bar_graph_fills_from_the_bottom() {
+    assert_eq!(bar_graph(0), [[0; 5]; 5]);
+    assert_eq!(
+        bar_graph(7),
+        [
+            [0, 0, 0, 0, 0],
+            [0, 0, 0, 0, 0],
+            [0, 0, 0, 0, 0],
+            [1, 1, 0, 0, 0],
+            [1, 1, 1, 1, 1],
+        ]
+    );
+    assert_eq!(bar_graph(25), [[1; 5]; 5]);
+    assert_eq!(bar_graph(100), [[1; 5]; 5]);
+}
+
+#[test]
+fn pitch_rises_with_the_level() {
+    assert_eq!(pitch_hz(0), 440);
+    assert_eq!(pitch_hz(25), 1_440);
+}
@@ -0,0 +1,173 @@
+//! The impact detector against synthetic 400 Hz traces.
+
+use core::f32::consts::PI;
+
+use punch::signal::{Detection, DetectorConfig, GravityFilter, ImpactDetector};
+use test_support::Random;
+
+const SAMPLE_PERIOD_US: u32 = 2_500;
+const SAMPLE_RATE_HZ: f32 = 400.0;
+
+/// Runs `trace`, in g, through a detector with the default configuration.
+fn detect(trace: &[[f32; 3]]) -> (ImpactDetector, Vec<(usize, Detection)>) {
+    let mut detector = ImpactDetector::new(DetectorConfig::default());
+    let mut detections = Vec::new();
+    for (i, accel) in trace.iter().enumerate() {
+        let mg = [
+            (accel[0] * 1000.0) as i32,
+            (accel[1] * 1000.0) as i32,
+            (accel[2] * 1000.0) as i32,
+        ];
+        match detector.update(i as u32 * SAMPLE_PERIOD_US, mg) {
+            Detection::Idle | Detection::Ongoing => {}
+            detection => detections.push((i, detection)),
+        }
+    }
+    (detector, detections)
+}
+
+/// Gravity for a board tilted by `angle` radians around its y axis.
+fn tilted(angle: f32) -> [f32; 3] {
+    [angle.sin(), 0.0, angle.cos()]
+}
+
+fn flat(samples: usize) -> Vec<[f32; 3]> {
+    vec![tilted(0.0); samples]
+}
+
+#[test]
+fn clean_punch() {
+    let mut trace = flat(400);
+    // Along y, 8 g at its peak and 12.5 ms long
+    for &y in [2.0, 6.0, 8.0, 6.0, 2.0].iter() {
+        trace.push([0.0, y, 1.0]);
+    }
+    trace.extend(flat(400));
+
+    let (detector, detections) = detect(&trace);
+    assert_eq!(detections.len(), 2, "{:?}", detections);
+    assert_eq!(detections[0], (400, Detection::Onset));
+    match detections[1] {
+        (405, Detection::Done(impact)) => {
+            assert!((impact.peak_g - 8.0).abs() < 0.1, "{:?}", impact);
+            assert_eq!(impact.duration_us, 4 * SAMPLE_PERIOD_US);
+        }
+        detection => panic!("unexpected {:?}", detection),
+    }
+    // The punch didn't drag the gravity estimate along
+    let gravity = detector.gravity().gravity().unwrap();
+    assert!(gravity[1].abs() < 0.01, "{:?}", gravity);
+}
+
+#[test]
+fn punch_while_tilted() {
+    let gravity = tilted(PI / 3.0);
+    let mut trace = vec![gravity; 400];
+    for &x in [2.0, 5.0, 3.0].iter() {
+        trace.push([gravity[0] - x, gravity[1], gravity[2]]);
+    }
+    trace.extend(vec![gravity; 100]);
+
+    let (_, detections) = detect(&trace);
+    assert_eq!(detections.len(), 2, "{:?}", detections);
+    match detections[1].1 {
+        Detection::Done(impact) => assert!((impact.peak_g - 5.0).abs() < 0.1, "{:?}", impact),
+        detection => panic!("unexpected {:?}", detection),
+    }
+}
+
+#[test]
+fn slow_tilt() {
+    // Turned on its side over two seconds, then held there
+    let mut trace = flat(400);
+    let steps = 2 * SAMPLE_RATE_HZ as usize;
+    for i in 0..steps {
+        trace.push(tilted(PI / 2.0 * i as f32 / steps as f32));
+    }
+    trace.extend(vec![tilted(PI / 2.0); 800]);
+
+    let (detector, detections) = detect(&trace);
+    assert_eq!(detections, vec![]);
+    let gravity = detector.gravity().gravity().unwrap();
+    assert!((gravity[0] - 1.0).abs() < 0.01, "{:?}", gravity);
+}
+
+#[test]
+fn sudden_tilt_is_dropped() {
+    // Flipped on its side within 10 ms, faster than the gravity estimate follows
+    let mut trace = flat(400);
+    for i in 0..4 {
+        trace.push(tilted(PI / 2.0 * (i + 1) as f32 / 4.0));
+    }
+    trace.extend(vec![tilted(PI / 2.0); 2_000]);
+
+    let (detector, detections) = detect(&trace);
+    // It looks like an impact at first, but never calms down
+    assert_eq!(detections.len(), 2, "{:?}", detections);
+    let (onset_at, detection) = detections[0];
+    assert_eq!(detection, Detection::Onset);
+    let (dropped_at, detection) = detections[1];
+    assert_eq!(detection, Detection::Dropped);
+    let max_samples = (DetectorConfig::default().max_duration_us / SAMPLE_PERIOD_US) as usize;
+    assert_eq!(dropped_at - onset_at, max_samples);
+
+    // Gravity starts over from the new orientation instead of firing again and again
+    let gravity = detector.gravity().gravity().unwrap();
+    assert!((gravity[0] - 1.0).abs() < 0.01, "{:?}", gravity);
+}
+
+#[test]
+fn sustained_shake() {
+    // Shaken back and forth at 4 Hz with 1.5 g for ten seconds
+    let mut trace = flat(400);
+    let samples = 10 * SAMPLE_RATE_HZ as usize;
+    for i in 0..samples {
+        let t = i as f32 / SAMPLE_RATE_HZ;
+        trace.push([1.5 * (2.0 * PI * 4.0 * t).sin(), 0.0, 1.0]);
+    }
+
+    let (detector, detections) = detect(&trace);
+    // The threshold adapts, so only the first swings are taken for impacts
+    let last_second = 400 + samples - SAMPLE_RATE_HZ as usize;
+    assert!(
+        detections.iter().all(|(i, _)| *i < last_second),
+        "{:?}",
+        detections
+    );
+    assert!(detector.threshold() > 1.5, "{}", detector.threshold());
+}
+
+#[test]
+fn sensor_noise() {
+    let mut random = Random::new(1);
+    let trace: Vec<_> = (0..10 * SAMPLE_RATE_HZ as usize)
+        .map(|_| {
+            [
+                0.05 * random.uniform(),
+                0.05 * random.uniform(),
+                1.0 + 0.05 * random.uniform(),
+            ]
+        })
+        .collect();
+
+    let (detector, detections) = detect(&trace);
+    assert_eq!(detections, vec![]);
+    assert_eq!(
+        detector.threshold(),
+        DetectorConfig::default().min_threshold_g
+    );
+}
+
+#[test]
+fn gravity_filter_follows_slowly() {
+    let mut filter = GravityFilter::new(0.5);
+    assert_eq!(filter.gravity(), None);
+    filter.update([0.0, 0.0, 1.0]);
+    assert_eq!(filter.gravity(), Some([0.0, 0.0, 1.0]));
+    filter.update([1.0, 0.0, 1.0]);
+    assert_eq!(filter.gravity(), Some([0.5, 0.0, 1.0]));
+    assert_eq!(filter.linear([1.0, 0.0, 1.0]), [0.5, 0.0, 0.0]);
+
+    filter.seed([0.0, 1.0, 0.0]);
+    assert_eq!(filter.gravity(), Some([0.0, 1.0, 0.0]));
+}
@@ -0,0 +1,222 @@
+//! Framing of accelerometer and magnetometer samples for streaming over a serial line.
+//!
+//! Every [`Sample`] is serialized into a fixed size little endian payload, followed by a
+//! CRC-16/CCITT-FALSE of that payload. The result is COBS encoded, so it contains no zero
+//! bytes, and terminated with a single `0x00` delimiter. A receiver that joins the stream at
+//! an arbitrary point only has to wait for the next delimiter to be in sync.
+
+#![no_std]
+
+/// Size of a serialized [`Sample`].
+pub const PAYLOAD_LEN: usize = 4 + 3 * 4 + 3 * 4;
+
+/// Largest encoded frame: payload, CRC, COBS overhead and the delimiter.
+pub const MAX_FRAME_LEN: usize = PAYLOAD_LEN + 2 + 1 + 1;
+
+/// One reading of both sensors of the LSM303AGR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Sample {
+    /// Time the sample was taken at, in microseconds. Wraps after ~71 minutes.
+    pub timestamp_us: u32,
+    /// Acceleration in milli-g.
+    pub accel: [i32; 3],
+    /// Magnetic field in nanotesla.
+    pub mag: [i32; 3],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The frame is not valid COBS.
+    Cobs,
+    /// The frame has the wrong size for a sample.
+    Length,
+    /// The checksum does not match the payload.
+    Crc,
+}
+
+impl Sample {
+    pub fn to_bytes(&self) -> [u8; PAYLOAD_LEN] {
+        let mut bytes = [0; PAYLOAD_LEN];
+        bytes[0..4].copy_from_slice(&self.timestamp_us.to_le_bytes());
+        for (i, value) in self.accel.iter().chain(self.mag.iter()).enumerate() {
+            let start = 4 + i * 4;
+            bytes[start..start + 4].copy_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; PAYLOAD_LEN]) -> Sample {
+        let word = |i: usize| [bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]];
+        let value = |i: usize| i32::from_le_bytes(word(4 + i * 4));
+        Sample {
+            timestamp_us: u32::from_le_bytes(word(0)),
+            accel: [value(0), value(1), value(2)],
+            mag: [value(3), value(4), value(5)],
+        }
+    }
+
+    /// Encodes the sample into `frame`, delimiter included, and returns the frame length.
+    pub fn encode(&self, frame: &mut [u8; MAX_FRAME_LEN]) -> usize {
+        let mut raw = [0; PAYLOAD_LEN + 2];
+        raw[..PAYLOAD_LEN].copy_from_slice(&self.to_bytes());
+        let crc = crc16(&raw[..PAYLOAD_LEN]);
+        raw[PAYLOAD_LEN..].copy_from_slice(&crc.to_le_bytes());
+
+        let len = cobs_encode(&raw, frame);
+        frame[len] = 0;
+        len + 1
+    }
+
+    /// Decodes a frame without its delimiter.
+    pub fn decode(frame: &[u8]) -> Result<Sample, Error> {
+        let mut raw = [0; PAYLOAD_LEN + 2];
+        if frame.len() > MAX_FRAME_LEN - 1 {
+            return Err(Error::Length);
+        }
+        let len = cobs_decode(frame, &mut raw)?;
+        if len != raw.len() {
+            return Err(Error::Length);
+        }
+
+        let (payload, crc) = raw.split_at(PAYLOAD_LEN);
+        if crc16(payload).to_le_bytes() != crc {
+            return Err(Error::Crc);
+        }
+
+        let mut bytes = [0; PAYLOAD_LEN];
+        bytes.copy_from_slice(payload);
+        Ok(Sample::from_bytes(&bytes))
+    }
+}
+
+/// CRC-16/CCITT-FALSE: polynomial `0x1021`, initial value `0xFFFF`, no reflection.
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for byte in data {
+        crc ^= u16::from(*byte) << 8;
+        for _ in 0..8 {
+            if crc & 0x8000 != 0 {
+                crc = (crc << 1) ^ 0x1021;
+            } else {
+                crc <<= 1;
+            }
+        }
+    }
+    crc
+}
+
+/// COBS encodes `input` into `output` and returns the number of bytes written.
+///
+/// `output` must be at least `input.len() + input.len() / 254 + 1` bytes long.
+pub fn cobs_encode(input: &[u8], output: &mut [u8]) -> usize {
+    let mut code_index = 0;
+    let mut out = 1;
+    let mut code: u8 = 1;
+
+    for byte in input {
+        if *byte == 0 {
+            output[code_index] = code;
+            code_index = out;
+            out += 1;
+            code = 1;
+        } else {
+            output[out] = *byte;
+            out += 1;
+            code += 1;
+            if code == 0xFF {
+                output[code_index] = code;
+                code_index = out;
+                out += 1;
+                code = 1;
+            }
+        }
+    }
+    output[code_index] = code;
+    out
+}
+
+/// Decodes a COBS encoded block, without delimiter, into `output`.
+///
+/// Returns the number of decoded bytes.
+pub fn cobs_decode(input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+    let mut i = 0;
+    let mut out = 0;
+
+    while i < input.len() {
+        let code = input[i];
+        if code == 0 || i + code as usize > input.len() {
+            return Err(Error::Cobs);
+        }
+        i += 1;
+
+        for _ in 1..code {
+            if input[i] == 0 || out == output.len() {
+                return Err(Error::Cobs);
+            }
+            output[out] = input[i];
+            out += 1;
+            i += 1;
+        }
+
+        if code != 0xFF && i < input.len() {
+            if out == output.len() {
+                return Err(Error::Cobs);
+            }
+            output[out] = 0;
+            out += 1;
+        }
+    }
+    Ok(out)
+}
+
+/// Reassembles frames from a byte stream.
+pub struct FrameDecoder {
+    buffer: [u8; MAX_FRAME_LEN],
+    len: usize,
+    overflow: bool,
+}
+
+impl Default for FrameDecoder {
+    fn default() -> FrameDecoder {
+        FrameDecoder::new()
+    }
+}
+
+impl FrameDecoder {
+    pub const fn new() -> FrameDecoder {
+        FrameDecoder {
+            buffer: [0; MAX_FRAME_LEN],
+            len: 0,
+            overflow: false,
+        }
+    }
+
+    /// Feeds one byte of the stream.
+    ///
+    /// Returns `Some` whenever a delimiter completes a frame. Empty frames, as produced by
+    /// consecutive delimiters, are skipped.
+    pub fn push(&mut self, byte: u8) -> Option<Result<Sample, Error>> {
+        if byte != 0 {
+            if self.len < self.buffer.len() {
+                self.buffer[self.len] = byte;
+                self.len += 1;
+            } else {
+                self.overflow = true;
+            }
+            return None;
+        }
+
+        let len = self.len;
+        let overflow = self.overflow;
+        self.len = 0;
+        self.overflow = false;
+
+        if overflow {
+            Some(Err(Error::Length))
+        } else if len == 0 {
+            None
+        } else {
+            Some(Sample::decode(&self.buffer[..len]))
+        }
+    }
+}
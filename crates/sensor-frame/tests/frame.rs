@@ -0,0 +1,200 @@
+use sensor_frame::{
+    cobs_decode, cobs_encode, crc16, Error, FrameDecoder, Sample, MAX_FRAME_LEN, PAYLOAD_LEN,
+};
+
+fn sample() -> Sample {
+    Sample {
+        timestamp_us: 0x0102_0304,
+        accel: [-1000, 0, 1000],
+        mag: [i32::MIN, 256, i32::MAX],
+    }
+}
+
+fn encoded(sample: &Sample) -> Vec<u8> {
+    let mut frame = [0; MAX_FRAME_LEN];
+    let len = sample.encode(&mut frame);
+    frame[..len].to_vec()
+}
+
+/// Encodes and decodes `input`, checking the COBS invariants on the way.
+fn cobs_round_trip(input: &[u8]) {
+    let mut encoded = vec![0xAA; input.len() + input.len() / 254 + 1];
+    let len = cobs_encode(input, &mut encoded);
+    assert!(len <= encoded.len());
+    assert!(
+        !encoded[..len].contains(&0),
+        "zero in encoding of {:?}",
+        input
+    );
+
+    let mut decoded = vec![0; input.len()];
+    let decoded_len = cobs_decode(&encoded[..len], &mut decoded).unwrap();
+    assert_eq!(&decoded[..decoded_len], input);
+}
+
+#[test]
+fn crc16_check_value() {
+    // The check value of CRC-16/CCITT-FALSE
+    assert_eq!(crc16(b"123456789"), 0x29B1);
+    assert_eq!(crc16(&[]), 0xFFFF);
+}
+
+#[test]
+fn cobs_every_byte_value() {
+    for byte in 0..=255u8 {
+        cobs_round_trip(&[byte]);
+        cobs_round_trip(&[byte, byte]);
+    }
+    let all: Vec<u8> = (0..=255).collect();
+    cobs_round_trip(&all);
+    let reversed: Vec<u8> = (0..=255).rev().collect();
+    cobs_round_trip(&reversed);
+}
+
+#[test]
+fn cobs_around_block_length() {
+    // Runs of non-zero bytes are split into blocks of 254
+    for len in 250..=520 {
+        let run = vec![0x55; len];
+        cobs_round_trip(&run);
+        let mut with_zero = run.clone();
+        with_zero.push(0);
+        cobs_round_trip(&with_zero);
+    }
+}
+
+#[test]
+fn cobs_zero_heavy() {
+    cobs_round_trip(&[]);
+    for len in 1..=300 {
+        cobs_round_trip(&vec![0; len]);
+    }
+    let alternating: Vec<u8> = (0..300).map(|i| if i % 2 == 0 { 0 } else { 7 }).collect();
+    cobs_round_trip(&alternating);
+
+    let mut encoded = [0; 5];
+    let len = cobs_encode(&[0, 0, 0, 0], &mut encoded);
+    assert_eq!(&encoded[..len], &[1, 1, 1, 1, 1]);
+}
+
+#[test]
+fn cobs_rejects_invalid_input() {
+    let mut output = [0; 16];
+    // Zero inside a block
+    assert_eq!(cobs_decode(&[3, 1, 0], &mut output), Err(Error::Cobs));
+    // Code pointing past the end
+    assert_eq!(cobs_decode(&[5, 1, 2], &mut output), Err(Error::Cobs));
+    // Output too small
+    assert_eq!(
+        cobs_decode(&[4, 1, 2, 3], &mut output[..2]),
+        Err(Error::Cobs)
+    );
+}
+
+#[test]
+fn sample_round_trip() {
+    let samples = [
+        Sample::default(),
+        sample(),
+        Sample {
+            timestamp_us: u32::MAX,
+            accel: [-1, -1, -1],
+            mag: [0, 0, 0],
+        },
+    ];
+    for sample in samples.iter() {
+        assert_eq!(Sample::from_bytes(&sample.to_bytes()), *sample);
+
+        let frame = encoded(sample);
+        assert!(frame.len() <= MAX_FRAME_LEN);
+        assert_eq!(frame.last(), Some(&0));
+        assert!(!frame[..frame.len() - 1].contains(&0));
+        assert_eq!(Sample::decode(&frame[..frame.len() - 1]), Ok(*sample));
+    }
+}
+
+#[test]
+fn corrupted_crc() {
+    let sample = sample();
+    let mut raw = [0; PAYLOAD_LEN + 2];
+    raw[..PAYLOAD_LEN].copy_from_slice(&sample.to_bytes());
+    let crc = crc16(&raw[..PAYLOAD_LEN]) ^ 0x0100;
+    raw[PAYLOAD_LEN..].copy_from_slice(&crc.to_le_bytes());
+
+    let mut frame = [0; MAX_FRAME_LEN];
+    let len = cobs_encode(&raw, &mut frame);
+    assert_eq!(Sample::decode(&frame[..len]), Err(Error::Crc));
+}
+
+#[test]
+fn corrupted_payload() {
+    let mut frame = encoded(&sample());
+    // Any non-zero byte keeps the COBS structure intact as long as no code byte is hit
+    frame[5] ^= 0x10;
+    assert_eq!(Sample::decode(&frame[..frame.len() - 1]), Err(Error::Crc));
+}
+
+#[test]
+fn truncated_frame() {
+    let frame = encoded(&sample());
+    let body = &frame[..frame.len() - 1];
+    for len in 1..body.len() {
+        assert!(Sample::decode(&body[..len]).is_err(), "{} bytes", len);
+    }
+    assert_eq!(Sample::decode(&[]), Err(Error::Length));
+}
+
+#[test]
+fn oversized_frame() {
+    assert_eq!(Sample::decode(&[1; MAX_FRAME_LEN]), Err(Error::Length));
+}
+
+#[test]
+fn decoder_stream() {
+    let first = sample();
+    let second = Sample::default();
+    let mut stream = encoded(&first);
+    stream.push(0);
+    stream.extend(encoded(&second));
+
+    let mut decoder = FrameDecoder::new();
+    let results: Vec<_> = stream.iter().filter_map(|b| decoder.push(*b)).collect();
+    assert_eq!(results, vec![Ok(first), Ok(second)]);
+}
+
+#[test]
+fn decoder_truncated_frame() {
+    // The sender restarted in the middle of a frame
+    let frame = encoded(&sample());
+    let mut stream = frame[..10].to_vec();
+    stream.push(0);
+    stream.extend(&frame);
+
+    let mut decoder = FrameDecoder::new();
+    let results: Vec<_> = stream.iter().filter_map(|b| decoder.push(*b)).collect();
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_err());
+    assert_eq!(results[1], Ok(sample()));
+}
+
+#[test]
+fn decoder_resyncs_after_garbage() {
+    let sample = sample();
+    let mut decoder = FrameDecoder::new();
+
+    // Joining the stream in the middle of a frame
+    let frame = encoded(&sample);
+    for byte in &frame[7..] {
+        decoder.push(*byte);
+    }
+    let results: Vec<_> = frame.iter().filter_map(|b| decoder.push(*b)).collect();
+    assert_eq!(results, vec![Ok(sample)]);
+
+    // Line noise longer than any frame
+    for byte in (1..=255u8).cycle().take(3 * MAX_FRAME_LEN) {
+        assert_eq!(decoder.push(byte), None);
+    }
+    assert_eq!(decoder.push(0), Some(Err(Error::Length)));
+    let results: Vec<_> = frame.iter().filter_map(|b| decoder.push(*b)).collect();
+    assert_eq!(results, vec![Ok(sample)]);
+}
@@ -0,0 +1,121 @@
+//! Line settings of a serial port, shared by the F3 Discovery and the micro:bit code.
+//!
+//! Frames always carry 8 data bits; parity, when enabled, comes on top of them.
+//!
+//! ```
+//! use serial_config::{Parity, SerialConfig};
+//!
+//! // What the HC-05 Bluetooth module uses out of the box
+//! let hc05 = SerialConfig::baud(9_600);
+//! assert_eq!(hc05.parity, Parity::None);
+//!
+//! let even = SerialConfig {
+//!     parity: Parity::Even,
+//!     ..SerialConfig::default()
+//! };
+//! assert_eq!(even.baud_rate, 115_200);
+//!
+//! // What a `baud` command typed into a terminal accepts
+//! assert_eq!(serial_config::parse_baud_rate("38400"), Ok(38_400));
+//! assert!(serial_config::parse_baud_rate("12345").is_err());
+//! ```
+
+#![no_std]
+
+use core::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    None,
+    Even,
+    Odd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopBits {
+    One,
+    Two,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowControl {
+    None,
+    /// Hardware flow control with the RTS and CTS lines.
+    RtsCts,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SerialConfig {
+    /// In bits per second.
+    pub baud_rate: u32,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+    pub flow_control: FlowControl,
+}
+
+impl Default for SerialConfig {
+    /// 115200 bps, no parity, one stop bit and no flow control, which is what the book uses.
+    fn default() -> SerialConfig {
+        SerialConfig::baud(115_200)
+    }
+}
+
+impl SerialConfig {
+    /// No parity, one stop bit and no flow control at `baud_rate`.
+    pub const fn baud(baud_rate: u32) -> SerialConfig {
+        SerialConfig {
+            baud_rate,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            flow_control: FlowControl::None,
+        }
+    }
+}
+
+impl fmt::Display for SerialConfig {
+    /// Formats the settings the way terminal programs show them, e.g. `115200 8N1`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parity = match self.parity {
+            Parity::None => 'N',
+            Parity::Even => 'E',
+            Parity::Odd => 'O',
+        };
+        let stop_bits = match self.stop_bits {
+            StopBits::One => 1,
+            StopBits::Two => 2,
+        };
+        write!(f, "{} 8{}{}", self.baud_rate, parity, stop_bits)?;
+        if self.flow_control == FlowControl::RtsCts {
+            f.write_str(" RTS/CTS")?;
+        }
+        Ok(())
+    }
+}
+
+/// The baud rates both boards support and terminal programs offer.
+pub const BAUD_RATES: [u32; 12] = [
+    1_200, 2_400, 4_800, 9_600, 14_400, 19_200, 28_800, 38_400, 57_600, 115_200, 230_400, 460_800,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidBaudRate;
+
+impl fmt::Display for InvalidBaudRate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("unsupported baud rate, try one of")?;
+        for baud_rate in BAUD_RATES.iter() {
+            write!(f, " {}", baud_rate)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a baud rate typed in decimal, accepting only the ones in `BAUD_RATES`.
+pub fn parse_baud_rate(s: &str) -> Result<u32, InvalidBaudRate> {
+    let baud_rate = s.trim().parse().map_err(|_| InvalidBaudRate)?;
+    if BAUD_RATES.contains(&baud_rate) {
+        Ok(baud_rate)
+    } else {
+        Err(InvalidBaudRate)
+    }
+}
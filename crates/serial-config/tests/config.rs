@@ -0,0 +1,86 @@
+use serial_config::{
+    parse_baud_rate, FlowControl, InvalidBaudRate, Parity, SerialConfig, StopBits, BAUD_RATES,
+};
+
+#[test]
+fn every_listed_baud_rate_parses() {
+    for &baud_rate in BAUD_RATES.iter() {
+        assert_eq!(parse_baud_rate(&baud_rate.to_string()), Ok(baud_rate));
+    }
+}
+
+#[test]
+fn surrounding_whitespace_is_ignored() {
+    assert_eq!(parse_baud_rate(" 9600"), Ok(9_600));
+    assert_eq!(parse_baud_rate("115200\r\n"), Ok(115_200));
+    assert_eq!(parse_baud_rate("\t38400 "), Ok(38_400));
+    // Not in the middle, though
+    assert_eq!(parse_baud_rate("115 200"), Err(InvalidBaudRate));
+}
+
+#[test]
+fn unlisted_rates_are_rejected() {
+    // Valid numbers that aren't in `BAUD_RATES`, including rates one of the boards can do
+    for rate in ["0", "12345", "9601", "921600", "1000000"].iter() {
+        assert_eq!(parse_baud_rate(rate), Err(InvalidBaudRate), "{:?}", rate);
+    }
+}
+
+#[test]
+fn malformed_numbers_are_rejected() {
+    for rate in ["", "   ", "-9600", "9600bps", "0x2580", "9600.0", "fast"].iter() {
+        assert_eq!(parse_baud_rate(rate), Err(InvalidBaudRate), "{:?}", rate);
+    }
+}
+
+#[test]
+fn overflow_is_rejected() {
+    assert_eq!(parse_baud_rate("4294967296"), Err(InvalidBaudRate));
+    assert_eq!(
+        parse_baud_rate("99999999999999999999"),
+        Err(InvalidBaudRate)
+    );
+}
+
+#[test]
+fn error_lists_the_supported_rates() {
+    let message = InvalidBaudRate.to_string();
+    assert!(message.starts_with("unsupported baud rate, try one of 1200 2400"));
+    assert!(message.ends_with(" 230400 460800"), "{}", message);
+}
+
+#[test]
+fn display_default() {
+    assert_eq!(SerialConfig::default().to_string(), "115200 8N1");
+    assert_eq!(SerialConfig::baud(9_600).to_string(), "9600 8N1");
+}
+
+#[test]
+fn display_parity_and_stop_bits() {
+    let config = |parity, stop_bits| SerialConfig {
+        parity,
+        stop_bits,
+        ..SerialConfig::baud(19_200)
+    };
+    assert_eq!(config(Parity::Even, StopBits::One).to_string(), "19200 8E1");
+    assert_eq!(config(Parity::Odd, StopBits::One).to_string(), "19200 8O1");
+    assert_eq!(config(Parity::None, StopBits::Two).to_string(), "19200 8N2");
+    assert_eq!(config(Parity::Odd, StopBits::Two).to_string(), "19200 8O2");
+}
+
+#[test]
+fn display_flow_control() {
+    let config = SerialConfig {
+        flow_control: FlowControl::RtsCts,
+        ..SerialConfig::default()
+    };
+    assert_eq!(config.to_string(), "115200 8N1 RTS/CTS");
+
+    let config = SerialConfig {
+        parity: Parity::Even,
+        stop_bits: StopBits::Two,
+        flow_control: FlowControl::RtsCts,
+        ..SerialConfig::baud(57_600)
+    };
+    assert_eq!(config.to_string(), "57600 8E2 RTS/CTS");
+}
@@ -0,0 +1,170 @@
+//! Typed parsing of command arguments.
+
+use core::convert::TryFrom;
+use core::fmt;
+use core::str::SplitWhitespace;
+
+/// The arguments that follow a command's name.
+pub struct Args<'l> {
+    words: SplitWhitespace<'l>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgError {
+    /// The argument with this name was not given.
+    Missing(&'static str),
+    /// The argument `name` could not be parsed as `expected`.
+    Invalid {
+        name: &'static str,
+        expected: &'static str,
+    },
+    TooMany,
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArgError::Missing(name) => write!(f, "missing argument <{}>", name),
+            ArgError::Invalid { name, expected } => write!(f, "<{}> must be {}", name, expected),
+            ArgError::TooMany => f.write_str("too many arguments"),
+        }
+    }
+}
+
+impl<'l> Args<'l> {
+    pub fn new(args: &'l str) -> Args<'l> {
+        Args {
+            words: args.split_whitespace(),
+        }
+    }
+
+    /// Parses the next argument, which must be present.
+    pub fn required<T: Arg>(&mut self, name: &'static str) -> Result<T, ArgError> {
+        let word = self.word(name)?;
+        parse(word, name)
+    }
+
+    /// Parses the next argument if there is one.
+    pub fn optional<T: Arg>(&mut self, name: &'static str) -> Result<Option<T>, ArgError> {
+        match self.words.next() {
+            Some(word) => parse(word, name).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The next argument as it was typed.
+    pub fn word(&mut self, name: &'static str) -> Result<&'l str, ArgError> {
+        self.words.next().ok_or(ArgError::Missing(name))
+    }
+
+    /// The remaining arguments as they were typed.
+    pub fn rest(self) -> SplitWhitespace<'l> {
+        self.words
+    }
+
+    /// Fails if there are arguments left that the command doesn't take.
+    pub fn finish(&mut self) -> Result<(), ArgError> {
+        match self.words.next() {
+            Some(_) => Err(ArgError::TooMany),
+            None => Ok(()),
+        }
+    }
+}
+
+fn parse<T: Arg>(word: &str, name: &'static str) -> Result<T, ArgError> {
+    T::parse(word).ok_or(ArgError::Invalid {
+        name,
+        expected: T::EXPECTED,
+    })
+}
+
+/// A type arguments can be parsed into.
+pub trait Arg: Sized {
+    /// Describes the accepted values in error messages, e.g. "a number between 0 and 255".
+    const EXPECTED: &'static str;
+
+    fn parse(word: &str) -> Option<Self>;
+}
+
+/// Splits off the `0x` or `0b` prefix of hexadecimal and binary numbers.
+fn radix(word: &str) -> (&str, u32) {
+    if let Some(hex) = word.strip_prefix("0x") {
+        (hex, 16)
+    } else if let Some(bin) = word.strip_prefix("0b") {
+        (bin, 2)
+    } else {
+        (word, 10)
+    }
+}
+
+macro_rules! unsigned {
+    ($($ty:ty: $expected:expr),*) => {
+        $(
+            impl Arg for $ty {
+                const EXPECTED: &'static str = $expected;
+
+                fn parse(word: &str) -> Option<$ty> {
+                    let (digits, radix) = radix(word);
+                    <$ty>::from_str_radix(digits, radix).ok()
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! signed {
+    ($($ty:ty: $expected:expr),*) => {
+        $(
+            impl Arg for $ty {
+                const EXPECTED: &'static str = $expected;
+
+                fn parse(word: &str) -> Option<$ty> {
+                    let (negative, word) = match word.strip_prefix('-') {
+                        Some(word) => (true, word),
+                        None => (false, word),
+                    };
+                    let (digits, radix) = radix(word);
+                    if digits.starts_with('-') {
+                        return None;
+                    }
+                    // Wide enough that the most negative value can be negated
+                    let magnitude = i64::from_str_radix(digits, radix).ok()?;
+                    let value = if negative { -magnitude } else { magnitude };
+                    <$ty>::try_from(value).ok()
+                }
+            }
+        )*
+    };
+}
+
+unsigned! {
+    u8: "a number between 0 and 255",
+    u16: "a number between 0 and 65535",
+    u32: "a number between 0 and 4294967295"
+}
+
+signed! {
+    i8: "a number between -128 and 127",
+    i16: "a number between -32768 and 32767",
+    i32: "a number between -2147483648 and 2147483647"
+}
+
+impl Arg for f32 {
+    const EXPECTED: &'static str = "a number";
+
+    fn parse(word: &str) -> Option<f32> {
+        word.parse().ok()
+    }
+}
+
+impl Arg for bool {
+    const EXPECTED: &'static str = "on or off";
+
+    fn parse(word: &str) -> Option<bool> {
+        match word {
+            "on" | "true" | "1" => Some(true),
+            "off" | "false" | "0" => Some(false),
+            _ => None,
+        }
+    }
+}
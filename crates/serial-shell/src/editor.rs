@@ -0,0 +1,351 @@
+//! Turning keystrokes into a line of input.
+
+use heapless::{Deque, String};
+
+/// What a keystroke, or a sequence of bytes for the special keys, means to the editor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    /// A printable character.
+    Char(char),
+    Backspace,
+    /// Delete, erases the character under the cursor.
+    Delete,
+    Enter,
+    Tab,
+    /// Arrow up, the previous line in the history.
+    Up,
+    /// Arrow down, the next line in the history.
+    Down,
+    /// Arrow left, moves the cursor back a character.
+    Left,
+    /// Arrow right, moves the cursor forward a character.
+    Right,
+    /// Home or Ctrl-A, moves the cursor to the start of the line.
+    Home,
+    /// End or Ctrl-E, moves the cursor to the end of the line.
+    End,
+    /// Ctrl-U, erases the whole line.
+    Kill,
+    /// Ctrl-C, abandons the line.
+    Cancel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    None,
+    /// Got `ESC`.
+    Started,
+    /// Got `ESC [`, the start of a control sequence, and its first parameter so far.
+    Csi(u8),
+    /// Past the first parameter of a control sequence, which is all we need.
+    CsiRest(u8),
+    /// Got `ESC O`, which terminals in application mode send before an arrow key.
+    Ss3,
+}
+
+/// Decodes the bytes a terminal sends into [`Key`]s.
+#[derive(Debug)]
+pub struct KeyDecoder {
+    escape: Escape,
+    last_was_cr: bool,
+}
+
+impl Default for KeyDecoder {
+    fn default() -> KeyDecoder {
+        KeyDecoder::new()
+    }
+}
+
+impl KeyDecoder {
+    pub const fn new() -> KeyDecoder {
+        KeyDecoder {
+            escape: Escape::None,
+            last_was_cr: false,
+        }
+    }
+
+    /// Feeds a byte in, returning the key it completes, if any.
+    pub fn decode(&mut self, byte: u8) -> Option<Key> {
+        let last_was_cr = core::mem::replace(&mut self.last_was_cr, byte == b'\r');
+        match self.escape {
+            Escape::Started => match byte {
+                b'[' => {
+                    self.escape = Escape::Csi(0);
+                    return None;
+                }
+                b'O' => {
+                    self.escape = Escape::Ss3;
+                    return None;
+                }
+                // Not a sequence we know, so the byte is a key of its own
+                _ => self.escape = Escape::None,
+            },
+            Escape::Csi(param) | Escape::CsiRest(param) => {
+                return match byte {
+                    b'0'..=b'9' if self.escape == Escape::Csi(param) => {
+                        let param = param.saturating_mul(10).saturating_add(byte - b'0');
+                        self.escape = Escape::Csi(param);
+                        None
+                    }
+                    b';' => {
+                        self.escape = Escape::CsiRest(param);
+                        None
+                    }
+                    // Parameters and intermediate bytes come before the final byte
+                    0x20..=0x3F => None,
+                    0x40..=0x7E => {
+                        self.escape = Escape::None;
+                        match (byte, param) {
+                            (b'~', 1) | (b'~', 7) => Some(Key::Home),
+                            (b'~', 3) => Some(Key::Delete),
+                            (b'~', 4) | (b'~', 8) => Some(Key::End),
+                            (b'~', _) => None,
+                            _ => Self::cursor_key(byte),
+                        }
+                    }
+                    // Not part of a control sequence, so the sequence is over
+                    _ => {
+                        self.escape = Escape::None;
+                        self.key(byte, last_was_cr)
+                    }
+                };
+            }
+            Escape::Ss3 => {
+                self.escape = Escape::None;
+                return Self::cursor_key(byte);
+            }
+            Escape::None => {}
+        }
+        self.key(byte, last_was_cr)
+    }
+
+    /// The key for the final byte of `ESC [` and `ESC O` sequences.
+    fn cursor_key(byte: u8) -> Option<Key> {
+        match byte {
+            b'A' => Some(Key::Up),
+            b'B' => Some(Key::Down),
+            b'C' => Some(Key::Right),
+            b'D' => Some(Key::Left),
+            b'H' => Some(Key::Home),
+            b'F' => Some(Key::End),
+            _ => None,
+        }
+    }
+
+    /// The key for a byte outside of escape sequences.
+    fn key(&mut self, byte: u8, last_was_cr: bool) -> Option<Key> {
+        match byte {
+            0x1B => {
+                self.escape = Escape::Started;
+                None
+            }
+            b'\r' => Some(Key::Enter),
+            // Terminals that send CR LF would otherwise enter every line twice
+            b'\n' if last_was_cr => None,
+            b'\n' => Some(Key::Enter),
+            b'\t' => Some(Key::Tab),
+            0x08 | 0x7F => Some(Key::Backspace),
+            0x01 => Some(Key::Home),
+            0x03 => Some(Key::Cancel),
+            0x05 => Some(Key::End),
+            0x15 => Some(Key::Kill),
+            b' '..=b'~' => Some(Key::Char(byte as char)),
+            _ => None,
+        }
+    }
+}
+
+/// The line being edited plus the history of the `HISTORY` previously entered ones, each at
+/// most `LINE` bytes long.
+pub struct LineEditor<const LINE: usize, const HISTORY: usize> {
+    line: String<LINE>,
+    /// Where in `line` characters get inserted, in bytes.
+    cursor: usize,
+    history: Deque<String<LINE>, HISTORY>,
+    /// How far back in the history we are, `None` while editing a new line.
+    browsing: Option<usize>,
+    /// The new line, saved while browsing the history.
+    draft: String<LINE>,
+}
+
+impl<const LINE: usize, const HISTORY: usize> Default for LineEditor<LINE, HISTORY> {
+    fn default() -> Self {
+        LineEditor::new()
+    }
+}
+
+impl<const LINE: usize, const HISTORY: usize> LineEditor<LINE, HISTORY> {
+    pub const fn new() -> Self {
+        LineEditor {
+            line: String::new(),
+            cursor: 0,
+            history: Deque::new(),
+            browsing: None,
+            draft: String::new(),
+        }
+    }
+
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    /// The position of the cursor in `line`, in bytes.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// The part of the line after the cursor.
+    pub fn after_cursor(&self) -> &str {
+        &self.line[self.cursor..]
+    }
+
+    /// Inserts a character at the cursor, returning false if the line is full.
+    pub fn insert(&mut self, c: char) -> bool {
+        if self.line.len() + c.len_utf8() > LINE {
+            return false;
+        }
+        if self.cursor == self.line.len() {
+            self.line.push(c).ok();
+        } else {
+            let mut line = String::new();
+            // Can't fail, we checked there is room for all of it
+            line.push_str(&self.line[..self.cursor]).ok();
+            line.push(c).ok();
+            line.push_str(&self.line[self.cursor..]).ok();
+            self.line = line;
+        }
+        self.cursor += c.len_utf8();
+        true
+    }
+
+    /// Inserts as much of `s` as fits, returning how many bytes were inserted.
+    pub fn insert_str(&mut self, s: &str) -> usize {
+        let mut inserted = 0;
+        for c in s.chars() {
+            if !self.insert(c) {
+                break;
+            }
+            inserted += c.len_utf8();
+        }
+        inserted
+    }
+
+    /// Removes the character before the cursor, returning false if there is none.
+    pub fn backspace(&mut self) -> bool {
+        self.left() && self.delete()
+    }
+
+    /// Removes the character under the cursor, returning false if the cursor is at the end.
+    pub fn delete(&mut self) -> bool {
+        let len = match self.line[self.cursor..].chars().next() {
+            Some(c) => c.len_utf8(),
+            None => return false,
+        };
+        let mut line = String::new();
+        line.push_str(&self.line[..self.cursor]).ok();
+        line.push_str(&self.line[self.cursor + len..]).ok();
+        self.line = line;
+        true
+    }
+
+    /// Moves the cursor back a character, returning false if it is at the start.
+    pub fn left(&mut self) -> bool {
+        match self.line[..self.cursor].chars().next_back() {
+            Some(c) => {
+                self.cursor -= c.len_utf8();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves the cursor forward a character, returning false if it is at the end.
+    pub fn right(&mut self) -> bool {
+        match self.line[self.cursor..].chars().next() {
+            Some(c) => {
+                self.cursor += c.len_utf8();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves the cursor to the start of the line, returning false if it already was there.
+    pub fn home(&mut self) -> bool {
+        core::mem::replace(&mut self.cursor, 0) != 0
+    }
+
+    /// Moves the cursor to the end of the line, returning false if it already was there.
+    pub fn end(&mut self) -> bool {
+        core::mem::replace(&mut self.cursor, self.line.len()) != self.line.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.line.clear();
+        self.cursor = 0;
+        self.browsing = None;
+    }
+
+    /// Replaces the line with the previous entry in the history. Returns false when there is
+    /// none.
+    pub fn history_prev(&mut self) -> bool {
+        let back = match self.browsing {
+            None => 0,
+            Some(back) => back + 1,
+        };
+        if back >= self.history.len() {
+            return false;
+        }
+        if self.browsing.is_none() {
+            self.draft = self.line.clone();
+        }
+        self.browsing = Some(back);
+        self.set_line(self.entry(back).clone());
+        true
+    }
+
+    /// Replaces the line with the next entry in the history, or with the line that was
+    /// being typed before browsing started. Returns false if we weren't browsing.
+    pub fn history_next(&mut self) -> bool {
+        match self.browsing {
+            None => return false,
+            Some(0) => {
+                self.browsing = None;
+                self.set_line(self.draft.clone());
+            }
+            Some(back) => {
+                self.browsing = Some(back - 1);
+                self.set_line(self.entry(back - 1).clone());
+            }
+        }
+        true
+    }
+
+    /// Records the current line in the history. The line itself stays until `clear`.
+    pub fn submit(&mut self) {
+        self.browsing = None;
+        let line = self.line.trim();
+        if line.is_empty() || self.history.back().map(|last| last.as_str()) == Some(line) {
+            return;
+        }
+        if self.history.is_full() {
+            self.history.pop_front();
+        }
+        let mut entry = String::new();
+        // Can't fail, the entry is no longer than the line
+        entry.push_str(line).ok();
+        self.history.push_back(entry).ok();
+    }
+
+    /// Replaces the line, with the cursor at its end.
+    fn set_line(&mut self, line: String<LINE>) {
+        self.cursor = line.len();
+        self.line = line;
+    }
+
+    /// The history entry `back` lines ago.
+    fn entry(&self, back: usize) -> &String<LINE> {
+        let len = self.history.len();
+        self.history.iter().nth(len - 1 - back).unwrap()
+    }
+}
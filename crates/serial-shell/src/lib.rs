@@ -0,0 +1,374 @@
+//! A line editing command shell for a serial port.
+//!
+//! The application registers its commands, each with a name, a one line description and a
+//! function that receives the application's context, the typed arguments and the serial port
+//! to print to. The shell takes care of everything else: echoing what is typed, backspace and
+//! Ctrl-U, Ctrl-C, moving the cursor and going through the history with the arrow keys,
+//! completing command names with tab and a `help` command that lists everything that is
+//! available.
+//!
+//! Everything is written against the serial traits of `embedded-hal`, so the shell can be
+//! tried out on the host with a fake serial port:
+//!
+//! ```
+//! use core::fmt::Write;
+//! use std::collections::VecDeque;
+//!
+//! use embedded_hal::serial;
+//! use serial_shell::{Args, ArgError, Command, Shell};
+//!
+//! /// Plays back what was "typed" and records what was written.
+//! struct FakeSerial {
+//!     input: VecDeque<u8>,
+//!     output: Vec<u8>,
+//! }
+//!
+//! impl serial::Read<u8> for FakeSerial {
+//!     type Error = ();
+//!
+//!     fn read(&mut self) -> nb::Result<u8, ()> {
+//!         self.input.pop_front().ok_or(nb::Error::WouldBlock)
+//!     }
+//! }
+//!
+//! impl serial::Write<u8> for FakeSerial {
+//!     type Error = ();
+//!
+//!     fn write(&mut self, byte: u8) -> nb::Result<(), ()> {
+//!         self.output.push(byte);
+//!         Ok(())
+//!     }
+//!
+//!     fn flush(&mut self) -> nb::Result<(), ()> {
+//!         Ok(())
+//!     }
+//! }
+//!
+//! fn add(total: &mut i32, mut args: Args, out: &mut dyn Write) -> Result<(), ArgError> {
+//!     let a: i32 = args.required("a")?;
+//!     let b: i32 = args.optional("b")?.unwrap_or(1);
+//!     args.finish()?;
+//!     *total += a + b;
+//!     write!(out, "total = {}\r\n", total).ok();
+//!     Ok(())
+//! }
+//!
+//! let commands = [Command {
+//!     name: "add",
+//!     help: "add <a> [b]: adds a and b (default 1) to the total",
+//!     run: add,
+//! }];
+//!
+//! // Tab completes the command, backspace erases the typo and arrow up repeats the line
+//! let typed = b"ad\t2 0x11\x7f0\r\x1b[A\r add x\r";
+//! let serial = FakeSerial {
+//!     input: typed.iter().copied().collect(),
+//!     output: Vec::new(),
+//! };
+//! let mut shell: Shell<_, _, 32, 4> = Shell::new(serial, &commands, "> ");
+//! let mut total = 0;
+//! shell.poll(&mut total);
+//!
+//! assert_eq!(total, 2 * (2 + 0x10));
+//! let output = String::from_utf8(shell.release().output).unwrap();
+//! assert!(output.contains("total = 36\r\n"));
+//! assert!(output.contains("error: <a> must be a number between"));
+//! ```
+
+#![no_std]
+
+use core::fmt::{self, Write as _};
+
+use embedded_hal::serial;
+
+pub mod args;
+pub mod editor;
+
+pub use args::{Arg, ArgError, Args};
+pub use editor::{Key, KeyDecoder, LineEditor};
+
+/// A command the shell can run.
+pub struct Command<C> {
+    pub name: &'static str,
+    /// Shown by `help`, usually the syntax followed by a short description.
+    pub help: &'static str,
+    pub run: fn(&mut C, Args, &mut dyn fmt::Write) -> Result<(), ArgError>,
+}
+
+const HELP: &str = "help";
+
+/// An interactive shell running the `commands` that operate on a context of type `C`.
+/// Lines are at most `LINE` bytes long and the last `HISTORY` of them are remembered.
+pub struct Shell<'c, S, C, const LINE: usize, const HISTORY: usize> {
+    serial: S,
+    commands: &'c [Command<C>],
+    prompt: &'static str,
+    keys: KeyDecoder,
+    editor: LineEditor<LINE, HISTORY>,
+    started: bool,
+}
+
+impl<'c, S, C, const LINE: usize, const HISTORY: usize> Shell<'c, S, C, LINE, HISTORY>
+where
+    S: serial::Read<u8> + serial::Write<u8>,
+{
+    pub fn new(serial: S, commands: &'c [Command<C>], prompt: &'static str) -> Self {
+        Shell {
+            serial,
+            commands,
+            prompt,
+            keys: KeyDecoder::new(),
+            editor: LineEditor::new(),
+            started: false,
+        }
+    }
+
+    /// Gives the serial port back.
+    pub fn release(self) -> S {
+        self.serial
+    }
+
+    /// The serial port, e.g. to change its settings between calls to `poll`.
+    pub fn serial_mut(&mut self) -> &mut S {
+        &mut self.serial
+    }
+
+    /// Handles the bytes that have arrived so far without waiting for more.
+    pub fn poll(&mut self, context: &mut C) {
+        loop {
+            match self.serial.read() {
+                Ok(byte) => self.feed(byte, context),
+                Err(nb::Error::WouldBlock) => break,
+                // Framing or overrun errors only cost us a character
+                Err(nb::Error::Other(_)) => {}
+            }
+        }
+    }
+
+    /// Handles input forever.
+    pub fn run(&mut self, context: &mut C) -> ! {
+        loop {
+            if let Ok(byte) = nb::block!(self.serial.read()) {
+                self.feed(byte, context);
+            }
+        }
+    }
+
+    /// Handles a byte of input, running a command when it completes a line.
+    pub fn feed(&mut self, byte: u8, context: &mut C) {
+        if !self.started {
+            self.started = true;
+            self.prompt();
+        }
+
+        let key = match self.keys.decode(byte) {
+            Some(key) => key,
+            None => return,
+        };
+        match key {
+            Key::Char(c) => {
+                if self.editor.insert(c) {
+                    self.output().write_char(c).ok();
+                    self.redraw_tail(0);
+                } else {
+                    // Line full, ring the bell
+                    self.output().write_char('\x07').ok();
+                }
+            }
+            Key::Backspace => {
+                if self.editor.backspace() {
+                    self.output().write_char('\x08').ok();
+                    self.redraw_tail(1);
+                }
+            }
+            Key::Delete => {
+                if self.editor.delete() {
+                    self.redraw_tail(1);
+                }
+            }
+            Key::Left => {
+                if self.editor.left() {
+                    self.output().write_char('\x08').ok();
+                }
+            }
+            Key::Right => {
+                let from = self.editor.cursor();
+                if self.editor.right() {
+                    // Moving over a character is writing it again
+                    let mut out = Output(&mut self.serial);
+                    out.write_str(&self.editor.line()[from..self.editor.cursor()])
+                        .ok();
+                }
+            }
+            Key::Home => {
+                if self.editor.home() {
+                    self.redraw();
+                }
+            }
+            Key::End => {
+                if self.editor.end() {
+                    self.redraw();
+                }
+            }
+            Key::Kill => {
+                self.editor.clear();
+                self.redraw();
+            }
+            Key::Cancel => {
+                self.editor.clear();
+                self.output().write_str("^C\r\n").ok();
+                self.prompt();
+            }
+            Key::Up => {
+                if self.editor.history_prev() {
+                    self.redraw();
+                }
+            }
+            Key::Down => {
+                if self.editor.history_next() {
+                    self.redraw();
+                }
+            }
+            Key::Tab => self.complete(),
+            Key::Enter => {
+                self.output().write_str("\r\n").ok();
+                self.editor.submit();
+                self.execute(context);
+                self.editor.clear();
+                self.prompt();
+            }
+        }
+    }
+
+    fn execute(&mut self, context: &mut C) {
+        let mut out = Output(&mut self.serial);
+        let line = self.editor.line().trim_start();
+        let (name, args) = match line.find(' ') {
+            Some(end) => (&line[..end], &line[end..]),
+            None => (line, ""),
+        };
+
+        if name.is_empty() {
+            return;
+        }
+        if let Some(command) = self.commands.iter().find(|command| command.name == name) {
+            if let Err(e) = (command.run)(context, Args::new(args), &mut out) {
+                write!(out, "error: {}\r\n", e).ok();
+            }
+        } else if name == HELP {
+            for command in self.commands {
+                write!(out, "{}\r\n", command.help).ok();
+            }
+        } else {
+            write!(out, "unknown command `{}`, try `{}`\r\n", name, HELP).ok();
+        }
+    }
+
+    /// Completes the command name being typed.
+    fn complete(&mut self) {
+        let prefix = self.editor.line();
+        // Only names get completed, not arguments
+        if prefix.contains(' ') || !self.editor.after_cursor().is_empty() {
+            return;
+        }
+
+        let mut matches = Self::names(self.commands)
+            .filter(|name| name.starts_with(prefix))
+            .peekable();
+        let first = match matches.peek() {
+            Some(first) => *first,
+            None => {
+                Output(&mut self.serial).write_char('\x07').ok();
+                return;
+            }
+        };
+        // The longest prefix all matches share
+        let mut common = first.len();
+        let mut count = 0;
+        for name in matches {
+            common = first
+                .bytes()
+                .zip(name.bytes())
+                .take(common)
+                .take_while(|(a, b)| a == b)
+                .count();
+            count += 1;
+        }
+
+        let typed = prefix.len();
+        if count == 1 {
+            self.insert(&first[typed..]);
+            self.insert(" ");
+        } else if common > typed {
+            self.insert(&first[typed..common]);
+        } else {
+            // Nothing to add, show the candidates instead
+            let mut out = Output(&mut self.serial);
+            out.write_str("\r\n").ok();
+            for name in Self::names(self.commands).filter(|name| name.starts_with(prefix)) {
+                write!(out, "{}  ", name).ok();
+            }
+            out.write_str("\r\n").ok();
+            self.redraw();
+        }
+    }
+
+    /// The commands' names, plus `help` unless the application has its own.
+    fn names(commands: &'c [Command<C>]) -> impl Iterator<Item = &'static str> + 'c {
+        let help = if commands.iter().any(|command| command.name == HELP) {
+            None
+        } else {
+            Some(HELP)
+        };
+        commands.iter().map(|command| command.name).chain(help)
+    }
+
+    fn insert(&mut self, s: &str) {
+        let inserted = self.editor.insert_str(s);
+        Output(&mut self.serial).write_str(&s[..inserted]).ok();
+    }
+
+    fn output(&mut self) -> Output<'_, S> {
+        Output(&mut self.serial)
+    }
+
+    fn prompt(&mut self) {
+        let prompt = self.prompt;
+        self.output().write_str(prompt).ok();
+    }
+
+    /// Erases the terminal's line and prints the prompt and the line being edited again.
+    fn redraw(&mut self) {
+        let mut out = Output(&mut self.serial);
+        write!(out, "\r\x1b[K{}{}", self.prompt, self.editor.line()).ok();
+        for _ in self.editor.after_cursor().chars() {
+            out.write_char('\x08').ok();
+        }
+    }
+
+    /// Prints the line after the cursor again, followed by `erased` blanks for the characters
+    /// that were removed, and moves the terminal's cursor back to where it was.
+    fn redraw_tail(&mut self, erased: usize) {
+        let mut out = Output(&mut self.serial);
+        let tail = self.editor.after_cursor();
+        out.write_str(tail).ok();
+        for _ in 0..erased {
+            out.write_char(' ').ok();
+        }
+        for _ in 0..tail.chars().count() + erased {
+            out.write_char('\x08').ok();
+        }
+    }
+}
+
+/// Blocking `fmt::Write` on top of the serial port.
+struct Output<'s, S>(&'s mut S);
+
+impl<'s, S: serial::Write<u8>> fmt::Write for Output<'s, S> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            nb::block!(self.0.write(byte)).map_err(|_| fmt::Error)?;
+        }
+        nb::block!(self.0.flush()).map_err(|_| fmt::Error)
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::VecDeque;
+
+use embedded_hal::serial;
+use serial_shell::{ArgError, Args, Command, Key, KeyDecoder, LineEditor, Shell};
+
+fn decode(bytes: &[u8]) -> Vec<Key> {
+    let mut decoder = KeyDecoder::new();
+    bytes
+        .iter()
+        .filter_map(|&byte| decoder.decode(byte))
+        .collect()
+}
+
+fn typed(editor: &mut LineEditor<16, 3>, line: &str) {
+    editor.clear();
+    editor.insert_str(line);
+    editor.submit();
+}
+
+#[test]
+fn printable_and_control_keys() {
+    assert_eq!(
+        decode(b"a Z~\t\x08\x7f\x01\x05\x03\x15"),
+        vec![
+            Key::Char('a'),
+            Key::Char(' '),
+            Key::Char('Z'),
+            Key::Char('~'),
+            Key::Tab,
+            Key::Backspace,
+            Key::Backspace,
+            Key::Home,
+            Key::End,
+            Key::Cancel,
+            Key::Kill,
+        ]
+    );
+    // Other control characters are ignored
+    assert_eq!(decode(b"\x00\x02\x07"), vec![]);
+}
+
+#[test]
+fn line_endings() {
+    assert_eq!(decode(b"\r"), vec![Key::Enter]);
+    assert_eq!(decode(b"\n"), vec![Key::Enter]);
+    assert_eq!(decode(b"\r\n"), vec![Key::Enter]);
+    assert_eq!(decode(b"\n\n"), vec![Key::Enter, Key::Enter]);
+    assert_eq!(decode(b"\r\r\n"), vec![Key::Enter, Key::Enter]);
+}
+
+#[test]
+fn csi_sequences() {
+    assert_eq!(
+        decode(b"\x1b[A\x1b[B\x1b[C\x1b[D\x1b[H\x1b[F"),
+        vec![
+            Key::Up,
+            Key::Down,
+            Key::Right,
+            Key::Left,
+            Key::Home,
+            Key::End
+        ]
+    );
+    assert_eq!(
+        decode(b"\x1b[1~\x1b[7~\x1b[3~\x1b[4~\x1b[8~"),
+        vec![Key::Home, Key::Home, Key::Delete, Key::End, Key::End]
+    );
+    // Modifiers are parameters, the key is still an arrow
+    assert_eq!(decode(b"\x1b[1;5C"), vec![Key::Right]);
+    assert_eq!(decode(b"\x1b[3;5~"), vec![Key::Delete]);
+    // Sequences we don't know are skipped as a whole
+    assert_eq!(
+        decode(b"\x1b[15~x\x1b[2Jy"),
+        vec![Key::Char('x'), Key::Char('y')]
+    );
+}
+
+#[test]
+fn ss3_sequences() {
+    // What terminals in application cursor mode send for the same keys
+    assert_eq!(
+        decode(b"\x1bOA\x1bOB\x1bOC\x1bOD\x1bOH\x1bOF"),
+        vec![
+            Key::Up,
+            Key::Down,
+            Key::Right,
+            Key::Left,
+            Key::Home,
+            Key::End
+        ]
+    );
+    assert_eq!(decode(b"\x1bOPq"), vec![Key::Char('q')]);
+}
+
+#[test]
+fn unknown_escapes_pass_bytes_through() {
+    assert_eq!(decode(b"\x1bx"), vec![Key::Char('x')]);
+    assert_eq!(decode(b"\x1b\r"), vec![Key::Enter]);
+    assert_eq!(decode(b"\x1b\x1b[A"), vec![Key::Up]);
+    // A control character cuts a control sequence short
+    assert_eq!(decode(b"\x1b[1\rb"), vec![Key::Enter, Key::Char('b')]);
+}
+
+#[test]
+fn cursor_movement() {
+    let mut editor: LineEditor<16, 3> = LineEditor::new();
+    assert!(!editor.left());
+    assert!(!editor.right());
+    editor.insert_str("scan");
+    assert_eq!(editor.cursor(), 4);
+    assert!(!editor.right());
+    assert!(!editor.end());
+
+    assert!(editor.left());
+    assert!(editor.left());
+    assert_eq!(editor.after_cursor(), "an");
+    assert!(editor.insert('x'));
+    assert_eq!(editor.line(), "scxan");
+    assert_eq!(editor.cursor(), 3);
+
+    assert!(editor.home());
+    assert!(!editor.home());
+    assert!(!editor.left());
+    assert!(!editor.backspace());
+    assert!(editor.insert('>'));
+    assert_eq!(editor.line(), ">scxan");
+
+    assert!(editor.right());
+    assert!(editor.right());
+    assert!(editor.delete());
+    assert_eq!(editor.line(), ">scan");
+    assert!(editor.backspace());
+    assert_eq!(editor.line(), ">san");
+    assert_eq!(editor.cursor(), 2);
+
+    assert!(editor.end());
+    assert!(!editor.delete());
+    assert!(editor.backspace());
+    assert_eq!(editor.line(), ">sa");
+}
+
+#[test]
+fn multi_byte_characters() {
+    let mut editor: LineEditor<16, 3> = LineEditor::new();
+    editor.insert_str("1°C");
+    assert!(editor.left());
+    assert!(editor.left());
+    assert_eq!(editor.cursor(), 1);
+    assert!(editor.delete());
+    assert_eq!(editor.line(), "1C");
+}
+
+#[test]
+fn full_line() {
+    let mut editor: LineEditor<4, 3> = LineEditor::new();
+    assert_eq!(editor.insert_str("abcdef"), 4);
+    assert!(editor.home());
+    assert!(!editor.insert('x'));
+    assert_eq!(editor.line(), "abcd");
+    assert_eq!(editor.cursor(), 0);
+}
+
+#[test]
+fn history() {
+    let mut editor: LineEditor<16, 3> = LineEditor::new();
+    assert!(!editor.history_prev());
+    assert!(!editor.history_next());
+    for line in ["one", "two", "  ", "two", "three", "four"].iter() {
+        typed(&mut editor, line);
+    }
+    // Blank lines and repeats aren't recorded, the oldest entry made room for the newest
+    editor.clear();
+    editor.insert_str("fi");
+    assert!(editor.history_prev());
+    assert_eq!(editor.line(), "four");
+    assert_eq!(editor.cursor(), 4);
+    assert!(editor.history_prev());
+    assert_eq!(editor.line(), "three");
+    assert!(editor.history_prev());
+    assert_eq!(editor.line(), "two");
+    assert!(!editor.history_prev());
+    assert_eq!(editor.line(), "two");
+
+    assert!(editor.history_next());
+    assert!(editor.history_next());
+    assert_eq!(editor.line(), "four");
+    // Back to what was being typed
+    assert!(editor.history_next());
+    assert_eq!(editor.line(), "fi");
+    assert_eq!(editor.cursor(), 2);
+    assert!(!editor.history_next());
+}
+
+#[test]
+fn history_entries_are_trimmed() {
+    let mut editor: LineEditor<16, 3> = LineEditor::new();
+    typed(&mut editor, "  scan ");
+    editor.clear();
+    assert!(editor.history_prev());
+    assert_eq!(editor.line(), "scan");
+}
+
+/// Plays back what was "typed" and records what was written.
+struct FakeSerial {
+    input: VecDeque<u8>,
+    output: Vec<u8>,
+}
+
+impl serial::Read<u8> for FakeSerial {
+    type Error = ();
+
+    fn read(&mut self) -> nb::Result<u8, ()> {
+        self.input.pop_front().ok_or(nb::Error::WouldBlock)
+    }
+}
+
+impl serial::Write<u8> for FakeSerial {
+    type Error = ();
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), ()> {
+        self.output.push(byte);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), ()> {
+        Ok(())
+    }
+}
+
+fn record(
+    lines: &mut Vec<String>,
+    args: Args,
+    _: &mut dyn core::fmt::Write,
+) -> Result<(), ArgError> {
+    lines.push(args.rest().collect::<Vec<_>>().join(" "));
+    Ok(())
+}
+
+fn run(typed: &[u8]) -> (Vec<String>, String) {
+    let commands = [Command {
+        name: "echo",
+        help: "echo <text>: records the text",
+        run: record,
+    }];
+    let serial = FakeSerial {
+        input: typed.iter().copied().collect(),
+        output: Vec::new(),
+    };
+    let mut shell: Shell<_, _, 32, 4> = Shell::new(serial, &commands, "> ");
+    let mut lines = Vec::new();
+    shell.poll(&mut lines);
+    let output = String::from_utf8(shell.release().output).unwrap();
+    (lines, output)
+}
+
+#[test]
+fn shell_edits_in_the_middle() {
+    // Fix the typo at the start of the line, then add to the end
+    let (lines, output) = run(b"echo abd\x1b[D\x1b[D\x7fx\x1bOC\x1b[3~c\x1b[Fe\r");
+    assert_eq!(lines, vec!["xbce"]);
+    // The terminal's cursor is moved back after the rest of the line is printed again
+    assert!(output.contains("\x08bd \x08\x08\x08"), "{:?}", output);
+}
+
+#[test]
+fn shell_recalls_and_edits_history() {
+    let (lines, _) =
+        run(b"echo one\r\x1bOA\x01\x1b[C\x1b[C\x1b[C\x1b[C\x1b[C\x1b[3~\x1b[3~\x1b[3~two\r");
+    assert_eq!(lines, vec!["one", "two"]);
+}
@@ -0,0 +1,93 @@
+//! Fixtures shared by the host tests of the sensor crates.
+//!
+//! The compass calibration, the LSM303AGR replay and the punch-o-meter are all tested with
+//! synthetic sensor data: a board turned around in Earth's field, or lying on a table until
+//! it is hit. This crate generates that data, so every test plays with the same board. It is
+//! only ever a `[dev-dependencies]` entry.
+
+use core::f32::consts::PI;
+
+/// Earth's field somewhere in central Europe, in nT, as the magnetometer of a board lying flat
+/// with its y axis pointing north sees it.
+pub const EARTH: [f32; 3] = [0.0, 20_000.0, -45_000.0];
+
+/// Gravity as the accelerometer of a board lying flat sees it, in milli-g.
+pub const GRAVITY: [f32; 3] = [0.0, 0.0, 1000.0];
+
+/// Small deterministic pseudo random number generator, in `-1.0..1.0`.
+pub struct Random(u32);
+
+impl Random {
+    /// The same `seed` always gives the same numbers.
+    pub fn new(seed: u32) -> Random {
+        Random(seed)
+    }
+
+    pub fn uniform(&mut self) -> f32 {
+        self.0 = self.0.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (self.0 >> 8) as f32 / (1 << 23) as f32 - 1.0
+    }
+}
+
+pub type Rotation = [[f32; 3]; 3];
+
+/// Rotation matrix from yaw, pitch and roll in radians.
+pub fn rotation(yaw: f32, pitch: f32, roll: f32) -> Rotation {
+    let (sy, cy) = yaw.sin_cos();
+    let (sp, cp) = pitch.sin_cos();
+    let (sr, cr) = roll.sin_cos();
+    [
+        [cy * cp, cy * sp * sr - sy * cr, cy * sp * cr + sy * sr],
+        [sy * cp, sy * sp * sr + cy * cr, sy * sp * cr - cy * sr],
+        [-sp, cp * sr, cp * cr],
+    ]
+}
+
+/// A board turned around at random, `tilt` limiting how far it is tipped over (`PI` for all
+/// the way).
+pub fn random_rotation(random: &mut Random, tilt: f32) -> Rotation {
+    rotation(
+        random.uniform() * PI,
+        random.uniform() * tilt / 2.0,
+        random.uniform() * tilt,
+    )
+}
+
+pub fn rotate(r: &Rotation, v: [f32; 3]) -> [f32; 3] {
+    let mut out = [0.0; 3];
+    for (row, out) in r.iter().zip(out.iter_mut()) {
+        *out = row[0] * v[0] + row[1] * v[1] + row[2] * v[2];
+    }
+    out
+}
+
+/// Rounds a vector to the integers the sensors report.
+pub fn round(v: [f32; 3]) -> [i32; 3] {
+    [v[0] as i32, v[1] as i32, v[2] as i32]
+}
+
+/// Accelerometer samples, in milli-g, of a board lying on the table with a few milli-g of
+/// sensor noise.
+pub fn rest(random: &mut Random, samples: usize) -> Vec<[i32; 3]> {
+    (0..samples)
+        .map(|_| {
+            [
+                10 + (random.uniform() * 15.0) as i32,
+                -20 + (random.uniform() * 15.0) as i32,
+                1000 + (random.uniform() * 15.0) as i32,
+            ]
+        })
+        .collect()
+}
+
+/// Accelerometer samples of a punch along the x axis peaking at `peak_mg`, lasting about
+/// 20 ms at 400 Hz.
+pub fn punch(peak_mg: i32) -> Vec<[i32; 3]> {
+    [130, 420, 780, 1000, 870, 520, 200, -100, -150, -50, 20]
+        .iter()
+        .map(|permille| {
+            let x = peak_mg * permille / 1000;
+            [10 + x, -20 + x / 5, 1000 - x / 10]
+        })
+        .collect()
+}
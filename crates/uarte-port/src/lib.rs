@@ -0,0 +1,546 @@
+//! Interrupt driven, ring buffered serial port on top of the nRF52 UARTE.
+//!
+//! The HAL's `Uarte` configures the pins, the baud rate and the parity; after that
+//! [`UartePort`] drives the peripheral's registers itself. Bytes written to the port go into a
+//! transmit ring buffer and are sent in chunks of up to [`TX_CHUNK_LEN`] bytes per EasyDMA
+//! transfer. There are two chunk buffers: while one is being sent, the next chunk is put
+//! together in the other, so the `ENDTX` interrupt can start it without copying anything
+//! first.
+//!
+//! Reception also happens in chunks, of [`RX_CHUNK_LEN`] bytes, and is double buffered as
+//! well. The UARTE reads `RXD.PTR` when a transfer starts, so once it signals `RXSTARTED` the
+//! pointer is moved on to the other buffer, and the `ENDRX_STARTRX` shortcut starts the next
+//! transfer into it the moment the current one is full. The `ENDRX` interrupt then only has to
+//! move the full chunk into the receive ring buffer, while the UARTE already fills the other
+//! one.
+//!
+//! The UARTE only tells how many bytes it has received once a transfer ends. A chunk that is
+//! only partly filled is collected when the application asks for a byte and the ring buffer
+//! is empty: the port stops the receiver, which ends the transfer with `ENDRX`, moves whatever
+//! is left in the UARTE's FIFO to RAM once it signals `RXTO` and then restarts reception. So
+//! there is one interrupt per chunk instead of one per byte, and bytes are not lost while the
+//! application is busy doing something else, unless it lets more than `RX_LEN` of them pile
+//! up.
+//!
+//! Everything the port shares with its interrupt handler lives in a [`Shared`], which has
+//! to be a `static` because the EasyDMA buffers must stay put while a transfer runs. The
+//! application installs the interrupt handler itself:
+//!
+//! ``` ignore
+//! use microbit::pac::{interrupt, UARTE0};
+//! use uarte_port::{Shared, UartePort};
+//!
+//! static SERIAL: Shared<UARTE0, 256, 256> = Shared::new();
+//!
+//! #[interrupt]
+//! fn UARTE0_UART0() {
+//!     SERIAL.on_interrupt();
+//! }
+//!
+//! let mut serial = UartePort::new(&SERIAL, uarte);
+//! ```
+//!
+//! [`UartePort::with_config`] sets the port up from a `SerialConfig` instead, covering the
+//! settings the HAL doesn't know about: odd parity, two stop bits and RTS/CTS flow control.
+
+#![no_std]
+
+use core::cell::{RefCell, UnsafeCell};
+use core::fmt;
+use core::sync::atomic::{compiler_fence, Ordering};
+
+use cortex_m::interrupt::{self as cm_interrupt, Mutex};
+use embedded_hal::blocking::serial as bserial;
+use embedded_hal::serial;
+use heapless::Deque;
+use microbit::{
+    hal::uarte::{self, Baudrate, Pins, Uarte},
+    pac::{uarte0, Interrupt, NVIC, UARTE0, UARTE1},
+};
+use serial_config::{FlowControl, Parity, SerialConfig, StopBits};
+
+/// Largest number of bytes sent by a single DMA transfer.
+pub const TX_CHUNK_LEN: usize = 32;
+/// Number of bytes received by a single DMA transfer, unless it is cut short.
+pub const RX_CHUNK_LEN: usize = 32;
+
+// Bits of the `CONFIG` register
+const CONFIG_HWFC: u32 = 1 << 0;
+const CONFIG_STOP_TWO: u32 = 1 << 4;
+const CONFIG_PARITYTYPE_ODD: u32 = 1 << 8;
+
+/// A UARTE peripheral together with its interrupt.
+pub trait Instance: uarte::Instance + Send {
+    const INTERRUPT: Interrupt;
+}
+
+impl Instance for UARTE0 {
+    const INTERRUPT: Interrupt = Interrupt::UARTE0_UART0;
+}
+
+impl Instance for UARTE1 {
+    const INTERRUPT: Interrupt = Interrupt::UARTE1;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// A byte arrived before the previous one was moved out of the UARTE.
+    Overrun,
+    Parity,
+    /// A byte didn't end with a valid stop bit.
+    Framing,
+    /// The line was held low for longer than a byte.
+    Break,
+    /// The receive ring buffer was full and bytes had to be dropped.
+    BufferFull,
+}
+
+impl Error {
+    /// Decodes `ERRORSRC`, reporting the first error it contains.
+    fn from_errorsrc(bits: u32) -> Error {
+        if bits & (1 << 0) != 0 {
+            Error::Overrun
+        } else if bits & (1 << 1) != 0 {
+            Error::Parity
+        } else if bits & (1 << 2) != 0 {
+            Error::Framing
+        } else {
+            Error::Break
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The UARTE can't generate this baud rate.
+    UnsupportedBaudRate,
+    /// Flow control needs `cts` and `rts` pins.
+    MissingFlowControlPins,
+}
+
+fn baudrate(baud_rate: u32) -> Result<Baudrate, ConfigError> {
+    Ok(match baud_rate {
+        1_200 => Baudrate::BAUD1200,
+        2_400 => Baudrate::BAUD2400,
+        4_800 => Baudrate::BAUD4800,
+        9_600 => Baudrate::BAUD9600,
+        14_400 => Baudrate::BAUD14400,
+        19_200 => Baudrate::BAUD19200,
+        28_800 => Baudrate::BAUD28800,
+        31_250 => Baudrate::BAUD31250,
+        38_400 => Baudrate::BAUD38400,
+        56_000 => Baudrate::BAUD56000,
+        57_600 => Baudrate::BAUD57600,
+        76_800 => Baudrate::BAUD76800,
+        115_200 => Baudrate::BAUD115200,
+        230_400 => Baudrate::BAUD230400,
+        250_000 => Baudrate::BAUD250000,
+        460_800 => Baudrate::BAUD460800,
+        921_600 => Baudrate::BAUD921600,
+        1_000_000 => Baudrate::BAUD1M,
+        _ => return Err(ConfigError::UnsupportedBaudRate),
+    })
+}
+
+fn registers<T: Instance>() -> &'static uarte0::RegisterBlock {
+    // The peripheral belongs to `State`, which only lets one context use it at a time
+    unsafe { &*T::ptr() }
+}
+
+/// Where reception is at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rx {
+    /// A transfer into the DMA buffer is running.
+    Running,
+    /// `STOPRX` was triggered to collect a partly filled chunk, waiting for `RXTO`.
+    Stopping,
+    /// `FLUSHRX` was triggered to move what is left in the FIFO, waiting for `ENDRX`.
+    Flushing,
+}
+
+/// The buffers EasyDMA reads from and writes to, two per direction.
+struct Dma {
+    tx: [UnsafeCell<[u8; TX_CHUNK_LEN]>; 2],
+    rx: [UnsafeCell<[u8; RX_CHUNK_LEN]>; 2],
+}
+
+/// What the application and the interrupt handler share.
+struct State<T, const TX_LEN: usize, const RX_LEN: usize> {
+    // Owned so that nobody else can use the peripheral
+    _uarte: Uarte<T>,
+    tx: Deque<u8, TX_LEN>,
+    rx: Deque<u8, RX_LEN>,
+    /// Whether a DMA transfer from one of the transmit buffers is running.
+    tx_busy: bool,
+    /// The transmit buffer that isn't being sent, where the next chunk is put together.
+    tx_next: usize,
+    /// Bytes of the next chunk already in `tx_next`.
+    tx_next_len: usize,
+    rx_state: Rx,
+    /// The receive buffer of the transfer that ends with the next `ENDRX`.
+    rx_current: usize,
+    /// Error to report on the next read.
+    error: Option<Error>,
+}
+
+impl<T: Instance, const TX_LEN: usize, const RX_LEN: usize> State<T, TX_LEN, RX_LEN> {
+    /// Moves bytes from the transmit ring buffer into the next chunk, as far as it has room.
+    fn fill_tx(&mut self, dma: &Dma) {
+        // The DMA only ever reads the other buffer
+        let buffer = unsafe { &mut *dma.tx[self.tx_next].get() };
+        while self.tx_next_len < TX_CHUNK_LEN {
+            match self.tx.pop_front() {
+                Some(byte) => buffer[self.tx_next_len] = byte,
+                None => break,
+            }
+            self.tx_next_len += 1;
+        }
+    }
+
+    /// Sends the next chunk, unless a transfer is already running, and starts putting the one
+    /// after it together.
+    fn start_tx(&mut self, dma: &Dma) {
+        if self.tx_busy || self.tx_next_len == 0 {
+            return;
+        }
+
+        let uarte = registers::<T>();
+        let buffer = dma.tx[self.tx_next].get() as u32;
+        // Make sure the buffer has been written before the DMA starts reading it
+        compiler_fence(Ordering::Release);
+        unsafe {
+            uarte.txd.ptr.write(|w| w.bits(buffer));
+            uarte.txd.maxcnt.write(|w| w.bits(self.tx_next_len as u32));
+            uarte.tasks_starttx.write(|w| w.bits(1));
+        }
+        self.tx_busy = true;
+        self.tx_next ^= 1;
+        self.tx_next_len = 0;
+        self.fill_tx(dma);
+    }
+
+    /// Starts receiving into `buffer`, with the next transfer following on its own.
+    fn start_rx(&mut self, dma: &Dma, buffer: usize) {
+        let uarte = registers::<T>();
+        unsafe {
+            uarte.rxd.ptr.write(|w| w.bits(dma.rx[buffer].get() as u32));
+            uarte.rxd.maxcnt.write(|w| w.bits(RX_CHUNK_LEN as u32));
+        }
+        uarte.shorts.modify(|_, w| w.endrx_startrx().enabled());
+        // An `RXSTARTED` left over from flushing must not move the pointer of this transfer
+        uarte.events_rxstarted.reset();
+        compiler_fence(Ordering::Release);
+        uarte.tasks_startrx.write(|w| unsafe { w.bits(1) });
+        self.rx_current = buffer;
+        self.rx_state = Rx::Running;
+    }
+
+    /// Moves what the transfer that just ended received into the ring buffer. The next
+    /// transfer, if any, goes to the other buffer.
+    fn take_rx(&mut self, dma: &Dma) {
+        let uarte = registers::<T>();
+        // Don't read the buffer before the DMA is done with it
+        compiler_fence(Ordering::Acquire);
+        let amount = (uarte.rxd.amount.read().bits() as usize).min(RX_CHUNK_LEN);
+        let received = unsafe { &(*dma.rx[self.rx_current].get())[..amount] };
+        for byte in received {
+            if self.rx.push_back(*byte).is_err() {
+                self.error.get_or_insert(Error::BufferFull);
+            }
+        }
+        self.rx_current ^= 1;
+    }
+
+    /// Ends the current transfer early when it holds bytes the application is waiting for.
+    fn collect_rx(&mut self) {
+        let uarte = registers::<T>();
+        // `RXDRDY` is set for every byte that arrives, whether its interrupt is enabled or
+        // not. A pending `ENDRX` is taken care of by the interrupt handler anyway.
+        if self.rx_state != Rx::Running
+            || uarte.events_rxdrdy.read().bits() == 0
+            || uarte.events_endrx.read().bits() != 0
+        {
+            return;
+        }
+        uarte.events_rxdrdy.reset();
+        // Otherwise the transfer that stopping ends would start the next one
+        uarte.shorts.modify(|_, w| w.endrx_startrx().disabled());
+        uarte.tasks_stoprx.write(|w| unsafe { w.bits(1) });
+        self.rx_state = Rx::Stopping;
+    }
+
+    fn on_interrupt(&mut self, dma: &Dma) {
+        let uarte = registers::<T>();
+
+        if uarte.events_error.read().bits() != 0 {
+            uarte.events_error.reset();
+            let source = uarte.errorsrc.read().bits();
+            // Writing ones clears the flags
+            uarte.errorsrc.write(|w| unsafe { w.bits(source) });
+            self.error.get_or_insert(Error::from_errorsrc(source));
+        }
+
+        // Handled before RXSTARTED and RXTO: a transfer ends before the next one starts, and
+        // stopping the receiver ends the transfer first
+        if uarte.events_endrx.read().bits() != 0 {
+            uarte.events_endrx.reset();
+            self.take_rx(dma);
+            match self.rx_state {
+                // The shortcut has already started the next transfer, or `RXTO` follows
+                Rx::Running | Rx::Stopping => {}
+                Rx::Flushing => self.start_rx(dma, self.rx_current),
+            }
+        }
+
+        if uarte.events_rxstarted.read().bits() != 0 {
+            uarte.events_rxstarted.reset();
+            // `RXD.PTR` has been read, point it at the buffer for the transfer after this one
+            if self.rx_state == Rx::Running {
+                let buffer = dma.rx[self.rx_current ^ 1].get() as u32;
+                uarte.rxd.ptr.write(|w| unsafe { w.bits(buffer) });
+            }
+        }
+
+        if uarte.events_rxto.read().bits() != 0 {
+            uarte.events_rxto.reset();
+            // The receiver has stopped, but up to four bytes may still sit in the FIFO.
+            // Flushing them ends with another `ENDRX`, even if there are none.
+            self.rx_state = Rx::Flushing;
+            let buffer = dma.rx[self.rx_current].get() as u32;
+            unsafe {
+                uarte.rxd.ptr.write(|w| w.bits(buffer));
+                uarte.rxd.maxcnt.write(|w| w.bits(RX_CHUNK_LEN as u32));
+                uarte.tasks_flushrx.write(|w| w.bits(1));
+            }
+        }
+
+        if uarte.events_endtx.read().bits() != 0 {
+            uarte.events_endtx.reset();
+            self.tx_busy = false;
+            // Topping the next chunk up first costs no more than the gap it saves
+            self.fill_tx(dma);
+            self.start_tx(dma);
+        }
+    }
+}
+
+/// The state of a [`UartePort`] that its interrupt handler needs, with transmit and receive
+/// ring buffers of `TX_LEN` and `RX_LEN` bytes. Writes block while the transmit buffer is
+/// full; bytes that arrive while the receive buffer is full are dropped.
+pub struct Shared<T, const TX_LEN: usize, const RX_LEN: usize> {
+    state: Mutex<RefCell<Option<State<T, TX_LEN, RX_LEN>>>>,
+    dma: Dma,
+}
+
+// The DMA buffers are only touched from within a critical section that holds the state, and
+// by the DMA while no such section does
+unsafe impl<T: Send, const TX_LEN: usize, const RX_LEN: usize> Sync for Shared<T, TX_LEN, RX_LEN> {}
+
+impl<T, const TX_LEN: usize, const RX_LEN: usize> Shared<T, TX_LEN, RX_LEN> {
+    pub const fn new() -> Shared<T, TX_LEN, RX_LEN> {
+        Shared {
+            state: Mutex::new(RefCell::new(None)),
+            dma: Dma {
+                tx: [
+                    UnsafeCell::new([0; TX_CHUNK_LEN]),
+                    UnsafeCell::new([0; TX_CHUNK_LEN]),
+                ],
+                rx: [
+                    UnsafeCell::new([0; RX_CHUNK_LEN]),
+                    UnsafeCell::new([0; RX_CHUNK_LEN]),
+                ],
+            },
+        }
+    }
+}
+
+impl<T: Instance, const TX_LEN: usize, const RX_LEN: usize> Shared<T, TX_LEN, RX_LEN> {
+    /// Body of the interrupt handler of the UARTE, see the crate documentation.
+    pub fn on_interrupt(&self) {
+        cm_interrupt::free(|cs| {
+            if let Some(state) = self.state.borrow(cs).borrow_mut().as_mut() {
+                state.on_interrupt(&self.dma);
+            }
+        });
+    }
+}
+
+/// Handle to the serial port. Only one can exist per [`Shared`].
+pub struct UartePort<T: 'static, const TX_LEN: usize, const RX_LEN: usize> {
+    shared: &'static Shared<T, TX_LEN, RX_LEN>,
+}
+
+impl<T: Instance, const TX_LEN: usize, const RX_LEN: usize> UartePort<T, TX_LEN, RX_LEN> {
+    /// Takes over the UARTE configured by `serial` and starts receiving.
+    ///
+    /// # Panics
+    ///
+    /// If `shared` already belongs to a port.
+    pub fn new(
+        shared: &'static Shared<T, TX_LEN, RX_LEN>,
+        serial: Uarte<T>,
+    ) -> UartePort<T, TX_LEN, RX_LEN> {
+        // Checked before touching the registers, which a running port is still using
+        let taken = cm_interrupt::free(|cs| shared.state.borrow(cs).borrow().is_some());
+        assert!(!taken, "only one `UartePort` can use a `Shared`");
+
+        let uarte = registers::<T>();
+
+        uarte.events_endrx.reset();
+        uarte.events_endtx.reset();
+        uarte.events_rxdrdy.reset();
+        uarte.events_rxto.reset();
+        uarte.events_rxstarted.reset();
+        uarte.events_error.reset();
+        uarte.shorts.reset();
+        uarte.intenset.write(|w| {
+            w.endrx().set();
+            w.rxstarted().set();
+            w.endtx().set();
+            w.rxto().set();
+            w.error().set()
+        });
+
+        cm_interrupt::free(|cs| {
+            let mut state = State {
+                _uarte: serial,
+                tx: Deque::new(),
+                rx: Deque::new(),
+                tx_busy: false,
+                tx_next: 0,
+                tx_next_len: 0,
+                rx_state: Rx::Running,
+                rx_current: 0,
+                error: None,
+            };
+            state.start_rx(&shared.dma, 0);
+            *shared.state.borrow(cs).borrow_mut() = Some(state);
+        });
+        unsafe {
+            NVIC::unmask(T::INTERRUPT);
+        }
+
+        UartePort { shared }
+    }
+
+    /// Sets up `uarte` on `pins` with the line settings in `config` and starts receiving.
+    ///
+    /// For RTS/CTS flow control, `pins.cts` and `pins.rts` must be set.
+    pub fn with_config(
+        shared: &'static Shared<T, TX_LEN, RX_LEN>,
+        uarte: T,
+        pins: Pins,
+        config: &SerialConfig,
+    ) -> Result<UartePort<T, TX_LEN, RX_LEN>, ConfigError> {
+        let baudrate = baudrate(config.baud_rate)?;
+        let flow_control = config.flow_control == FlowControl::RtsCts;
+        if flow_control && (pins.cts.is_none() || pins.rts.is_none()) {
+            return Err(ConfigError::MissingFlowControlPins);
+        }
+        let parity = match config.parity {
+            Parity::None => uarte::Parity::EXCLUDED,
+            Parity::Even | Parity::Odd => uarte::Parity::INCLUDED,
+        };
+        let serial = Uarte::new(uarte, pins, parity, baudrate);
+
+        // The HAL only knows about even parity and one stop bit
+        let mut bits = 0;
+        if flow_control {
+            bits |= CONFIG_HWFC;
+        }
+        if config.stop_bits == StopBits::Two {
+            bits |= CONFIG_STOP_TWO;
+        }
+        if config.parity == Parity::Odd {
+            bits |= CONFIG_PARITYTYPE_ODD;
+        }
+        let mask = CONFIG_HWFC | CONFIG_STOP_TWO | CONFIG_PARITYTYPE_ODD;
+        registers::<T>()
+            .config
+            .modify(|r, w| unsafe { w.bits(r.bits() & !mask | bits) });
+
+        Ok(UartePort::new(shared, serial))
+    }
+
+    /// Switches to another baud rate once everything written so far has been sent.
+    pub fn set_baud_rate(&mut self, baud_rate: u32) -> Result<(), ConfigError> {
+        let baudrate = baudrate(baud_rate)?;
+        nb::block!(serial::Write::flush(self)).ok();
+        registers::<T>()
+            .baudrate
+            .write(|w| w.baudrate().variant(baudrate));
+        Ok(())
+    }
+
+    fn with_state<R>(&mut self, f: impl FnOnce(&mut State<T, TX_LEN, RX_LEN>, &Dma) -> R) -> R {
+        let shared = self.shared;
+        cm_interrupt::free(|cs| {
+            f(
+                shared.state.borrow(cs).borrow_mut().as_mut().unwrap(),
+                &shared.dma,
+            )
+        })
+    }
+}
+
+impl<T: Instance, const TX_LEN: usize, const RX_LEN: usize> fmt::Write
+    for UartePort<T, TX_LEN, RX_LEN>
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            nb::block!(serial::Write::write(self, byte)).map_err(|_| fmt::Error)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Instance, const TX_LEN: usize, const RX_LEN: usize> serial::Write<u8>
+    for UartePort<T, TX_LEN, RX_LEN>
+{
+    type Error = Error;
+
+    fn write(&mut self, b: u8) -> nb::Result<(), Self::Error> {
+        self.with_state(|state, dma| {
+            state.tx.push_back(b).map_err(|_| nb::Error::WouldBlock)?;
+            state.fill_tx(dma);
+            state.start_tx(dma);
+            Ok(())
+        })
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        self.with_state(|state, _| {
+            if state.tx_busy || state.tx_next_len > 0 || !state.tx.is_empty() {
+                Err(nb::Error::WouldBlock)
+            } else {
+                Ok(())
+            }
+        })
+    }
+}
+
+impl<T: Instance, const TX_LEN: usize, const RX_LEN: usize> bserial::write::Default<u8>
+    for UartePort<T, TX_LEN, RX_LEN>
+{
+}
+
+impl<T: Instance, const TX_LEN: usize, const RX_LEN: usize> serial::Read<u8>
+    for UartePort<T, TX_LEN, RX_LEN>
+{
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        self.with_state(|state, _| {
+            if let Some(error) = state.error.take() {
+                return Err(nb::Error::Other(error));
+            }
+            match state.rx.pop_front() {
+                Some(byte) => Ok(byte),
+                None => {
+                    state.collect_rx();
+                    Err(nb::Error::WouldBlock)
+                }
+            }
+        })
+    }
+}
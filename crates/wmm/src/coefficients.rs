@@ -0,0 +1,214 @@
+//! Coefficients of WMM2020, valid from 2020.0 to 2025.0, and WMM2025, valid from 2025.0 to
+//! 2030.0.
+//!
+//! Transcribed from the `WMM.COF` files distributed by NOAA's National Centers for
+//! Environmental Information together with the [WMM2020] and [WMM2025] technical reports.
+//!
+//! [WMM2020]: https://www.ngdc.noaa.gov/geomag/WMM/data/WMMReports/WMM2020_Report.pdf
+//! [WMM2025]: https://www.ncei.noaa.gov/products/world-magnetic-model
+
+use crate::{Coefficient, Model, COEFFICIENTS};
+
+const fn c(g: f32, h: f32, g_dot: f32, h_dot: f32) -> Coefficient {
+    Coefficient { g, h, g_dot, h_dot }
+}
+
+/// The World Magnetic Model for 2020 to 2025.
+pub const WMM2020: Model = Model {
+    epoch: 2020.0,
+    coefficients: TABLE_2020,
+};
+
+/// The World Magnetic Model for 2025 to 2030.
+pub const WMM2025: Model = Model {
+    epoch: 2025.0,
+    coefficients: TABLE_2025,
+};
+
+#[rustfmt::skip]
+const TABLE_2020: [Coefficient; COEFFICIENTS] = [
+    /*  1  0 */ c(-29404.5,     0.0,   6.7,   0.0),
+    /*  1  1 */ c( -1450.7,  4652.9,   7.7, -25.1),
+    /*  2  0 */ c( -2500.0,     0.0, -11.5,   0.0),
+    /*  2  1 */ c(  2982.0, -2991.6,  -7.1, -30.2),
+    /*  2  2 */ c(  1676.8,  -734.8,  -2.2, -23.9),
+    /*  3  0 */ c(  1363.9,     0.0,   2.8,   0.0),
+    /*  3  1 */ c( -2381.0,   -82.2,  -6.2,   5.7),
+    /*  3  2 */ c(  1236.2,   241.8,   3.4,  -1.0),
+    /*  3  3 */ c(   525.7,  -542.9, -12.2,   1.1),
+    /*  4  0 */ c(   903.1,     0.0,  -1.1,   0.0),
+    /*  4  1 */ c(   809.4,   282.0,  -1.6,   0.2),
+    /*  4  2 */ c(    86.2,  -158.4,  -6.0,   6.9),
+    /*  4  3 */ c(  -309.4,   199.8,   5.4,   3.7),
+    /*  4  4 */ c(    47.9,  -350.1,  -5.5,  -5.6),
+    /*  5  0 */ c(  -234.4,     0.0,  -0.3,   0.0),
+    /*  5  1 */ c(   363.1,    47.7,   0.6,   0.1),
+    /*  5  2 */ c(   187.8,   208.4,  -0.7,   2.5),
+    /*  5  3 */ c(  -140.7,  -121.3,   0.1,  -0.9),
+    /*  5  4 */ c(  -151.2,    32.2,   1.2,   3.0),
+    /*  5  5 */ c(    13.7,    99.1,   1.0,   0.5),
+    /*  6  0 */ c(    65.9,     0.0,  -0.6,   0.0),
+    /*  6  1 */ c(    65.6,   -19.1,  -0.4,   0.1),
+    /*  6  2 */ c(    73.0,    25.0,   0.5,  -1.8),
+    /*  6  3 */ c(  -121.5,    52.7,   1.4,  -1.4),
+    /*  6  4 */ c(   -36.2,   -64.4,  -1.4,   0.9),
+    /*  6  5 */ c(    13.5,     9.0,  -0.0,   0.1),
+    /*  6  6 */ c(   -64.7,    68.1,   0.8,   1.0),
+    /*  7  0 */ c(    80.6,     0.0,  -0.1,   0.0),
+    /*  7  1 */ c(   -76.8,   -51.4,  -0.3,   0.5),
+    /*  7  2 */ c(    -8.3,   -16.8,  -0.1,   0.6),
+    /*  7  3 */ c(    56.5,     2.3,   0.7,  -0.7),
+    /*  7  4 */ c(    15.8,    23.5,   0.2,  -0.2),
+    /*  7  5 */ c(     6.4,    -2.2,  -0.5,  -1.2),
+    /*  7  6 */ c(    -7.2,   -27.2,  -0.8,   0.2),
+    /*  7  7 */ c(     9.8,    -1.9,   1.0,   0.3),
+    /*  8  0 */ c(    23.6,     0.0,  -0.1,   0.0),
+    /*  8  1 */ c(     9.8,     8.4,   0.1,  -0.3),
+    /*  8  2 */ c(   -17.5,   -15.3,  -0.1,   0.7),
+    /*  8  3 */ c(    -0.4,    12.8,   0.5,  -0.2),
+    /*  8  4 */ c(   -21.1,   -11.8,  -0.1,   0.5),
+    /*  8  5 */ c(    15.3,    14.9,   0.4,  -0.3),
+    /*  8  6 */ c(    13.7,     3.6,   0.5,  -0.5),
+    /*  8  7 */ c(   -16.5,    -6.9,   0.0,   0.4),
+    /*  8  8 */ c(    -0.3,     2.8,   0.4,   0.1),
+    /*  9  0 */ c(     5.0,     0.0,  -0.1,   0.0),
+    /*  9  1 */ c(     8.2,   -23.3,  -0.2,  -0.3),
+    /*  9  2 */ c(     2.9,    11.1,  -0.0,   0.2),
+    /*  9  3 */ c(    -1.4,     9.8,   0.4,  -0.4),
+    /*  9  4 */ c(    -1.1,    -5.1,  -0.3,   0.4),
+    /*  9  5 */ c(   -13.3,    -6.2,  -0.0,   0.1),
+    /*  9  6 */ c(     1.1,     7.8,   0.3,  -0.0),
+    /*  9  7 */ c(     8.9,     0.4,  -0.0,  -0.2),
+    /*  9  8 */ c(    -9.3,    -1.5,  -0.0,   0.5),
+    /*  9  9 */ c(   -11.9,     9.7,  -0.4,   0.2),
+    /* 10  0 */ c(    -1.9,     0.0,   0.0,   0.0),
+    /* 10  1 */ c(    -6.2,     3.4,  -0.0,  -0.0),
+    /* 10  2 */ c(    -0.1,    -0.2,  -0.0,   0.1),
+    /* 10  3 */ c(     1.7,     3.5,   0.2,  -0.3),
+    /* 10  4 */ c(    -0.9,     4.8,  -0.1,   0.1),
+    /* 10  5 */ c(     0.6,    -8.6,  -0.2,  -0.2),
+    /* 10  6 */ c(    -0.9,    -0.1,  -0.0,   0.1),
+    /* 10  7 */ c(     1.9,    -4.2,  -0.1,  -0.0),
+    /* 10  8 */ c(     1.4,    -3.4,  -0.2,  -0.1),
+    /* 10  9 */ c(    -2.4,    -0.1,  -0.1,   0.2),
+    /* 10 10 */ c(    -3.9,    -8.8,  -0.0,  -0.0),
+    /* 11  0 */ c(     3.0,     0.0,  -0.0,   0.0),
+    /* 11  1 */ c(    -1.4,    -0.0,  -0.1,  -0.0),
+    /* 11  2 */ c(    -2.5,     2.6,  -0.0,   0.1),
+    /* 11  3 */ c(     2.4,    -0.5,   0.0,   0.0),
+    /* 11  4 */ c(    -0.9,    -0.4,  -0.0,   0.2),
+    /* 11  5 */ c(     0.3,     0.6,  -0.1,  -0.0),
+    /* 11  6 */ c(    -0.7,    -0.2,   0.0,   0.0),
+    /* 11  7 */ c(    -0.1,    -1.7,  -0.0,   0.1),
+    /* 11  8 */ c(     1.4,    -1.6,  -0.1,  -0.0),
+    /* 11  9 */ c(    -0.6,    -3.0,  -0.1,  -0.1),
+    /* 11 10 */ c(     0.2,    -2.0,  -0.1,   0.0),
+    /* 11 11 */ c(     3.1,    -2.6,  -0.1,  -0.0),
+    /* 12  0 */ c(    -2.0,     0.0,   0.0,   0.0),
+    /* 12  1 */ c(    -0.1,    -1.2,  -0.0,  -0.0),
+    /* 12  2 */ c(     0.5,     0.5,  -0.0,   0.0),
+    /* 12  3 */ c(     1.3,     1.3,   0.0,  -0.1),
+    /* 12  4 */ c(    -1.2,    -1.8,  -0.0,   0.1),
+    /* 12  5 */ c(     0.7,     0.1,  -0.0,  -0.0),
+    /* 12  6 */ c(     0.3,     0.7,   0.0,   0.0),
+    /* 12  7 */ c(     0.5,    -0.1,  -0.0,  -0.0),
+    /* 12  8 */ c(    -0.2,     0.6,   0.0,   0.1),
+    /* 12  9 */ c(    -0.5,     0.2,  -0.0,  -0.0),
+    /* 12 10 */ c(     0.1,    -0.9,  -0.0,  -0.0),
+    /* 12 11 */ c(    -1.1,    -0.0,  -0.0,   0.0),
+    /* 12 12 */ c(    -0.3,     0.5,  -0.1,  -0.1),
+];
+
+#[rustfmt::skip]
+const TABLE_2025: [Coefficient; COEFFICIENTS] = [
+    /*  1  0 */ c(-29351.8,     0.0,  12.0,   0.0),
+    /*  1  1 */ c( -1410.8,  4545.4,   9.7, -21.5),
+    /*  2  0 */ c( -2556.6,     0.0, -11.6,   0.0),
+    /*  2  1 */ c(  2951.1, -3133.6,  -5.2, -27.7),
+    /*  2  2 */ c(  1649.3,  -815.1,  -8.0, -12.1),
+    /*  3  0 */ c(  1361.0,     0.0,  -1.3,   0.0),
+    /*  3  1 */ c( -2404.1,   -56.6,  -4.2,   4.0),
+    /*  3  2 */ c(  1243.8,   237.5,   0.4,  -0.3),
+    /*  3  3 */ c(   453.6,  -549.5, -15.6,  -4.1),
+    /*  4  0 */ c(   895.0,     0.0,  -1.6,   0.0),
+    /*  4  1 */ c(   799.5,   278.6,  -2.4,  -1.1),
+    /*  4  2 */ c(    55.7,  -133.9,  -6.0,   4.1),
+    /*  4  3 */ c(  -281.1,   212.0,   5.6,   1.6),
+    /*  4  4 */ c(    12.1,  -375.6,  -7.0,  -4.4),
+    /*  5  0 */ c(  -233.2,     0.0,   0.6,   0.0),
+    /*  5  1 */ c(   368.9,    45.4,   1.4,  -0.5),
+    /*  5  2 */ c(   187.2,   220.2,   0.0,   2.2),
+    /*  5  3 */ c(  -138.7,  -122.9,   0.6,   0.4),
+    /*  5  4 */ c(  -142.0,    43.0,   2.2,   1.7),
+    /*  5  5 */ c(    20.9,   106.1,   0.9,   1.9),
+    /*  6  0 */ c(    64.4,     0.0,  -0.2,   0.0),
+    /*  6  1 */ c(    63.8,   -18.4,  -0.4,   0.3),
+    /*  6  2 */ c(    76.9,    16.8,   0.9,  -1.6),
+    /*  6  3 */ c(  -115.7,    48.8,   1.2,  -0.4),
+    /*  6  4 */ c(   -40.9,   -59.8,  -0.9,   0.9),
+    /*  6  5 */ c(    14.9,    10.9,   0.3,   0.7),
+    /*  6  6 */ c(   -60.7,    72.7,   0.9,   0.9),
+    /*  7  0 */ c(    79.5,     0.0,  -0.0,   0.0),
+    /*  7  1 */ c(   -77.0,   -48.9,  -0.1,   0.6),
+    /*  7  2 */ c(    -8.8,   -14.4,  -0.1,   0.5),
+    /*  7  3 */ c(    59.3,    -1.0,   0.5,  -0.8),
+    /*  7  4 */ c(    15.8,    23.4,  -0.1,   0.0),
+    /*  7  5 */ c(     2.5,    -7.4,  -0.8,  -1.0),
+    /*  7  6 */ c(   -11.1,   -25.1,  -0.8,   0.6),
+    /*  7  7 */ c(    14.2,    -2.3,   0.8,  -0.2),
+    /*  8  0 */ c(    23.2,     0.0,  -0.1,   0.0),
+    /*  8  1 */ c(    10.8,     7.1,   0.2,  -0.2),
+    /*  8  2 */ c(   -17.5,   -12.6,   0.0,   0.5),
+    /*  8  3 */ c(     2.0,    11.4,   0.5,  -0.4),
+    /*  8  4 */ c(   -21.7,    -9.7,  -0.1,   0.4),
+    /*  8  5 */ c(    16.9,    12.7,   0.3,  -0.5),
+    /*  8  6 */ c(    15.0,     0.7,   0.2,  -0.6),
+    /*  8  7 */ c(   -16.8,    -5.2,  -0.0,   0.3),
+    /*  8  8 */ c(     0.9,     3.9,   0.2,   0.2),
+    /*  9  0 */ c(     4.6,     0.0,  -0.0,   0.0),
+    /*  9  1 */ c(     7.8,   -24.8,  -0.1,  -0.3),
+    /*  9  2 */ c(     3.0,    12.2,   0.1,   0.3),
+    /*  9  3 */ c(    -0.2,     8.3,   0.3,  -0.3),
+    /*  9  4 */ c(    -2.5,    -3.3,  -0.3,   0.3),
+    /*  9  5 */ c(   -13.1,    -5.2,   0.0,   0.2),
+    /*  9  6 */ c(     2.4,     7.2,   0.3,  -0.1),
+    /*  9  7 */ c(     8.6,    -0.6,  -0.1,  -0.2),
+    /*  9  8 */ c(    -8.7,     0.8,   0.1,   0.4),
+    /*  9  9 */ c(   -12.9,    10.0,  -0.1,   0.1),
+    /* 10  0 */ c(    -1.3,     0.0,   0.1,   0.0),
+    /* 10  1 */ c(    -6.4,     3.3,   0.0,   0.0),
+    /* 10  2 */ c(     0.2,     0.0,   0.1,  -0.0),
+    /* 10  3 */ c(     2.0,     2.4,   0.1,  -0.2),
+    /* 10  4 */ c(    -1.0,     5.3,  -0.0,   0.1),
+    /* 10  5 */ c(    -0.6,    -9.1,  -0.3,  -0.1),
+    /* 10  6 */ c(    -0.9,     0.4,   0.0,   0.1),
+    /* 10  7 */ c(     1.5,    -4.2,  -0.1,   0.0),
+    /* 10  8 */ c(     0.9,    -3.8,  -0.1,  -0.1),
+    /* 10  9 */ c(    -2.7,     0.9,  -0.0,   0.2),
+    /* 10 10 */ c(    -3.9,    -9.1,  -0.0,  -0.0),
+    /* 11  0 */ c(     2.9,     0.0,   0.0,   0.0),
+    /* 11  1 */ c(    -1.5,     0.0,  -0.0,  -0.0),
+    /* 11  2 */ c(    -2.5,     2.9,   0.0,   0.1),
+    /* 11  3 */ c(     2.4,    -0.6,   0.0,  -0.0),
+    /* 11  4 */ c(    -0.6,     0.2,   0.0,   0.1),
+    /* 11  5 */ c(    -0.1,     0.5,  -0.1,  -0.0),
+    /* 11  6 */ c(    -0.6,    -0.3,   0.0,  -0.0),
+    /* 11  7 */ c(    -0.1,    -1.2,  -0.0,   0.1),
+    /* 11  8 */ c(     1.1,    -1.7,  -0.1,  -0.0),
+    /* 11  9 */ c(    -1.0,    -2.9,  -0.1,   0.0),
+    /* 11 10 */ c(    -0.2,    -1.8,  -0.1,   0.0),
+    /* 11 11 */ c(     2.6,    -2.3,  -0.1,   0.0),
+    /* 12  0 */ c(    -2.0,     0.0,   0.0,   0.0),
+    /* 12  1 */ c(    -0.2,    -1.3,   0.0,  -0.0),
+    /* 12  2 */ c(     0.3,     0.7,  -0.0,   0.0),
+    /* 12  3 */ c(     1.2,     1.0,  -0.0,  -0.1),
+    /* 12  4 */ c(    -1.3,    -1.4,  -0.0,   0.1),
+    /* 12  5 */ c(     0.6,    -0.0,  -0.0,  -0.0),
+    /* 12  6 */ c(     0.6,     0.6,   0.1,  -0.0),
+    /* 12  7 */ c(     0.5,    -0.1,  -0.0,  -0.0),
+    /* 12  8 */ c(    -0.1,     0.8,   0.0,   0.0),
+    /* 12  9 */ c(    -0.4,     0.1,   0.0,  -0.0),
+    /* 12 10 */ c(    -0.2,    -1.0,  -0.1,  -0.0),
+    /* 12 11 */ c(    -1.3,     0.1,  -0.0,   0.0),
+    /* 12 12 */ c(    -0.7,     0.2,  -0.1,  -0.1),
+];
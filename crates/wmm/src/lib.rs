@@ -0,0 +1,232 @@
+//! The World Magnetic Model, small enough to evaluate on a microcontroller.
+//!
+//! The [WMM] describes the Earth's main magnetic field as a spherical harmonic expansion up
+//! to degree and order 12, i.e. 90 pairs of Gauss coefficients plus their yearly change. That
+//! table takes less than 2 KiB of flash, and evaluating it for a single position needs a
+//! couple of hundred floating point operations, so a compass can work out its own
+//! declination instead of relying on a value looked up by hand.
+//!
+//! Every model is published for five years, after which the coefficients need to be updated.
+//! [`Model::is_valid`] tells whether a date is covered; the model still returns a field
+//! outside that range, it just gets less accurate with every year. [`model_for`] picks the
+//! model made for a date out of the ones shipped with this crate.
+//!
+//! [WMM]: https://www.ncei.noaa.gov/products/world-magnetic-model
+
+#![no_std]
+
+mod coefficients;
+
+use libm::{asinf, atan2f, cosf, sinf, sqrtf};
+
+pub use coefficients::{WMM2020, WMM2025};
+
+/// The models this crate ships, oldest first.
+pub const MODELS: [Model; 2] = [WMM2020, WMM2025];
+
+/// Highest degree (and order) of the expansion.
+pub const MAX_DEGREE: usize = 12;
+
+/// Number of `(n, m)` pairs with `1 <= n <= MAX_DEGREE` and `0 <= m <= n`.
+pub const COEFFICIENTS: usize = MAX_DEGREE * (MAX_DEGREE + 3) / 2;
+
+/// Radius of the geomagnetic reference sphere in km.
+const REFERENCE_RADIUS_KM: f32 = 6371.2;
+
+/// Semi-major axis of the WGS 84 ellipsoid in km.
+const WGS84_A_KM: f32 = 6378.137;
+
+/// Flattening of the WGS 84 ellipsoid.
+const WGS84_F: f32 = 1.0 / 298.257_23;
+
+/// Gauss coefficients of one degree `n` and order `m`, in nT and nT per year.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coefficient {
+    pub g: f32,
+    pub h: f32,
+    pub g_dot: f32,
+    pub h_dot: f32,
+}
+
+/// A set of coefficients valid for five years from `epoch`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Model {
+    /// Decimal year the coefficients refer to.
+    pub epoch: f32,
+    /// Coefficients ordered by degree, then order: `(1, 0), (1, 1), (2, 0), ...`.
+    pub coefficients: [Coefficient; COEFFICIENTS],
+}
+
+/// The magnetic field at a position, in nT, in the local north, east, down frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Field {
+    /// Northward component.
+    pub x: f32,
+    /// Eastward component.
+    pub y: f32,
+    /// Downward component.
+    pub z: f32,
+}
+
+impl Model {
+    /// Whether `year`, in decimal years, lies within the five years the model is made for.
+    pub fn is_valid(&self, year: f32) -> bool {
+        year >= self.epoch && year < self.epoch + 5.0
+    }
+
+    /// Evaluates the model at a geodetic `latitude` and `longitude` in degrees (north and
+    /// east positive), `height_km` above the WGS 84 ellipsoid and date `year` in decimal
+    /// years, e.g. `2022.5` for the beginning of July 2022.
+    ///
+    /// ```
+    /// let field = wmm::WMM2020.field(0.0, 120.0, 0.0, 2020.0);
+    ///
+    /// assert!((field.x - 39624.3).abs() < 1.0);
+    /// assert!((field.declination() - 0.16).abs() < 0.01);
+    /// ```
+    pub fn field(&self, latitude: f32, longitude: f32, height_km: f32, year: f32) -> Field {
+        let latitude = latitude.to_radians();
+        let longitude = longitude.to_radians();
+        let dt = year - self.epoch;
+
+        // Geodetic to geocentric spherical coordinates
+        let e2 = WGS84_F * (2.0 - WGS84_F);
+        let (sin_lat, cos_lat) = (sinf(latitude), cosf(latitude));
+        let rc = WGS84_A_KM / sqrtf(1.0 - e2 * sin_lat * sin_lat);
+        let p = (rc + height_km) * cos_lat;
+        let z = (rc * (1.0 - e2) + height_km) * sin_lat;
+        let r = sqrtf(p * p + z * z);
+        let geocentric_latitude = asinf(z / r);
+
+        // Schmidt semi-normalised associated Legendre functions of the colatitude and their
+        // derivatives with respect to it
+        let (cos_theta, sin_theta) = (sinf(geocentric_latitude), cosf(geocentric_latitude));
+        let (p, dp) = legendre(cos_theta, sin_theta);
+
+        let mut cos_ml = [0.0; MAX_DEGREE + 1];
+        let mut sin_ml = [0.0; MAX_DEGREE + 1];
+        for (m, (c, s)) in cos_ml.iter_mut().zip(sin_ml.iter_mut()).enumerate() {
+            *c = cosf(m as f32 * longitude);
+            *s = sinf(m as f32 * longitude);
+        }
+
+        let (mut north, mut east, mut down) = (0.0, 0.0, 0.0);
+        let ratio = REFERENCE_RADIUS_KM / r;
+        let mut ratio_n = ratio * ratio;
+        let mut coefficients = self.coefficients.iter();
+        for n in 1..=MAX_DEGREE {
+            ratio_n *= ratio;
+            for m in 0..=n {
+                let c = coefficients.next().unwrap();
+                let g = c.g + dt * c.g_dot;
+                let h = c.h + dt * c.h_dot;
+                let gh_cos = g * cos_ml[m] + h * sin_ml[m];
+                let gh_sin = g * sin_ml[m] - h * cos_ml[m];
+
+                north += ratio_n * gh_cos * dp[n][m];
+                east += ratio_n * m as f32 * gh_sin * p[n][m];
+                down -= ratio_n * (n + 1) as f32 * gh_cos * p[n][m];
+            }
+        }
+        // The east component is undefined right at the poles, where all meridians meet
+        east /= sin_theta.max(f32::EPSILON);
+
+        // Rotate from the geocentric back into the geodetic frame
+        let psi = geocentric_latitude - latitude;
+        let (sin_psi, cos_psi) = (sinf(psi), cosf(psi));
+        Field {
+            x: north * cos_psi - down * sin_psi,
+            y: east,
+            z: north * sin_psi + down * cos_psi,
+        }
+    }
+}
+
+/// The model to use for `year`, in decimal years: the newest one that starts at or before it.
+/// Dates after the newest model has run out still get the newest one, dates before the oldest
+/// one the oldest.
+///
+/// ```
+/// assert_eq!(wmm::model_for(2024.9).epoch, 2020.0);
+/// assert_eq!(wmm::model_for(2025.0).epoch, 2025.0);
+/// assert_eq!(wmm::model_for(2031.0).epoch, 2025.0);
+/// ```
+pub fn model_for(year: f32) -> &'static Model {
+    MODELS
+        .iter()
+        .rev()
+        .find(|model| model.epoch <= year)
+        .unwrap_or(&MODELS[0])
+}
+
+impl Field {
+    /// Angle between magnetic and true north in degrees, positive when magnetic north lies
+    /// east of true north.
+    pub fn declination(&self) -> f32 {
+        atan2f(self.y, self.x).to_degrees()
+    }
+
+    /// Angle between the field and the horizontal plane in degrees, positive downwards.
+    pub fn inclination(&self) -> f32 {
+        atan2f(self.z, self.horizontal_intensity()).to_degrees()
+    }
+
+    /// Strength of the horizontal part of the field in nT.
+    pub fn horizontal_intensity(&self) -> f32 {
+        sqrtf(self.x * self.x + self.y * self.y)
+    }
+
+    /// Strength of the field in nT.
+    pub fn total_intensity(&self) -> f32 {
+        sqrtf(self.x * self.x + self.y * self.y + self.z * self.z)
+    }
+}
+
+type Table = [[f32; MAX_DEGREE + 1]; MAX_DEGREE + 1];
+
+/// Returns `P(n, m)` and `dP(n, m) / dθ` for colatitude `θ`, Schmidt semi-normalised.
+fn legendre(cos_theta: f32, sin_theta: f32) -> (Table, Table) {
+    let mut p = [[0.0; MAX_DEGREE + 1]; MAX_DEGREE + 1];
+    let mut dp = [[0.0; MAX_DEGREE + 1]; MAX_DEGREE + 1];
+
+    // Gauss normalised functions first, they have the simplest recursion
+    p[0][0] = 1.0;
+    for n in 1..=MAX_DEGREE {
+        for m in 0..=n {
+            if m == n {
+                p[n][n] = sin_theta * p[n - 1][n - 1];
+                dp[n][n] = sin_theta * dp[n - 1][n - 1] + cos_theta * p[n - 1][n - 1];
+            } else {
+                let k = if n == 1 {
+                    0.0
+                } else {
+                    ((n - 1) * (n - 1) - m * m) as f32 / ((2 * n - 1) * (2 * n - 3)) as f32
+                };
+                let (p2, dp2) = if n >= 2 {
+                    (p[n - 2][m], dp[n - 2][m])
+                } else {
+                    (0.0, 0.0)
+                };
+                p[n][m] = cos_theta * p[n - 1][m] - k * p2;
+                dp[n][m] = cos_theta * dp[n - 1][m] - sin_theta * p[n - 1][m] - k * dp2;
+            }
+        }
+    }
+
+    // Then scale them to Schmidt semi-normalisation
+    let mut scale_n0 = 1.0;
+    for n in 1..=MAX_DEGREE {
+        scale_n0 *= (2 * n - 1) as f32 / n as f32;
+        let mut scale = scale_n0;
+        for m in 0..=n {
+            if m > 0 {
+                let factor = if m == 1 { 2 } else { 1 };
+                scale *= sqrtf(((n - m + 1) * factor) as f32 / (n + m) as f32);
+            }
+            p[n][m] *= scale;
+            dp[n][m] *= scale;
+        }
+    }
+
+    (p, dp)
+}
@@ -0,0 +1,44 @@
+//! Choosing between the shipped models, and WMM2025 picking up where WMM2020 left off.
+
+use wmm::{model_for, WMM2020, WMM2025};
+
+#[test]
+fn model_by_year() {
+    assert_eq!(model_for(2019.0), &WMM2020);
+    assert_eq!(model_for(2020.0), &WMM2020);
+    assert_eq!(model_for(2024.99), &WMM2020);
+    assert_eq!(model_for(2025.0), &WMM2025);
+    assert_eq!(model_for(2029.99), &WMM2025);
+    // Past the newest model it is still the best guess there is
+    assert_eq!(model_for(2031.0), &WMM2025);
+}
+
+#[test]
+fn validity() {
+    assert!(WMM2025.is_valid(2025.0));
+    assert!(WMM2025.is_valid(2029.99));
+    assert!(!WMM2025.is_valid(2024.99));
+    assert!(!WMM2025.is_valid(2030.0));
+}
+
+/// At 2025.0 WMM2020 is extrapolated five years and WMM2025 is fresh. The field doesn't jump,
+/// so apart from what WMM2020's secular variation got wrong they agree.
+#[test]
+fn continuous_at_2025() {
+    for &latitude in [-80.0, -45.0, 0.0, 45.0, 80.0].iter() {
+        for &longitude in [0.0, 60.0, 120.0, 180.0, 240.0, 300.0].iter() {
+            let old = WMM2020.field(latitude, longitude, 0.0, 2025.0);
+            let new = WMM2025.field(latitude, longitude, 0.0, 2025.0);
+            for &(old, new) in [(old.x, new.x), (old.y, new.y), (old.z, new.z)].iter() {
+                assert!(
+                    (old - new).abs() < 250.0,
+                    "{}, {}: {} nT with WMM2020, {} nT with WMM2025",
+                    latitude,
+                    longitude,
+                    old,
+                    new
+                );
+            }
+        }
+    }
+}
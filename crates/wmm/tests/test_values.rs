@@ -0,0 +1,83 @@
+//! The test values published with the WMM2020 technical report, table 19.
+
+use wmm::WMM2020;
+
+struct TestValue {
+    year: f32,
+    height_km: f32,
+    latitude: f32,
+    longitude: f32,
+    x: f32,
+    y: f32,
+    z: f32,
+    h: f32,
+    f: f32,
+    i: f32,
+    d: f32,
+}
+
+#[rustfmt::skip]
+const TEST_VALUES: [TestValue; 12] = [
+    TestValue { year: 2020.0, height_km: 0.0, latitude: 80.0, longitude: 0.0, x: 6570.4, y: -146.3, z: 54606.0, h: 6572.0, f: 55000.1, i: 83.14, d: -1.28 },
+    TestValue { year: 2020.0, height_km: 0.0, latitude: 0.0, longitude: 120.0, x: 39624.3, y: 109.9, z: -10932.5, h: 39624.4, f: 41104.9, i: -15.42, d: 0.16 },
+    TestValue { year: 2020.0, height_km: 0.0, latitude: -80.0, longitude: 240.0, x: 5940.6, y: 15772.1, z: -52480.8, h: 16853.8, f: 55120.6, i: -72.20, d: 69.36 },
+    TestValue { year: 2020.0, height_km: 100.0, latitude: 80.0, longitude: 0.0, x: 6261.8, y: -185.5, z: 52429.1, h: 6264.5, f: 52802.0, i: 83.19, d: -1.70 },
+    TestValue { year: 2020.0, height_km: 100.0, latitude: 0.0, longitude: 120.0, x: 37636.7, y: 104.9, z: -10474.8, h: 37636.9, f: 39067.3, i: -15.55, d: 0.16 },
+    TestValue { year: 2020.0, height_km: 100.0, latitude: -80.0, longitude: 240.0, x: 5744.9, y: 14799.5, z: -49969.4, h: 15875.4, f: 52430.6, i: -72.37, d: 68.78 },
+    TestValue { year: 2022.5, height_km: 0.0, latitude: 80.0, longitude: 0.0, x: 6529.9, y: 1.1, z: 54713.4, h: 6529.9, f: 55101.7, i: 83.19, d: 0.01 },
+    TestValue { year: 2022.5, height_km: 0.0, latitude: 0.0, longitude: 120.0, x: 39684.7, y: -42.2, z: -10809.5, h: 39684.7, f: 41130.5, i: -15.24, d: -0.06 },
+    TestValue { year: 2022.5, height_km: 0.0, latitude: -80.0, longitude: 240.0, x: 6016.5, y: 15776.7, z: -52251.6, h: 16885.0, f: 54912.1, i: -72.09, d: 69.13 },
+    TestValue { year: 2022.5, height_km: 100.0, latitude: 80.0, longitude: 0.0, x: 6224.0, y: -44.5, z: 52527.0, h: 6224.2, f: 52894.5, i: 83.24, d: -0.41 },
+    TestValue { year: 2022.5, height_km: 100.0, latitude: 0.0, longitude: 120.0, x: 37694.0, y: -35.3, z: -10362.0, h: 37694.1, f: 39092.4, i: -15.37, d: -0.05 },
+    TestValue { year: 2022.5, height_km: 100.0, latitude: -80.0, longitude: 240.0, x: 5815.0, y: 14803.0, z: -49755.3, h: 15904.1, f: 52235.4, i: -72.27, d: 68.55 },
+];
+
+/// The published values are rounded to 0.1 nT and 0.01 degrees, single precision adds a
+/// little on top of that.
+const NT_TOLERANCE: f32 = 0.5;
+const DEGREE_TOLERANCE: f32 = 0.01;
+
+fn assert_close(name: &str, value: &TestValue, actual: f32, expected: f32, tolerance: f32) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "{} at {}, {}, {} km, {}: expected {}, got {}",
+        name,
+        value.latitude,
+        value.longitude,
+        value.height_km,
+        value.year,
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn field_components() {
+    for value in TEST_VALUES.iter() {
+        let field = WMM2020.field(value.latitude, value.longitude, value.height_km, value.year);
+        assert_close("X", value, field.x, value.x, NT_TOLERANCE);
+        assert_close("Y", value, field.y, value.y, NT_TOLERANCE);
+        assert_close("Z", value, field.z, value.z, NT_TOLERANCE);
+    }
+}
+
+#[test]
+fn derived_elements() {
+    for value in TEST_VALUES.iter() {
+        let field = WMM2020.field(value.latitude, value.longitude, value.height_km, value.year);
+        let h = field.horizontal_intensity();
+        let f = field.total_intensity();
+        assert_close("H", value, h, value.h, NT_TOLERANCE);
+        assert_close("F", value, f, value.f, NT_TOLERANCE);
+        assert_close("I", value, field.inclination(), value.i, DEGREE_TOLERANCE);
+        assert_close("D", value, field.declination(), value.d, DEGREE_TOLERANCE);
+    }
+}
+
+#[test]
+fn validity() {
+    assert!(WMM2020.is_valid(2020.0));
+    assert!(WMM2020.is_valid(2024.99));
+    assert!(!WMM2020.is_valid(2019.99));
+    assert!(!WMM2020.is_valid(2025.0));
+}
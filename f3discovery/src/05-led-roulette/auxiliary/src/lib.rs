@@ -4,12 +4,12 @@
 
 pub use panic_itm; // panic handler
 
+pub use clock_profile::{self, Profile};
 pub use cortex_m_rt::entry;
 
 pub use stm32f3_discovery::{leds::Leds, stm32f3xx_hal, switch_hal};
 pub use switch_hal::{ActiveHigh, OutputSwitch, Switch, ToggleableOutputSwitch};
 
-use stm32f3xx_hal::prelude::*;
 pub use stm32f3xx_hal::{
     delay::Delay,
     gpio::{gpioe, Output, PushPull},
@@ -17,15 +17,23 @@ pub use stm32f3xx_hal::{
     pac,
 };
 
+use clock_profile::hal_0_7::freeze;
+use stm32f3xx_hal::prelude::*;
+
 pub type LedArray = [Switch<gpioe::PEx<Output<PushPull>>, ActiveHigh>; 8];
 
 pub fn init() -> (Delay, LedArray) {
+    init_with_profile(Profile::default())
+}
+
+/// Like `init`, with the clocks set up as `profile` says. `Delay` adapts to them.
+pub fn init_with_profile(profile: Profile) -> (Delay, LedArray) {
     let device_periphs = pac::Peripherals::take().unwrap();
     let mut reset_and_clock_control = device_periphs.RCC.constrain();
 
     let core_periphs = cortex_m::Peripherals::take().unwrap();
     let mut flash = device_periphs.FLASH.constrain();
-    let clocks = reset_and_clock_control.cfgr.freeze(&mut flash.acr);
+    let clocks = freeze(reset_and_clock_control.cfgr, &mut flash.acr, profile);
     let delay = Delay::new(core_periphs.SYST, clocks);
 
     // initialize user leds
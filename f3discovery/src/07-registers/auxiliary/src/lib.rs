@@ -8,10 +8,13 @@ use panic_itm as _; // panic handler
 pub use cortex_m::{asm::bkpt, iprint, iprintln, peripheral::ITM};
 pub use cortex_m_rt::entry;
 
+pub use mmio_reg::{self, AddressError, Bsrr, Reg, Volatile, RO, RW, WO};
 pub use stm32f3::stm32f303::{self, gpioc::RegisterBlock};
 pub use stm32f3_discovery::stm32f3xx_hal::pac::GPIOE;
 pub use stm32f3_discovery::{leds::Leds, stm32f3xx_hal};
 
+use core::ops::Range;
+
 use stm32f3xx_hal::prelude::*;
 pub use stm32f3xx_hal::stm32;
 
@@ -38,3 +41,28 @@ pub fn init() -> (ITM, &'static RegisterBlock) {
     let core_periphs = cortex_m::Peripherals::take().unwrap();
     (core_periphs.ITM, unsafe { &*stm32f303::GPIOE::ptr() })
 }
+
+/// The GPIO ports A to F, where `Reg::new` accepts addresses. `0x4800_1800` falls just after.
+pub static GPIO_MAP: [Range<usize>; 1] = [0x4800_0000..0x4800_1800];
+
+/// The GPIOE registers this chapter plays with, as `mmio-reg` registers.
+pub struct Gpioe {
+    pub odr: Reg<u32, RW>,
+    pub bsrr: Bsrr,
+}
+
+/// Like `init`, but hands the GPIOE registers out as `mmio-reg` registers instead of the PAC's.
+pub fn init_registers() -> (ITM, Gpioe) {
+    let (itm, _) = init();
+
+    let memory = Volatile::new(&GPIO_MAP);
+    // These are GPIOE's ODR and BSRR, and `init` is done configuring the LEDs
+    let gpioe = unsafe {
+        Gpioe {
+            odr: Reg::new(0x4800_1014, memory).unwrap(),
+            bsrr: Bsrr::new(Reg::new(0x4800_1018, memory).unwrap()),
+        }
+    };
+
+    (itm, gpioe)
+}
@@ -0,0 +1,31 @@
+//! `src/main.rs` without `unsafe` and magic addresses, through `aux7::init_registers`.
+
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+#[allow(unused_imports)]
+use aux7::{entry, iprint, iprintln};
+
+#[entry]
+fn main() -> ! {
+    let (mut itm, mut gpioe) = aux7::init_registers();
+
+    // Turn on the "North" LED (red)
+    gpioe.bsrr.set(9);
+    iprintln!(&mut itm.stim[0], "ODR = 0x{:04x}", gpioe.odr.read());
+
+    // Turn on the "East" LED (green)
+    gpioe.bsrr.set(11);
+    iprintln!(&mut itm.stim[0], "ODR = 0x{:04x}", gpioe.odr.read());
+
+    // Turn off the "North" LED
+    gpioe.bsrr.reset(9);
+    iprintln!(&mut itm.stim[0], "ODR = 0x{:04x}", gpioe.odr.read());
+
+    // Turn off the "East" LED
+    gpioe.bsrr.reset(11);
+    iprintln!(&mut itm.stim[0], "ODR = 0x{:04x}", gpioe.odr.read());
+
+    loop {}
+}
@@ -0,0 +1,46 @@
+//! The `gpio-typestate` pins on top of GPIOE.
+//!
+//! ``` ignore
+//! let pins = aux8::init_pins();
+//! let mut led = pins.p9.into_push_pull_output();
+//! led.set_high();
+//! ```
+
+pub use gpio_typestate::{Input, Output, Pin, Pins, Register, Registers, Unconfigured};
+
+use f3::hal::stm32f30x::GPIOE;
+
+/// The GPIOE registers, as the pins see them.
+pub struct Gpioe {
+    _private: (),
+}
+
+/// There is only one GPIOE, and `init_pins` makes sure only one `Pins` uses it.
+pub(crate) static GPIOE_REGISTERS: Gpioe = Gpioe { _private: () };
+
+impl Registers for Gpioe {
+    fn read(&self, register: Register) -> u32 {
+        // The pins of a port share it, but each one only modifies its own bits
+        let gpioe = unsafe { &*GPIOE::ptr() };
+        match register {
+            Register::Moder => gpioe.moder.read().bits(),
+            Register::Otyper => gpioe.otyper.read().bits(),
+            Register::Idr => gpioe.idr.read().bits(),
+            Register::Odr => gpioe.odr.read().bits(),
+            // Write-only
+            Register::Bsrr => 0,
+        }
+    }
+
+    fn write(&self, register: Register, value: u32) {
+        let gpioe = unsafe { &*GPIOE::ptr() };
+        match register {
+            Register::Moder => gpioe.moder.write(|w| unsafe { w.bits(value) }),
+            Register::Otyper => gpioe.otyper.write(|w| unsafe { w.bits(value) }),
+            // Read-only, writes are ignored
+            Register::Idr => {}
+            Register::Odr => gpioe.odr.write(|w| unsafe { w.bits(value) }),
+            Register::Bsrr => gpioe.bsrr.write(|w| unsafe { w.bits(value) }),
+        }
+    }
+}
@@ -9,7 +9,10 @@ pub use cortex_m::asm::bkpt;
 pub use cortex_m_rt::entry;
 pub use f3::hal::stm32f30x::{gpioc, rcc};
 
+pub mod gpio;
+
 use f3::hal::stm32f30x::{self, GPIOE, RCC};
+use gpio::{Gpioe, Pins};
 
 pub fn init() -> (&'static gpioc::RegisterBlock, &'static rcc::RegisterBlock) {
     // restrict access to the other peripherals
@@ -17,3 +20,14 @@ pub fn init() -> (&'static gpioc::RegisterBlock, &'static rcc::RegisterBlock) {
 
     unsafe { (&*GPIOE::ptr(), &*RCC::ptr()) }
 }
+
+/// Like `init`, but powers GPIOE on and hands its pins out through the type-state layer in
+/// `gpio`, which takes care of `MODER`.
+pub fn init_pins() -> Pins<'static, Gpioe> {
+    (stm32f30x::Peripherals::take().unwrap());
+
+    let rcc = unsafe { &*RCC::ptr() };
+    rcc.ahbenr.modify(|_, w| w.iopeen().set_bit());
+
+    Pins::new(&gpio::GPIOE_REGISTERS)
+}
@@ -0,0 +1,35 @@
+//! The solution of this chapter with the type-state pins of `aux8::gpio`.
+
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use aux8::entry;
+
+#[entry]
+fn main() -> ! {
+    // GPIOE is powered on, but every pin is `Unconfigured`
+    let pins = aux8::init_pins();
+
+    // The compass LEDs are on PE8 to PE15. Forget `into_push_pull_output` and `set_high`
+    // doesn't compile
+    let mut leds = [
+        pins.p8.into_push_pull_output(),
+        pins.p9.into_push_pull_output(),
+        pins.p10.into_push_pull_output(),
+        pins.p11.into_push_pull_output(),
+        pins.p12.into_push_pull_output(),
+        pins.p13.into_push_pull_output(),
+        pins.p14.into_push_pull_output(),
+        pins.p15.into_push_pull_output(),
+    ];
+
+    // Turn on all the LEDs in the compass
+    for led in leds.iter_mut() {
+        led.set_high();
+    }
+
+    aux8::bkpt();
+
+    loop {}
+}
@@ -0,0 +1,218 @@
+//! Delays with the TIM6 basic timer.
+//!
+//! This is what the `delay` function of this chapter turns into once it grows up: it works
+//! whatever the clock configuration is, takes microseconds as well as milliseconds, and
+//! implements the `embedded-hal` traits so that drivers that need a delay can use it.
+//!
+//! It can be used in three ways:
+//!
+//! - blocking, through `DelayMs` and `DelayUs`, which is what the chapter's `delay` does;
+//! - non-blocking, through `CountDown`: `start` the timer, then do something else and check
+//!   with `wait` every now and then whether the time is up;
+//! - interrupt driven, with `start_interrupt`: the processor can even go to sleep until the
+//!   `TIM6_DACUNDER` interrupt fires.
+
+use cortex_m::peripheral::NVIC;
+use embedded_hal::{
+    blocking::delay::{DelayMs, DelayUs},
+    timer::CountDown,
+};
+use stm32f3::stm32f303::{Interrupt, RCC, TIM6};
+use stm32f3_discovery::stm32f3xx_hal::rcc::Clocks;
+use void::Void;
+
+/// A duration in microseconds, what `CountDown::start` takes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MicroSeconds(pub u32);
+
+/// A duration in milliseconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MilliSeconds(pub u32);
+
+impl From<MilliSeconds> for MicroSeconds {
+    fn from(ms: MilliSeconds) -> MicroSeconds {
+        MicroSeconds(ms.0.saturating_mul(1_000))
+    }
+}
+
+/// The interrupt driven one-shot doesn't fit in a single shot of the timer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TooLong;
+
+/// TIM6 used as a one-shot timer.
+pub struct Tim6Delay {
+    tim6: TIM6,
+    /// Frequency of the clock that drives TIM6, in Hz.
+    timer_clock: u32,
+    /// Microseconds still to go after the current shot, `None` when no countdown is running.
+    remaining: Option<u32>,
+}
+
+impl Tim6Delay {
+    /// Powers TIM6 up and sets it up in one pulse mode. `clocks` is what the clock
+    /// configuration was frozen into.
+    pub fn new(tim6: TIM6, clocks: Clocks) -> Tim6Delay {
+        // The APB1 timers run at twice the bus frequency, unless the bus isn't divided down
+        let timer_clock = clock_profile::apb_timer_clock(clocks.hclk().0, clocks.pclk1().0);
+
+        // Nothing else touches APB1ENR concurrently while initializing
+        unsafe { &*RCC::ptr() }
+            .apb1enr
+            .modify(|_, w| w.tim6en().set_bit());
+
+        // OPM: stop after one shot
+        // URS: only overflows raise update events, not the `UG` in `start_shot`
+        tim6.cr1
+            .write(|w| w.opm().set_bit().urs().set_bit().cen().clear_bit());
+
+        Tim6Delay {
+            tim6,
+            timer_clock,
+            remaining: None,
+        }
+    }
+
+    /// Frequency of the clock that drives TIM6, in Hz.
+    pub fn timer_clock(&self) -> u32 {
+        self.timer_clock
+    }
+
+    /// Longest delay a single shot of the timer covers, in microseconds. Longer ones take
+    /// several shots, except with `start_interrupt`.
+    pub fn max_shot_us(&self) -> u32 {
+        clock_profile::max_shot_us(self.timer_clock)
+    }
+
+    /// Gives the timer back.
+    pub fn free(self) -> TIM6 {
+        self.tim6.cr1.modify(|_, w| w.cen().clear_bit());
+        self.tim6
+    }
+
+    /// Starts a one-shot of `us` microseconds that raises the `TIM6_DACUNDER` interrupt when
+    /// it goes off. The handler has to call `Tim6Delay::clear_interrupt`, or it will run over
+    /// and over.
+    pub fn start_interrupt(&mut self, us: u32) -> Result<(), TooLong> {
+        if us > self.max_shot_us() {
+            return Err(TooLong);
+        }
+        self.remaining = None;
+        self.tim6.dier.write(|w| w.uie().set_bit());
+        // Enabling an interrupt whose handler clears it doesn't break any critical section
+        unsafe { NVIC::unmask(Interrupt::TIM6_DACUNDER) };
+        self.start_shot(us);
+        Ok(())
+    }
+
+    /// Clears the update event that raised the `TIM6_DACUNDER` interrupt. To be called from
+    /// the interrupt handler.
+    pub fn clear_interrupt() {
+        // Only clears a flag, which can't interfere with what `Tim6Delay` is doing
+        let tim6 = unsafe { &*TIM6::ptr() };
+        tim6.sr.modify(|_, w| w.uif().clear_bit());
+    }
+
+    /// Stops the interrupt driven one-shot, or the countdown, if one is running.
+    pub fn cancel(&mut self) {
+        self.tim6.cr1.modify(|_, w| w.cen().clear_bit());
+        self.tim6.dier.write(|w| w.uie().clear_bit());
+        self.tim6.sr.modify(|_, w| w.uif().clear_bit());
+        NVIC::mask(Interrupt::TIM6_DACUNDER);
+        self.remaining = None;
+    }
+
+    /// Starts a countdown of `us` microseconds, split into as many shots as it takes.
+    fn start_us(&mut self, us: u32) {
+        self.tim6.dier.write(|w| w.uie().clear_bit());
+        let shot = us.min(self.max_shot_us());
+        self.remaining = Some(us - shot);
+        self.start_shot(shot);
+    }
+
+    /// Programs and starts a single shot of at most `max_shot_us`.
+    fn start_shot(&mut self, us: u32) {
+        let (psc, arr) = clock_profile::shot_timing(self.timer_clock, us);
+
+        self.tim6.cr1.modify(|_, w| w.cen().clear_bit());
+        self.tim6.psc.write(|w| w.psc().bits(psc));
+        self.tim6.arr.write(|w| w.arr().bits(arr));
+        // The prescaler only takes its new value on an update event, generate one
+        self.tim6.egr.write(|w| w.ug().set_bit());
+        self.tim6.sr.modify(|_, w| w.uif().clear_bit());
+        self.tim6.cr1.modify(|_, w| w.cen().set_bit());
+    }
+}
+
+impl DelayUs<u32> for Tim6Delay {
+    fn delay_us(&mut self, us: u32) {
+        self.start_us(us);
+        nb::block!(self.wait()).ok();
+    }
+}
+
+impl DelayUs<u16> for Tim6Delay {
+    fn delay_us(&mut self, us: u16) {
+        self.delay_us(u32::from(us));
+    }
+}
+
+impl DelayUs<u8> for Tim6Delay {
+    fn delay_us(&mut self, us: u8) {
+        self.delay_us(u32::from(us));
+    }
+}
+
+impl DelayMs<u32> for Tim6Delay {
+    fn delay_ms(&mut self, ms: u32) {
+        // Only `u32::MAX` microseconds at a time
+        let mut ms = ms;
+        while ms > 0 {
+            let chunk = ms.min(u32::MAX / 1_000);
+            self.delay_us(chunk * 1_000);
+            ms -= chunk;
+        }
+    }
+}
+
+impl DelayMs<u16> for Tim6Delay {
+    fn delay_ms(&mut self, ms: u16) {
+        self.delay_ms(u32::from(ms));
+    }
+}
+
+impl DelayMs<u8> for Tim6Delay {
+    fn delay_ms(&mut self, ms: u8) {
+        self.delay_ms(u32::from(ms));
+    }
+}
+
+impl CountDown for Tim6Delay {
+    type Time = MicroSeconds;
+
+    fn start<T>(&mut self, count: T)
+    where
+        T: Into<MicroSeconds>,
+    {
+        self.start_us(count.into().0);
+    }
+
+    /// Returns `Ok` once the time is up, and keeps doing so until the next `start`.
+    fn wait(&mut self) -> nb::Result<(), Void> {
+        let remaining = match self.remaining {
+            Some(remaining) => remaining,
+            None => return Ok(()),
+        };
+        if self.tim6.sr.read().uif().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        self.tim6.sr.modify(|_, w| w.uif().clear_bit());
+        if remaining == 0 {
+            self.remaining = None;
+            Ok(())
+        } else {
+            self.start_us(remaining);
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
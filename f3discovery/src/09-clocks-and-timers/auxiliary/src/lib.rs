@@ -5,11 +5,21 @@
 #[allow(unused_extern_crates)] // NOTE(allow) rust-lang/rust#53964
 extern crate panic_itm; // panic handler
 
-pub use cortex_m::asm::{bkpt, nop};
+pub use clock_profile::{self, Profile};
+pub use cortex_m::asm::{bkpt, nop, wfi};
 pub use cortex_m_rt::entry;
-pub use stm32f3::stm32f303::{rcc, tim6, RCC, TIM6};
+pub use embedded_hal::{
+    blocking::delay::{DelayMs, DelayUs},
+    timer::CountDown,
+};
+pub use stm32f3::stm32f303::{interrupt, rcc, tim6, RCC, TIM6};
 pub use stm32f3_discovery::switch_hal;
 
+pub mod delay;
+
+use clock_profile::hal_0_6::freeze;
+use delay::Tim6Delay;
+
 use stm32f3_discovery::{
     leds::Leds,
     stm32f3xx_hal::{prelude::*, stm32},
@@ -41,3 +51,35 @@ pub fn init() -> (
 
     (leds, unsafe { &*RCC::ptr() }, unsafe { &*TIM6::ptr() })
 }
+
+/// Like `init`, but sets TIM6 up as a `Tim6Delay` instead of handing its registers out.
+pub fn init_delay() -> (Leds, Tim6Delay) {
+    init_delay_with_profile(Profile::default())
+}
+
+/// Like `init_delay`, with the clocks set up as `profile` says. `Tim6Delay` adapts to them.
+pub fn init_delay_with_profile(profile: Profile) -> (Leds, Tim6Delay) {
+    let p = stm32::Peripherals::take().unwrap();
+
+    let mut flash = p.FLASH.constrain();
+    let mut rcc = p.RCC.constrain();
+
+    let clocks = freeze(rcc.cfgr, &mut flash.acr, profile);
+
+    let mut gpioe = p.GPIOE.split(&mut rcc.ahb);
+
+    let leds = Leds::new(
+        gpioe.pe8,
+        gpioe.pe9,
+        gpioe.pe10,
+        gpioe.pe11,
+        gpioe.pe12,
+        gpioe.pe13,
+        gpioe.pe14,
+        gpioe.pe15,
+        &mut gpioe.moder,
+        &mut gpioe.otyper,
+    );
+
+    (leds, Tim6Delay::new(p.TIM6, clocks))
+}
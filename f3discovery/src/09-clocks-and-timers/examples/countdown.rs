@@ -0,0 +1,30 @@
+//! Blinks the North LED with a `CountDown`. Nothing blocks, so the loop is free to do other
+//! work between checks of the timer.
+
+#![no_main]
+#![no_std]
+
+use aux9::{delay::MilliSeconds, entry, switch_hal::OutputSwitch, CountDown};
+
+#[entry]
+fn main() -> ! {
+    let (leds, mut timer) = aux9::init_delay();
+    let mut leds = leds.into_array();
+
+    let mut on = false;
+    timer.start(MilliSeconds(500));
+    loop {
+        // Not up yet: `WouldBlock`, go do something else
+        if timer.wait().is_ok() {
+            on = !on;
+            if on {
+                leds[0].on().unwrap();
+            } else {
+                leds[0].off().unwrap();
+            }
+            timer.start(MilliSeconds(500));
+        }
+
+        // Something else, which doesn't have to know about the timer
+    }
+}
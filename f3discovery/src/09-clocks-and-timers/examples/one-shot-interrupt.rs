@@ -0,0 +1,44 @@
+//! Sleeps until the TIM6 interrupt wakes the processor up, instead of polling the timer.
+
+#![no_main]
+#![no_std]
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use aux9::{delay::Tim6Delay, entry, interrupt, switch_hal::OutputSwitch, wfi};
+
+/// Set by the interrupt handler when the one-shot goes off.
+static FIRED: AtomicBool = AtomicBool::new(false);
+
+#[entry]
+fn main() -> ! {
+    let (leds, mut delay) = aux9::init_delay();
+    let mut leds = leds.into_array();
+
+    loop {
+        for curr in 0..8 {
+            let next = (curr + 1) % 8;
+
+            leds[next].on().unwrap();
+            sleep_us(&mut delay, 50_000);
+            leds[curr].off().unwrap();
+            sleep_us(&mut delay, 50_000);
+        }
+    }
+}
+
+fn sleep_us(delay: &mut Tim6Delay, us: u32) {
+    FIRED.store(false, Ordering::Relaxed);
+    delay.start_interrupt(us).unwrap();
+
+    // Other interrupts wake the processor up too, so check who it was
+    while !FIRED.load(Ordering::Relaxed) {
+        wfi();
+    }
+}
+
+#[interrupt]
+fn TIM6_DACUNDER() {
+    Tim6Delay::clear_interrupt();
+    FIRED.store(true, Ordering::Relaxed);
+}
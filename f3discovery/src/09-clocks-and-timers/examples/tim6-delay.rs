@@ -0,0 +1,24 @@
+//! The roulette of this chapter with `aux9::Tim6Delay` instead of a hand written `delay`.
+
+#![no_main]
+#![no_std]
+
+use aux9::{entry, switch_hal::OutputSwitch, DelayMs};
+
+#[entry]
+fn main() -> ! {
+    let (leds, mut delay) = aux9::init_delay();
+    let mut leds = leds.into_array();
+
+    let ms = 50_u16;
+    loop {
+        for curr in 0..8 {
+            let next = (curr + 1) % 8;
+
+            leds[next].on().unwrap();
+            delay.delay_ms(ms);
+            leds[curr].off().unwrap();
+            delay.delay_ms(ms);
+        }
+    }
+}
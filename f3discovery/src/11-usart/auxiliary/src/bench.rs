@@ -0,0 +1,39 @@
+//! Micro-benchmarks timed with the `MonoTimer`, see the `cycle-bench` crate.
+//!
+//! ``` ignore
+//! let mut harness: Harness<_, 4> = Harness::new(mono_timer, 100);
+//! harness.add("nop", &mut || cortex_m::asm::nop()).unwrap();
+//! harness.run(&mut ItmWriter(&mut itm.stim[0])).unwrap();
+//! ```
+
+use core::fmt;
+
+use cortex_m::{interrupt, itm, peripheral::itm::Stim};
+
+pub use cycle_bench::{parse_line, Error, Harness, Platform, Record, Stats, HEADER};
+
+use crate::monotimer::{self, MonoTimer};
+
+impl Platform for MonoTimer {
+    fn cycles(&self) -> u64 {
+        monotimer::cycles()
+    }
+
+    fn frequency(&self) -> u32 {
+        monotimer::hclk()
+    }
+
+    fn without_interrupts(&self, f: &mut dyn FnMut()) {
+        interrupt::free(|_| f())
+    }
+}
+
+/// Prints the report to an ITM stimulus port, like `iprint!` does.
+pub struct ItmWriter<'a>(pub &'a mut Stim);
+
+impl fmt::Write for ItmWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        itm::write_str(self.0, s);
+        Ok(())
+    }
+}
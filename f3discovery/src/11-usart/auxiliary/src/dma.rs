@@ -0,0 +1,261 @@
+//! USART1 with its data moved by DMA1.
+//!
+//! Instead of the processor writing every byte to `TDR` as soon as `TXE` allows it, channel 4
+//! of DMA1 copies a whole buffer to `TDR`, one byte every time the USART asks for one, while
+//! the processor does something else. In the other direction channel 5 copies every byte that
+//! arrives in `RDR` into a ring buffer, wrapping around at its end (circular mode), so nothing
+//! is lost while the program is busy as long as it reads the buffer often enough.
+//!
+//! Bytes on a serial line don't say where a message ends, but the USART can tell when the
+//! line has been idle for the duration of a frame after receiving something. `read_message`
+//! uses that to hand out whatever arrived as one message.
+//!
+//! The position of the DMA in the ring buffer alone can't tell an empty buffer from one that
+//! was filled all the way around, so the half transfer and transfer complete flags of channel 5
+//! are used to count the laps, and bytes that were overwritten before they were read are
+//! reported as an `Overrun`. That works as long as the buffer is looked at, with `available`,
+//! `read` or `read_message`, at least once every `RX_BUFFER_LEN * 3 / 2` received bytes.
+
+use core::sync::atomic::{compiler_fence, Ordering};
+
+use stm32f3_discovery::stm32f3xx_hal::pac::{dma1, DMA1, RCC};
+
+use crate::{serial::SerialPort, usart1};
+
+/// Size of the receive ring buffer.
+pub const RX_BUFFER_LEN: usize = 256;
+
+static mut RX_BUFFER: [u8; RX_BUFFER_LEN] = [0; RX_BUFFER_LEN];
+
+// Bit of the RCC's `AHBENR` register
+const DMA1EN: u32 = 1 << 0;
+
+// Bits of a channel's `CR` register
+const EN: u32 = 1 << 0;
+/// Read from memory, write to the peripheral.
+const DIR: u32 = 1 << 4;
+const CIRC: u32 = 1 << 5;
+/// Increment the memory address after every transfer.
+const MINC: u32 = 1 << 7;
+// Transfer complete flag of channel 4 in `ISR`, and the bit in `IFCR` that clears all of its flags
+const TCIF4: u32 = 1 << 13;
+const CGIF4: u32 = 1 << 12;
+// Transfer complete and half transfer flags of channel 5 in `ISR`, cleared by the same bits of
+// `IFCR`
+const TCIF5: u32 = 1 << 17;
+const HTIF5: u32 = 1 << 18;
+
+// Bits of the USART's `CR3`, `ISR` and `ICR` registers
+const DMAR: u32 = 1 << 6;
+const DMAT: u32 = 1 << 7;
+const IDLE: u32 = 1 << 4;
+
+/// More than `RX_BUFFER_LEN` bytes arrived between two reads, so the oldest ones were
+/// overwritten before they could be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overrun;
+
+/// USART1 with DMA1 channels 4 (transmit) and 5 (receive), which nothing else may use.
+pub struct DmaSerial {
+    usart1: &'static mut usart1::RegisterBlock,
+    /// Where in `RX_BUFFER` the next unread byte is.
+    read_pos: usize,
+    /// Where in `RX_BUFFER` the DMA was about to write when we last looked.
+    write_pos: usize,
+    /// Number of received bytes that haven't been read yet, at most `RX_BUFFER_LEN`.
+    unread: usize,
+    /// Flags of channel 5 that will be set for crossings already counted in `unread`, because
+    /// the DMA got there between us reading the flags and its position.
+    due: u32,
+    /// Whether bytes were overwritten since the last read.
+    overrun: bool,
+    /// Whether a transmission was started and hasn't been waited for yet.
+    writing: bool,
+}
+
+fn dma1() -> &'static dma1::RegisterBlock {
+    // Channels 4 and 5 are only touched through `DmaSerial`, of which there is at most one
+    // because it is made from the only `SerialPort`
+    unsafe { &*DMA1::ptr() }
+}
+
+impl DmaSerial {
+    /// Hands the serial port over to the DMA and starts receiving.
+    pub fn new(serial: SerialPort) -> DmaSerial {
+        let usart1 = serial.free();
+        let dma1 = dma1();
+
+        // Power up DMA1
+        unsafe { &*RCC::ptr() }
+            .ahbenr
+            .modify(|r, w| unsafe { w.bits(r.bits() | DMA1EN) });
+
+        // Receive channel: from RDR into the ring buffer, over and over
+        let rdr = &usart1.rdr as *const _ as u32;
+        // Nothing else accesses the buffer while the DMA writes it, `read` only reads it
+        let buffer = unsafe { RX_BUFFER.as_ptr() } as u32;
+        dma1.ch5.cr.write(|w| unsafe { w.bits(0) });
+        dma1.ifcr.write(|w| unsafe { w.bits(HTIF5 | TCIF5) });
+        dma1.ch5.par.write(|w| unsafe { w.bits(rdr) });
+        dma1.ch5.mar.write(|w| unsafe { w.bits(buffer) });
+        dma1.ch5
+            .ndtr
+            .write(|w| unsafe { w.bits(RX_BUFFER_LEN as u32) });
+        dma1.ch5.cr.write(|w| unsafe { w.bits(MINC | CIRC | EN) });
+
+        // Transmit channel: set up for every transfer in `start_write`
+        dma1.ch4.cr.write(|w| unsafe { w.bits(0) });
+        dma1.ch4
+            .par
+            .write(|w| unsafe { w.bits(&usart1.tdr as *const _ as u32) });
+
+        // Let the USART request transfers, and forget any idle line seen so far
+        usart1
+            .cr3
+            .modify(|r, w| unsafe { w.bits(r.bits() | DMAR | DMAT) });
+        usart1.icr.write(|w| unsafe { w.bits(IDLE) });
+
+        DmaSerial {
+            usart1,
+            read_pos: 0,
+            write_pos: 0,
+            unread: 0,
+            due: 0,
+            overrun: false,
+            writing: false,
+        }
+    }
+
+    /// Starts sending `bytes` and returns right away. If the previous transmission is still
+    /// running, waits for it first.
+    pub fn start_write(&mut self, bytes: &'static [u8]) {
+        // SAFETY: the bytes stay put for as long as the transfer needs them
+        unsafe { self.start(bytes) }
+    }
+
+    /// Sends `bytes` and waits until the last of them has left the USART.
+    pub fn write_all(&mut self, bytes: &[u8]) {
+        // SAFETY: we don't return, and give up the borrow, before the DMA is done
+        unsafe { self.start(bytes) }
+        self.wait_write();
+    }
+
+    /// Whether everything has been sent.
+    pub fn is_write_done(&self) -> bool {
+        !self.writing
+            || (dma1().isr.read().bits() & TCIF4 != 0 && self.usart1.isr.read().tc().bit_is_set())
+    }
+
+    /// Waits until everything has been sent.
+    pub fn wait_write(&mut self) {
+        while !self.is_write_done() {}
+        if self.writing {
+            dma1().ifcr.write(|w| unsafe { w.bits(CGIF4) });
+            self.writing = false;
+        }
+    }
+
+    /// `bytes` must stay valid until the transfer is done.
+    unsafe fn start(&mut self, bytes: &[u8]) {
+        self.wait_write();
+        if bytes.is_empty() {
+            return;
+        }
+
+        let ch4 = &dma1().ch4;
+        // The channel must be disabled to be reprogrammed
+        ch4.cr.write(|w| w.bits(0));
+        ch4.mar.write(|w| w.bits(bytes.as_ptr() as u32));
+        ch4.ndtr.write(|w| w.bits(bytes.len() as u32));
+        // `TC` is set again once the last byte has been sent
+        self.usart1.icr.write(|w| w.tccf().set_bit());
+        // The bytes must be in memory before the DMA goes to read them
+        compiler_fence(Ordering::Release);
+        ch4.cr.write(|w| w.bits(MINC | DIR | EN));
+        self.writing = true;
+    }
+
+    /// Number of received bytes that haven't been read yet.
+    pub fn available(&mut self) -> usize {
+        self.update();
+        self.unread
+    }
+
+    /// Copies as many received bytes into `buffer` as fit and returns how many that were.
+    ///
+    /// If more than `RX_BUFFER_LEN` bytes arrived since the last read, the oldest ones were
+    /// overwritten: that is reported once, and the next read starts at the oldest byte that
+    /// is left.
+    pub fn read(&mut self, buffer: &mut [u8]) -> Result<usize, Overrun> {
+        self.update();
+        if self.overrun {
+            self.overrun = false;
+            return Err(Overrun);
+        }
+
+        let count = self.unread.min(buffer.len());
+        for byte in &mut buffer[..count] {
+            // Only the DMA writes the buffer, and only past the bytes counted as unread
+            *byte = unsafe { RX_BUFFER[self.read_pos] };
+            self.read_pos = (self.read_pos + 1) % RX_BUFFER_LEN;
+        }
+        self.unread -= count;
+        Ok(count)
+    }
+
+    /// Returns a message once the line has gone idle after receiving it, copying it into
+    /// `buffer` like `read` does.
+    ///
+    /// If the message was partly overwritten its start is gone, so instead of what is left of
+    /// it the `Overrun` is returned.
+    pub fn read_message(&mut self, buffer: &mut [u8]) -> Option<Result<usize, Overrun>> {
+        if self.usart1.isr.read().bits() & IDLE == 0 {
+            return None;
+        }
+        self.usart1.icr.write(|w| unsafe { w.bits(IDLE) });
+        let result = self.read(buffer);
+        if result.is_err() {
+            // The rest doesn't end where the line went idle, drop it
+            self.read_pos = self.write_pos;
+            self.unread = 0;
+        }
+        Some(result)
+    }
+
+    /// Counts the bytes the DMA received since the last call.
+    fn update(&mut self) {
+        let dma1 = dma1();
+        // Flags first: a crossing that sets one after this is already in the position
+        let flags = dma1.isr.read().bits() & (HTIF5 | TCIF5);
+        dma1.ifcr.write(|w| unsafe { w.bits(flags) });
+        let write_pos = (RX_BUFFER_LEN - dma1.ch5.ndtr.read().bits() as usize) % RX_BUFFER_LEN;
+        // The DMA wrote the bytes before it moved on, don't look at them any earlier
+        compiler_fence(Ordering::Acquire);
+
+        // The least the DMA can have moved, and the flags that sets
+        let moved = (write_pos + RX_BUFFER_LEN - self.write_pos) % RX_BUFFER_LEN;
+        let end = self.write_pos + moved;
+        let half = RX_BUFFER_LEN / 2;
+        let mut expected = self.due;
+        if (self.write_pos < half && end >= half) || end >= RX_BUFFER_LEN + half {
+            expected |= HTIF5;
+        }
+        if end >= RX_BUFFER_LEN {
+            expected |= TCIF5;
+        }
+        // A flag nothing explains means the DMA went all the way around once more
+        let lapped = flags & !expected != 0;
+        self.due = expected & !flags;
+        self.write_pos = write_pos;
+
+        self.unread += moved;
+        if lapped {
+            self.unread += RX_BUFFER_LEN;
+        }
+        if self.unread > RX_BUFFER_LEN {
+            self.overrun = true;
+            self.unread = RX_BUFFER_LEN;
+            self.read_pos = write_pos;
+        }
+    }
+}
@@ -0,0 +1,11 @@
+//! The HC-05 Bluetooth module's AT commands over USART1, see the `hc05` crate.
+
+pub use ::hc05::*;
+
+use crate::monotimer::{self, MonoTimer};
+
+impl Millis for MonoTimer {
+    fn millis(&mut self) -> u32 {
+        (monotimer::cycles() / u64::from(monotimer::hclk() / 1_000)) as u32
+    }
+}
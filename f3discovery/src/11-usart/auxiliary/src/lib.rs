@@ -5,35 +5,94 @@
 #[allow(unused_extern_crates)] // NOTE(allow) bug rust-lang/rust53964
 extern crate panic_itm; // panic handler
 
+pub use clock_profile::{self, Profile};
 pub use cortex_m::{asm::bkpt, iprint, iprintln, peripheral::ITM};
 pub use cortex_m_rt::entry;
+pub use embedded_time::{self, duration::*};
+pub use monotimer::Elapsed;
+pub use serial_config::{self, FlowControl, Parity, SerialConfig, StopBits};
 pub use stm32f3_discovery::stm32f3xx_hal::pac::usart1;
 
+pub mod bench;
+pub mod dma;
+pub mod hc05;
 pub mod monotimer;
+pub mod serial;
 
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use clock_profile::hal_0_7::freeze;
+use monotimer::MonoTimer;
+use serial::SerialPort;
 use stm32f3_discovery::stm32f3xx_hal::{
+    pac::{self, USART1},
     prelude::*,
     serial::Serial,
-    pac::{self, USART1},
 };
-use monotimer::MonoTimer;
 
+/// Frequency of the clock that drives USART1, recorded by `init_with_profile` for
+/// `reconfigure`.
+static PCLK2: AtomicU32 = AtomicU32::new(0);
+
+// Bits of the USART configuration registers
+const CR1_UE: u32 = 1 << 0;
+const CR1_PS: u32 = 1 << 9;
+const CR1_PCE: u32 = 1 << 10;
+const CR1_M0: u32 = 1 << 12;
+const CR2_STOP: u32 = 0b11 << 12;
+const CR2_STOP_2: u32 = 0b10 << 12;
+const CR3_RTSE: u32 = 1 << 8;
+const CR3_CTSE: u32 = 1 << 9;
+
+/// Initializes USART1 at 115200 bps, no parity, one stop bit and no flow control.
 pub fn init() -> (&'static mut usart1::RegisterBlock, MonoTimer, ITM) {
+    // 115200 bps can be reached from the default profile's clock
+    init_with(SerialConfig::default()).unwrap()
+}
+
+/// Initializes USART1 with the given line settings.
+///
+/// With `FlowControl::RtsCts`, CTS is on PA11 and RTS on PA12.
+///
+/// Returns an error if the baud rate can't be reached from the USART1 clock.
+pub fn init_with(
+    config: SerialConfig,
+) -> Result<(&'static mut usart1::RegisterBlock, MonoTimer, ITM), clock_profile::Error> {
+    init_with_profile(Profile::default(), config)
+}
+
+/// Like `init_with`, with the clocks set up as `profile` says. The baud rate and the
+/// `MonoTimer` adapt to them.
+///
+/// Returns an error, before touching any peripheral, if the baud rate can't be reached from the
+/// USART1 clock of the profile.
+pub fn init_with_profile(
+    profile: Profile,
+    config: SerialConfig,
+) -> Result<(&'static mut usart1::RegisterBlock, MonoTimer, ITM), clock_profile::Error> {
+    clock_profile::usart_brr(profile.frequencies().pclk2, config.baud_rate)?;
+
     let cp = cortex_m::Peripherals::take().unwrap();
     let dp = pac::Peripherals::take().unwrap();
 
     let mut flash = dp.FLASH.constrain();
     let mut rcc = dp.RCC.constrain();
 
-    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+    let clocks = freeze(rcc.cfgr, &mut flash.acr, profile);
+
+    let mut gpioa = dp.GPIOA.split(&mut rcc.ahb);
 
     let (tx, rx) = match () {
         #[cfg(feature = "adapter")]
         () => {
-            let mut gpioa = dp.GPIOA.split(&mut rcc.ahb);
-
-            let tx = gpioa.pa9.into_af7_push_pull(&mut gpioa.moder, &mut gpioa.otyper, &mut gpioa.afrh);
-            let rx = gpioa.pa10.into_af7_push_pull(&mut gpioa.moder, &mut gpioa.otyper, &mut gpioa.afrh);
+            let tx =
+                gpioa
+                    .pa9
+                    .into_af7_push_pull(&mut gpioa.moder, &mut gpioa.otyper, &mut gpioa.afrh);
+            let rx =
+                gpioa
+                    .pa10
+                    .into_af7_push_pull(&mut gpioa.moder, &mut gpioa.otyper, &mut gpioa.afrh);
 
             (tx, rx)
         }
@@ -41,23 +100,114 @@ pub fn init() -> (&'static mut usart1::RegisterBlock, MonoTimer, ITM) {
         () => {
             let mut gpioc = dp.GPIOC.split(&mut rcc.ahb);
 
-            let tx = gpioc.pc4.into_af7_push_pull(&mut gpioc.moder, &mut gpioc.otyper, &mut gpioc.afrl);
-            let rx = gpioc.pc5.into_af7_push_pull(&mut gpioc.moder, &mut gpioc.otyper, &mut gpioc.afrl);
+            let tx =
+                gpioc
+                    .pc4
+                    .into_af7_push_pull(&mut gpioc.moder, &mut gpioc.otyper, &mut gpioc.afrl);
+            let rx =
+                gpioc
+                    .pc5
+                    .into_af7_push_pull(&mut gpioc.moder, &mut gpioc.otyper, &mut gpioc.afrl);
 
             (tx, rx)
         }
     };
 
-    Serial::new(dp.USART1, (tx, rx), 115_200.Bd(), clocks, &mut rcc.apb2);
-    // If you are having trouble sending/receiving data to/from the
-    // HC-05 bluetooth module, try this configuration instead:
-    // Serial::usart1(dp.USART1, (tx, rx), 9600.bps(), clocks, &mut rcc.apb2);
-
-    unsafe {
-        (
-            &mut *(USART1::ptr() as *mut _),
-            MonoTimer::new(cp.DWT, clocks),
-            cp.ITM,
-        )
+    if config.flow_control == FlowControl::RtsCts {
+        // CTS and RTS only exist on these pins
+        gpioa
+            .pa11
+            .into_af7_push_pull(&mut gpioa.moder, &mut gpioa.otyper, &mut gpioa.afrh);
+        gpioa
+            .pa12
+            .into_af7_push_pull(&mut gpioa.moder, &mut gpioa.otyper, &mut gpioa.afrh);
     }
+
+    // The HAL only knows about the baud rate, `reconfigure` takes care of the rest. If you
+    // are having trouble sending/receiving data to/from the HC-05 bluetooth module, try
+    // `init_with(SerialConfig::baud(9_600))`.
+    Serial::new(
+        dp.USART1,
+        (tx, rx),
+        config.baud_rate.Bd(),
+        clocks,
+        &mut rcc.apb2,
+    );
+    PCLK2.store(clocks.pclk2().0, Ordering::Relaxed);
+
+    let usart1: &'static mut usart1::RegisterBlock = unsafe { &mut *(USART1::ptr() as *mut _) };
+    reconfigure(usart1, &config)?;
+
+    Ok((usart1, MonoTimer::new(cp.DWT, cp.SYST, clocks), cp.ITM))
+}
+
+/// Like `init`, but hands USART1 out as a safe `SerialPort`.
+pub fn init_serial_port() -> (SerialPort, MonoTimer, ITM) {
+    let (usart1, mono_timer, itm) = init();
+    (SerialPort::new(usart1), mono_timer, itm)
+}
+
+/// Like `init_with`, but hands USART1 out as a safe `SerialPort`.
+pub fn init_serial_port_with(
+    config: SerialConfig,
+) -> Result<(SerialPort, MonoTimer, ITM), clock_profile::Error> {
+    let (usart1, mono_timer, itm) = init_with(config)?;
+    Ok((SerialPort::new(usart1), mono_timer, itm))
+}
+
+/// Changes the line settings of USART1 after `init` or `init_with`, e.g. to switch to the baud
+/// rate of a device on the other end.
+///
+/// Waits for the frame being sent to finish first. Note that flow control only works if it was
+/// enabled by `init_with`, which sets up the RTS and CTS pins.
+///
+/// Leaves the settings alone and returns an error if the baud rate can't be reached from the
+/// USART1 clock, like 921600 bps at 8 MHz.
+pub fn reconfigure(
+    usart1: &usart1::RegisterBlock,
+    config: &SerialConfig,
+) -> Result<(), clock_profile::Error> {
+    // 16x oversampling, rounded to the closest divider
+    let pclk2 = PCLK2.load(Ordering::Relaxed);
+    let brr = clock_profile::usart_brr(pclk2, config.baud_rate)?;
+
+    // The settings can only be changed while the USART is disabled
+    while usart1.isr.read().tc().bit_is_clear() {}
+    usart1
+        .cr1
+        .modify(|r, w| unsafe { w.bits(r.bits() & !CR1_UE) });
+
+    usart1.brr.write(|w| unsafe { w.bits(u32::from(brr)) });
+
+    // A parity bit makes the frame 9 bits long, so that there are still 8 data bits
+    let parity = match config.parity {
+        Parity::None => 0,
+        Parity::Even => CR1_M0 | CR1_PCE,
+        Parity::Odd => CR1_M0 | CR1_PCE | CR1_PS,
+    };
+    usart1
+        .cr1
+        .modify(|r, w| unsafe { w.bits(r.bits() & !(CR1_M0 | CR1_PCE | CR1_PS) | parity) });
+
+    let stop_bits = match config.stop_bits {
+        StopBits::One => 0,
+        StopBits::Two => CR2_STOP_2,
+    };
+    usart1
+        .cr2
+        .modify(|r, w| unsafe { w.bits(r.bits() & !CR2_STOP | stop_bits) });
+
+    let flow_control = match config.flow_control {
+        FlowControl::None => 0,
+        FlowControl::RtsCts => CR3_RTSE | CR3_CTSE,
+    };
+    usart1
+        .cr3
+        .modify(|r, w| unsafe { w.bits(r.bits() & !(CR3_RTSE | CR3_CTSE) | flow_control) });
+
+    usart1
+        .cr1
+        .modify(|r, w| unsafe { w.bits(r.bits() | CR1_UE) });
+
+    Ok(())
 }
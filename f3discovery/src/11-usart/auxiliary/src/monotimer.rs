@@ -1,54 +1,146 @@
 use stm32f3_discovery::stm32f3xx_hal as hal;
 
-use cortex_m::peripheral::DWT;
-use hal::{
-    rcc::Clocks,
-    time::rate::Hertz,
+use core::convert::TryFrom;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use cortex_m::{
+    interrupt,
+    peripheral::{syst::SystClkSource, DWT, SYST},
 };
+use cortex_m_rt::exception;
+use embedded_time::{clock, duration::Microseconds, fraction::Fraction, Clock};
+use hal::{rcc::Clocks, time::rate::Hertz};
+
+/// Rate at which `Instant`s count, whatever the core clock runs at. Cycles are converted with
+/// the ratio of the two at run time; when the core clock doesn't divide it, a tick is simply
+/// not a whole number of cycles.
+pub const TICK_FREQUENCY: u32 = 72_000_000;
+
+/// A point in time, in ticks of `TICK_FREQUENCY`. Adding or subtracting a duration, like
+/// `instant + Milliseconds(10_u32)`, and comparing instants work as expected.
+pub type Instant = embedded_time::Instant<MonoTimer>;
+
+/// Frequency of the core clock, which `CYCCNT` counts, recorded by `MonoTimer::new`.
+static HCLK: AtomicU32 = AtomicU32::new(0);
+
+/// The upper half of the 64-bit cycle count.
+static HIGH: AtomicU32 = AtomicU32::new(0);
+/// `CYCCNT` as last seen by `cycles`, to tell when it has wrapped around.
+static LAST: AtomicU32 = AtomicU32::new(0);
 
 /// A monotonic nondecreasing timer. This is a resurrection of MonoTimer from
 /// the stm32f3xx-hal where it got removed after 0.6.1.
+///
+/// `CYCCNT` is only 32 bits wide and wraps around after 9 minutes at 8 MHz (a minute at
+/// 72 MHz). The timer extends it to 64 bits, which needs it to be read at least once in that
+/// time, so the SysTick interrupt reads it once a second.
 #[derive(Clone, Copy)]
 pub struct MonoTimer {
-    frequency: Hertz,
+    _private: (),
 }
 
-// TODO: What about a refactoring to implement Clock from embedded-time?
 impl MonoTimer {
     /// Creates a new `Monotonic` timer
-    pub fn new(mut dwt: DWT, clocks: Clocks) -> Self {
+    pub fn new(mut dwt: DWT, mut syst: SYST, clocks: Clocks) -> Self {
+        // `SCALING_FACTOR` below has to be known at compile time, so cycles are converted to
+        // ticks of a fixed frequency instead
+        let hclk = clocks.hclk().0;
+        HCLK.store(hclk, Ordering::Relaxed);
+
         dwt.enable_cycle_counter();
 
         // now the CYCCNT counter can't be stopped or resetted
         drop(dwt);
 
-        MonoTimer {
-            frequency: clocks.hclk(),
-        }
+        // The external SysTick clock is HCLK / 8, so a second always fits the 24-bit reload
+        // value
+        syst.set_clock_source(SystClkSource::External);
+        syst.set_reload(hclk / 8 - 1);
+        syst.clear_current();
+        syst.enable_interrupt();
+        syst.enable_counter();
+
+        MonoTimer { _private: () }
     }
 
-    /// Returns the frequency at which the monotonic timer is operating at
+    /// Returns the frequency of the core clock, whose cycles the monotonic timer counts
     pub fn frequency(self) -> Hertz {
-        self.frequency
+        Hertz(hclk())
     }
 
     /// Returns an `Instant` corresponding to "now"
     pub fn now(self) -> Instant {
-        Instant {
-            now: DWT::get_cycle_count(),
+        Instant::new(ticks())
+    }
+}
+
+impl Clock for MonoTimer {
+    type T = u64;
+
+    const SCALING_FACTOR: Fraction = Fraction::new(1, TICK_FREQUENCY);
+
+    fn try_now(&self) -> Result<Instant, clock::Error> {
+        Ok(self.now())
+    }
+}
+
+/// Frequency of the core clock, 0 until `MonoTimer::new` is called.
+pub(crate) fn hclk() -> u32 {
+    HCLK.load(Ordering::Relaxed)
+}
+
+/// `cycles` in ticks of `TICK_FREQUENCY`, always 0 until `MonoTimer::new` is called.
+fn ticks() -> u64 {
+    match u64::from(hclk()) {
+        // `Instant::new` makes instants without a timer, they don't get to see time pass
+        0 => 0,
+        hclk => {
+            // Whole seconds and the rest separately, so that the multiplication can't
+            // overflow
+            let cycles = cycles();
+            let frequency = u64::from(TICK_FREQUENCY);
+            cycles / hclk * frequency + cycles % hclk * frequency / hclk
         }
     }
 }
 
-/// A measurement of a monotonically nondecreasing clock
-#[derive(Clone, Copy)]
-pub struct Instant {
-    now: u32,
+/// `CYCCNT` extended to 64 bits.
+pub(crate) fn cycles() -> u64 {
+    // An interrupt reading the counter in between could count the same wrap around twice
+    interrupt::free(|_| {
+        let low = DWT::get_cycle_count();
+        let mut high = HIGH.load(Ordering::Relaxed);
+        if low < LAST.load(Ordering::Relaxed) {
+            high += 1;
+            HIGH.store(high, Ordering::Relaxed);
+        }
+        LAST.store(low, Ordering::Relaxed);
+
+        u64::from(high) << 32 | u64::from(low)
+    })
+}
+
+// Kept in this module, next to `MonoTimer::new`: a handler in a library is only linked in
+// together with something else from its object file
+#[exception]
+fn SysTick() {
+    // Notices when `CYCCNT` wrapped around, even if nothing else reads the timer
+    cycles();
+}
+
+/// What `embedded-time`'s `Instant` lacks compared to `std::time::Instant`.
+pub trait Elapsed {
+    /// Time elapsed since the `Instant` was created, 0 if it is still in the future, like
+    /// `now + Milliseconds(10_u32)` is for another 10 ms
+    fn elapsed(&self) -> Microseconds<u64>;
 }
 
-impl Instant {
-    /// Ticks elapsed since the `Instant` was created
-    pub fn elapsed(self) -> u32 {
-        DWT::get_cycle_count().wrapping_sub(self.now)
+impl Elapsed for Instant {
+    fn elapsed(&self) -> Microseconds<u64> {
+        let now = Instant::new(ticks());
+        now.checked_duration_since(self)
+            // 64-bit microseconds don't overflow in the lifetime of the board
+            .and_then(|elapsed| Microseconds::try_from(elapsed).ok())
+            .unwrap_or(Microseconds(0))
     }
 }
@@ -0,0 +1,128 @@
+//! A safe handle to USART1.
+//!
+//! The chapter's programs work with the `usart1` register block directly, which is the point
+//! of the exercises. Once you have done that, `SerialPort` packages the same register
+//! accesses up: it implements `core::fmt::Write` for `write!`, blocking reads and writes, and
+//! the serial traits of `embedded-hal` so that drivers written against them work on top of it.
+//! Reception errors are reported instead of being silently ignored.
+
+use core::fmt;
+
+use embedded_hal::blocking::serial as bserial;
+use embedded_hal::serial;
+
+use crate::{reconfigure, usart1, SerialConfig};
+
+// Flags in `ISR`, and the bits that clear them in `ICR`
+const PE: u32 = 1 << 0;
+const FE: u32 = 1 << 1;
+const NF: u32 = 1 << 2;
+const ORE: u32 = 1 << 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// A byte arrived before the previous one was read, and was lost.
+    Overrun,
+    /// A byte didn't end with a valid stop bit, e.g. because the baud rates don't match.
+    Framing,
+    /// The line was noisy while a byte was being received.
+    Noise,
+    /// A byte failed the parity check.
+    Parity,
+}
+
+/// USART1, as set up by `aux11::init_serial_port`.
+pub struct SerialPort {
+    usart1: &'static mut usart1::RegisterBlock,
+}
+
+impl SerialPort {
+    pub(crate) fn new(usart1: &'static mut usart1::RegisterBlock) -> SerialPort {
+        SerialPort { usart1 }
+    }
+
+    /// Sends a byte, waiting for room in the transmit register first.
+    pub fn write_byte(&mut self, byte: u8) {
+        while self.usart1.isr.read().txe().bit_is_clear() {}
+        self.usart1.tdr.write(|w| w.tdr().bits(u16::from(byte)));
+    }
+
+    pub fn write_all(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.write_byte(*byte);
+        }
+    }
+
+    /// Waits for a byte to arrive.
+    pub fn read_byte(&mut self) -> Result<u8, Error> {
+        nb::block!(serial::Read::read(self))
+    }
+
+    /// Changes the line settings, see `aux11::reconfigure`.
+    pub fn reconfigure(&mut self, config: &SerialConfig) -> Result<(), clock_profile::Error> {
+        reconfigure(self.usart1, config)
+    }
+
+    /// Gives up the safe interface and returns the registers.
+    pub fn free(self) -> &'static mut usart1::RegisterBlock {
+        self.usart1
+    }
+}
+
+impl serial::Read<u8> for SerialPort {
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u8, Error> {
+        let isr = self.usart1.isr.read().bits();
+
+        let error = if isr & ORE != 0 {
+            Some((ORE, Error::Overrun))
+        } else if isr & FE != 0 {
+            Some((FE, Error::Framing))
+        } else if isr & NF != 0 {
+            Some((NF, Error::Noise))
+        } else if isr & PE != 0 {
+            Some((PE, Error::Parity))
+        } else {
+            None
+        };
+        if let Some((flag, error)) = error {
+            self.usart1.icr.write(|w| unsafe { w.bits(flag) });
+            return Err(nb::Error::Other(error));
+        }
+
+        if self.usart1.isr.read().rxne().bit_is_set() {
+            Ok(self.usart1.rdr.read().rdr().bits() as u8)
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+impl serial::Write<u8> for SerialPort {
+    type Error = Error;
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), Error> {
+        if self.usart1.isr.read().txe().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+        self.usart1.tdr.write(|w| w.tdr().bits(u16::from(byte)));
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Error> {
+        if self.usart1.isr.read().tc().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+        Ok(())
+    }
+}
+
+impl bserial::write::Default<u8> for SerialPort {}
+
+impl fmt::Write for SerialPort {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_all(s.as_bytes());
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+#![no_main]
+#![no_std]
+
+use core::str;
+
+use aux11::{entry, iprintln, serial_config, usart1, Profile, SerialConfig};
+use heapless::Vec;
+
+/// Echoes lines back, except for `baud <rate>` which switches to another baud rate.
+#[entry]
+fn main() -> ! {
+    let (usart1, _mono_timer, mut itm) = aux11::init();
+    let mut config = SerialConfig::default();
+    // `aux11::init` runs USART1 off the default profile's clock
+    let clocks = Profile::default().frequencies();
+
+    // A buffer with 32 bytes of capacity
+    let mut buffer: Vec<u8, 32> = Vec::new();
+
+    iprintln!(&mut itm.stim[0], "Listening at {}", config);
+    loop {
+        buffer.clear();
+
+        loop {
+            while usart1.isr.read().rxne().bit_is_clear() {}
+            let byte = usart1.rdr.read().rdr().bits() as u8;
+
+            // Carriage return
+            if byte == 13 {
+                break;
+            }
+            if buffer.push(byte).is_err() {
+                send(usart1, b"error: buffer full\r\n");
+                buffer.clear();
+            }
+        }
+
+        let line = str::from_utf8(&buffer).unwrap_or("");
+        match line.strip_prefix("baud ") {
+            Some(rate) => match serial_config::parse_baud_rate(rate) {
+                Ok(baud_rate) if clocks.usart1_brr(baud_rate).is_ok() => {
+                    send(usart1, b"switching, reconnect at the new baud rate\r\n");
+                    config.baud_rate = baud_rate;
+                    // Waits for the message above to go out before switching
+                    aux11::reconfigure(usart1, &config).unwrap();
+                    iprintln!(&mut itm.stim[0], "Listening at {}", config);
+                }
+                _ => send(usart1, b"error: unsupported baud rate\r\n"),
+            },
+            None => {
+                send(usart1, &buffer);
+                send(usart1, b"\r\n");
+            }
+        }
+    }
+}
+
+fn send(usart1: &usart1::RegisterBlock, bytes: &[u8]) {
+    for byte in bytes {
+        while usart1.isr.read().txe().bit_is_clear() {}
+        usart1.tdr.write(|w| w.tdr().bits(u16::from(*byte)));
+    }
+}
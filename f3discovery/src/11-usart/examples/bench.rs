@@ -0,0 +1,48 @@
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+
+use aux11::bench::{Harness, ItmWriter};
+#[allow(unused_imports)]
+use aux11::{entry, iprint, iprintln};
+use heapless::String;
+
+static PANGRAM: &[u8] = b"The quick brown fox jumps over the lazy dog.";
+
+#[entry]
+fn main() -> ! {
+    let (usart1, mono_timer, mut itm) = aux11::init();
+    let usart1 = &*usart1;
+
+    // Writes TDR without waiting, most bytes get lost
+    let mut tdr_loop = || {
+        for byte in PANGRAM {
+            usart1.tdr.write(|w| w.tdr().bits(u16::from(*byte)));
+        }
+    };
+
+    // Waits for TXE before every byte, all of them make it
+    let mut txe_loop = || {
+        for byte in PANGRAM {
+            while usart1.isr.read().txe().bit_is_clear() {}
+            usart1.tdr.write(|w| w.tdr().bits(u16::from(*byte)));
+        }
+        while usart1.isr.read().tc().bit_is_clear() {}
+    };
+
+    // What formatting a number costs, without sending anything
+    let mut format = || {
+        let mut s: String<32> = String::new();
+        write!(s, "{} {}", 42, -1234).ok();
+    };
+
+    // Every benchmark runs 10 times with interrupts disabled
+    let mut harness: Harness<_, 4> = Harness::new(mono_timer, 10);
+    harness.add("tdr-loop", &mut tdr_loop).unwrap();
+    harness.add("txe-loop", &mut txe_loop).unwrap();
+    harness.add("format", &mut format).unwrap();
+    harness.run(&mut ItmWriter(&mut itm.stim[0])).unwrap();
+
+    loop {}
+}
@@ -0,0 +1,54 @@
+#![no_main]
+#![no_std]
+
+#[allow(unused_imports)]
+use aux11::{
+    dma::{DmaSerial, Overrun},
+    entry, iprint, iprintln, Elapsed,
+};
+
+static PANGRAM: &[u8] = b"The quick brown fox jumps over the lazy dog.\r\n";
+
+#[entry]
+fn main() -> ! {
+    let (mut serial, mono_timer, mut itm) = aux11::init_serial_port();
+
+    // The processor waits on `TXE` before every byte and is busy until the last one is written
+    let instant = mono_timer.now();
+    serial.write_all(PANGRAM);
+    let elapsed = instant.elapsed();
+    iprintln!(&mut itm.stim[0], "TXE polling: busy for {} us", elapsed.0);
+
+    let mut serial = DmaSerial::new(serial);
+
+    // The DMA feeds `TDR` while the processor is free to do something else
+    let instant = mono_timer.now();
+    serial.start_write(PANGRAM);
+    let started = instant.elapsed();
+    let mut spins = 0;
+    while !serial.is_write_done() {
+        spins += 1;
+    }
+    let elapsed = instant.elapsed();
+    serial.wait_write();
+    iprintln!(
+        &mut itm.stim[0],
+        "DMA: busy for {} us, done after {} us, {} loop iterations meanwhile",
+        started.0,
+        elapsed.0,
+        spins
+    );
+
+    // Echo whatever arrives, one message at a time
+    let mut buffer = [0; aux11::dma::RX_BUFFER_LEN];
+    loop {
+        match serial.read_message(&mut buffer) {
+            Some(Ok(count)) => {
+                iprintln!(&mut itm.stim[0], "received a {} byte message", count);
+                serial.write_all(&buffer[..count]);
+            }
+            Some(Err(Overrun)) => iprintln!(&mut itm.stim[0], "message too long, dropped"),
+            None => {}
+        }
+    }
+}
@@ -3,7 +3,7 @@
 #![no_std]
 
 #[allow(unused_imports)]
-use aux11::{entry, iprint, iprintln};
+use aux11::{entry, iprint, iprintln, Elapsed};
 
 #[entry]
 fn main() -> ! {
@@ -14,14 +14,9 @@ fn main() -> ! {
     for byte in b"The quick brown fox jumps over the lazy dog.".iter() {
         usart1.tdr.write(|w| w.tdr().bits(u16::from(*byte)));
     }
-    let elapsed = instant.elapsed(); // in ticks
+    let elapsed = instant.elapsed(); // in microseconds
 
-    iprintln!(
-        &mut itm.stim[0],
-        "`for` loop took {} ticks ({} us)",
-        elapsed,
-        elapsed as f32 / mono_timer.frequency().0 as f32 * 1e6
-    );
+    iprintln!(&mut itm.stim[0], "`for` loop took {} us", elapsed.0);
 
     loop {}
 }
@@ -2,7 +2,7 @@
 #![no_std]
 
 #[allow(unused_imports)]
-use aux11::{entry, iprint, iprintln};
+use aux11::{entry, iprint, iprintln, Elapsed};
 
 #[entry]
 fn main() -> ! {
@@ -18,14 +18,9 @@ fn main() -> ! {
             .tdr
             .write(|w| w.tdr().bits(u16::from(*byte)));
     }
-    let elapsed = instant.elapsed(); // in ticks
+    let elapsed = instant.elapsed(); // in microseconds
 
-    iprintln!(
-        &mut itm.stim[0],
-        "`for` loop took {} ticks ({} us)",
-        elapsed,
-        elapsed as f32 / mono_timer.frequency().0 as f32 * 1e6
-    );
+    iprintln!(&mut itm.stim[0], "`for` loop took {} us", elapsed.0);
 
     loop {}
 }
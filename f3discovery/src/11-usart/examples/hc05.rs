@@ -0,0 +1,57 @@
+//! Configures an HC-05 Bluetooth module connected to PA9/PA10, as in the Serial over Bluetooth
+//! chapter, that was powered up in AT mode. Build with `--features adapter`.
+
+#![no_main]
+#![no_std]
+
+use aux11::hc05::{Hc05, Role};
+#[allow(unused_imports)]
+use aux11::{entry, iprint, iprintln, SerialConfig};
+
+#[entry]
+fn main() -> ! {
+    // Holding the button while powering the module up puts it in AT mode at 38400 bps
+    let (serial, mono_timer, mut itm) =
+        aux11::init_serial_port_with(SerialConfig::baud(38_400)).unwrap();
+    let mut hc05 = Hc05::new(serial, mono_timer);
+
+    match hc05.check() {
+        Ok(()) => iprintln!(&mut itm.stim[0], "Module found"),
+        Err(e) => {
+            iprintln!(
+                &mut itm.stim[0],
+                "No answer ({:?}), is the module in AT mode?",
+                e
+            );
+            loop {}
+        }
+    }
+
+    if let Ok(version) = hc05.version() {
+        iprintln!(&mut itm.stim[0], "Firmware version {}", version);
+    }
+
+    hc05.set_name("ferris").unwrap();
+    hc05.set_pin("1234").unwrap();
+    hc05.set_role(Role::Slave).unwrap();
+    // What `aux11::init` uses, so that the programs from the USART chapter work unchanged
+    hc05.set_serial_config(&SerialConfig::default()).unwrap();
+
+    let state = hc05.state().unwrap();
+    let config = hc05.serial_config().unwrap();
+    iprintln!(
+        &mut itm.stim[0],
+        "State {:?}, {} once restarted",
+        state,
+        config
+    );
+
+    // The module only picks the new settings up once it is out of AT mode
+    hc05.reset().unwrap();
+    iprintln!(
+        &mut itm.stim[0],
+        "Done, power the module up again without holding the button"
+    );
+
+    loop {}
+}
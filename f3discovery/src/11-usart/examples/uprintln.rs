@@ -0,0 +1,39 @@
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+
+#[allow(unused_imports)]
+use aux11::{entry, iprint, iprintln, serial::Error};
+
+macro_rules! uprint {
+    ($serial:expr, $($arg:tt)*) => {
+        $serial.write_fmt(format_args!($($arg)*)).ok()
+    };
+}
+
+macro_rules! uprintln {
+    ($serial:expr, $fmt:expr) => {
+        uprint!($serial, concat!($fmt, "\n"))
+    };
+    ($serial:expr, $fmt:expr, $($arg:tt)*) => {
+        uprint!($serial, concat!($fmt, "\n"), $($arg)*)
+    };
+}
+
+#[entry]
+fn main() -> ! {
+    let (mut serial, _mono_timer, mut itm) = aux11::init_serial_port();
+
+    uprintln!(serial, "The answer is {}", 40 + 2);
+
+    // Echo everything back, reporting what went wrong on the way
+    loop {
+        match serial.read_byte() {
+            Ok(byte) => serial.write_byte(byte),
+            Err(Error::Overrun) => iprintln!(&mut itm.stim[0], "overrun, a byte was lost"),
+            Err(e) => iprintln!(&mut itm.stim[0], "error: {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! A blocking I2C master written against the I2C1 registers.
+//!
+//! This is the program of this chapter turned into a driver: every transfer starts with
+//! programming `CR2` and then moves bytes through `TXDR` and `RXDR` as the flags in `ISR`
+//! allow. Unlike the chapter's busy loops, every wait also watches the error flags and gives
+//! up after a bounded number of polls, so a missing device or a stuck bus results in an
+//! [`Error`] rather than a program that hangs.
+
+use stm32f3_discovery::stm32f3xx_hal::{
+    hal::blocking::i2c::{Read, Write, WriteRead},
+    stm32::i2c1::{self, isr},
+};
+
+/// Default number of `ISR` polls before a wait times out. At the 8 MHz the processor runs
+/// at after `init`, that is a few tens of milliseconds.
+pub const DEFAULT_TIMEOUT: u32 = 100_000;
+
+/// Largest number of bytes `CR2.NBYTES` can hold. Longer transfers are split using `RELOAD`.
+const MAX_NBYTES: usize = 255;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The slave didn't acknowledge its address or a byte we sent.
+    Nack,
+    /// Another master took over the bus.
+    ArbitrationLoss,
+    /// A START or STOP condition showed up where it wasn't allowed.
+    Bus,
+    /// A status flag we were waiting for never got set.
+    Timeout,
+}
+
+/// What a transfer does after its last byte.
+#[derive(Clone, Copy, PartialEq)]
+enum End {
+    /// Broadcast STOP.
+    Stop,
+    /// Keep the bus, a RESTART follows.
+    Restart,
+}
+
+pub struct I2cMaster {
+    i2c1: &'static i2c1::RegisterBlock,
+    timeout: u32,
+}
+
+impl I2cMaster {
+    /// Takes over the peripheral that `aux14::init` has already configured.
+    pub fn new(i2c1: &'static i2c1::RegisterBlock) -> I2cMaster {
+        I2cMaster {
+            i2c1,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Changes the number of `ISR` polls before a wait times out.
+    pub fn set_timeout(&mut self, polls: u32) {
+        self.timeout = polls;
+    }
+
+    /// Broadcasts START (or RESTART) followed by the slave `address` and the R/W bit.
+    fn start(&self, address: u8, read: bool, len: usize, end: End) {
+        let nbytes = len.min(MAX_NBYTES) as u8;
+        self.i2c1.cr2.write(|w| {
+            w.start().set_bit();
+            w.sadd().bits(u16::from(address) << 1);
+            w.rd_wrn().bit(read);
+            w.nbytes().bits(nbytes);
+            w.reload().bit(len > MAX_NBYTES);
+            w.autoend().bit(end == End::Stop)
+        });
+    }
+
+    /// Sets up the next chunk of a transfer longer than `MAX_NBYTES`.
+    fn reload(&self, remaining: usize) -> Result<(), Error> {
+        self.wait(|isr| isr.tcr().bit_is_set())?;
+        let nbytes = remaining.min(MAX_NBYTES) as u8;
+        self.i2c1.cr2.modify(|_, w| {
+            w.nbytes().bits(nbytes);
+            w.reload().bit(remaining > MAX_NBYTES)
+        });
+        Ok(())
+    }
+
+    fn send(&self, address: u8, bytes: &[u8], end: End) -> Result<(), Error> {
+        self.start(address, false, bytes.len(), end);
+        for (i, chunk) in bytes.chunks(MAX_NBYTES).enumerate() {
+            if i > 0 {
+                self.reload(bytes.len() - i * MAX_NBYTES)?;
+            }
+            for byte in chunk {
+                // Wait until we can send more data
+                self.wait(|isr| isr.txis().bit_is_set())?;
+                self.i2c1.txdr.write(|w| w.txdata().bits(*byte));
+            }
+        }
+        self.finish(end)
+    }
+
+    fn receive(&self, address: u8, buffer: &mut [u8]) -> Result<(), Error> {
+        let len = buffer.len();
+        self.start(address, true, len, End::Stop);
+        for (i, chunk) in buffer.chunks_mut(MAX_NBYTES).enumerate() {
+            if i > 0 {
+                self.reload(len - i * MAX_NBYTES)?;
+            }
+            for byte in chunk {
+                // Wait until we have received the next byte
+                self.wait(|isr| isr.rxne().bit_is_set())?;
+                *byte = self.i2c1.rxdr.read().rxdata().bits();
+            }
+        }
+        self.finish(End::Stop)
+    }
+
+    /// Waits for the end of the transfer: the automatic STOP, or the transfer complete flag
+    /// that allows a RESTART.
+    fn finish(&self, end: End) -> Result<(), Error> {
+        match end {
+            End::Stop => {
+                self.wait(|isr| isr.stopf().bit_is_set())?;
+                self.i2c1.icr.write(|w| w.stopcf().set_bit());
+            }
+            End::Restart => self.wait(|isr| isr.tc().bit_is_set())?,
+        }
+        Ok(())
+    }
+
+    /// Polls `ISR` until `ready` returns true, an error flag gets set or we run out of
+    /// patience.
+    fn wait(&self, ready: impl Fn(&isr::R) -> bool) -> Result<(), Error> {
+        for _ in 0..self.timeout {
+            let isr = self.i2c1.isr.read();
+
+            if isr.arlo().bit_is_set() {
+                // The peripheral has already let go of the bus
+                self.i2c1.icr.write(|w| w.arlocf().set_bit());
+                return Err(Error::ArbitrationLoss);
+            }
+            if isr.berr().bit_is_set() {
+                self.i2c1.icr.write(|w| w.berrcf().set_bit());
+                self.stop();
+                return Err(Error::Bus);
+            }
+            if isr.nackf().bit_is_set() {
+                // The hardware broadcasts STOP on its own after a NACK
+                self.i2c1.icr.write(|w| w.nackcf().set_bit());
+                self.clear_stop();
+                return Err(Error::Nack);
+            }
+
+            if ready(&isr) {
+                return Ok(());
+            }
+        }
+
+        self.stop();
+        Err(Error::Timeout)
+    }
+
+    /// Broadcasts STOP to release the bus after an error.
+    fn stop(&self) {
+        self.i2c1.cr2.modify(|_, w| w.stop().set_bit());
+        self.clear_stop();
+    }
+
+    fn clear_stop(&self) {
+        for _ in 0..self.timeout {
+            if self.i2c1.isr.read().stopf().bit_is_set() {
+                break;
+            }
+        }
+        self.i2c1.icr.write(|w| w.stopcf().set_bit());
+    }
+}
+
+impl Write for I2cMaster {
+    type Error = Error;
+
+    /// Writes `bytes` to the slave at the 7-bit `address`. An empty `bytes` only checks
+    /// whether the slave acknowledges its address.
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Error> {
+        self.send(address, bytes, End::Stop)
+    }
+}
+
+impl Read for I2cMaster {
+    type Error = Error;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Error> {
+        self.receive(address, buffer)
+    }
+}
+
+impl WriteRead for I2cMaster {
+    type Error = Error;
+
+    /// Writes `bytes`, usually a register address, then RESTARTs and reads into `buffer`
+    /// without releasing the bus in between.
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Error> {
+        self.send(address, bytes, End::Restart)?;
+        self.receive(address, buffer)
+    }
+}
@@ -5,34 +5,53 @@
 #[allow(unused_extern_crates)] //  bug rust-lang/rust#53964
 extern crate panic_itm; // panic handler
 
+pub use clock_profile::{self, Profile};
 pub use cortex_m::{asm::bkpt, iprint, iprintln};
 pub use cortex_m_rt::entry;
 pub use stm32f3_discovery::stm32f3xx_hal::{delay::Delay, prelude, stm32::i2c1};
 
+pub mod i2c;
+
+use clock_profile::hal_0_6::{freeze, set_i2c1_timing};
 use cortex_m::peripheral::ITM;
 use stm32f3_discovery::{
     lsm303dlhc::Lsm303dlhc,
     stm32f3xx_hal::{
+        gpio::{
+            gpiob::{PB6, PB7},
+            AF4,
+        },
+        hal::serial,
         i2c::I2c,
         prelude::*,
-        stm32::{self, I2C1},
+        serial::{Rx, Serial, Tx},
+        stm32::{self, I2C1, USART1},
     },
 };
 
+/// The I2C bus on PB6 (SCL) and PB7 (SDA), shared by the LSM303DLHC and the header pins.
+pub type I2c1 = I2c<I2C1, (PB6<AF4>, PB7<AF4>)>;
+
 pub fn init() -> (&'static i2c1::RegisterBlock, Delay, ITM) {
+    init_with_profile(Profile::default())
+}
+
+/// Like `init`, with the clocks set up as `profile` says. The I2C bus still runs at 400 kHz.
+pub fn init_with_profile(profile: Profile) -> (&'static i2c1::RegisterBlock, Delay, ITM) {
     let cp = cortex_m::Peripherals::take().unwrap();
     let dp = stm32::Peripherals::take().unwrap();
 
     let mut flash = dp.FLASH.constrain();
     let mut rcc = dp.RCC.constrain();
 
-    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+    let clocks = freeze(rcc.cfgr, &mut flash.acr, profile);
 
     let mut gpiob = dp.GPIOB.split(&mut rcc.ahb);
     let scl = gpiob.pb6.into_af4(&mut gpiob.moder, &mut gpiob.afrl);
     let sda = gpiob.pb7.into_af4(&mut gpiob.moder, &mut gpiob.afrl);
 
     let i2c = I2c::new(dp.I2C1, (scl, sda), 400.khz(), clocks, &mut rcc.apb1);
+    set_i2c1_timing(profile, 400_000);
 
     Lsm303dlhc::new(i2c).unwrap();
 
@@ -40,3 +59,62 @@ pub fn init() -> (&'static i2c1::RegisterBlock, Delay, ITM) {
 
     unsafe { (&mut *(I2C1::ptr() as *mut _), delay, cp.ITM) }
 }
+
+/// USART1 on PC4 (TX) and PC5 (RX), both directions in one value.
+pub struct SerialPort {
+    tx: Tx<USART1>,
+    rx: Rx<USART1>,
+}
+
+impl serial::Write<u8> for SerialPort {
+    type Error = <Tx<USART1> as serial::Write<u8>>::Error;
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), Self::Error> {
+        self.tx.write(byte)
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        self.tx.flush()
+    }
+}
+
+impl serial::Read<u8> for SerialPort {
+    type Error = <Rx<USART1> as serial::Read<u8>>::Error;
+
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        self.rx.read()
+    }
+}
+
+/// Hands out the I2C bus through the HAL driver, together with the serial port, for the
+/// I2C inspector example.
+pub fn init_inspector() -> (I2c1, SerialPort, ITM) {
+    init_inspector_with_profile(Profile::default())
+}
+
+/// Like `init_inspector`, with the clocks set up as `profile` says.
+pub fn init_inspector_with_profile(profile: Profile) -> (I2c1, SerialPort, ITM) {
+    let cp = cortex_m::Peripherals::take().unwrap();
+    let dp = stm32::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let mut rcc = dp.RCC.constrain();
+
+    let clocks = freeze(rcc.cfgr, &mut flash.acr, profile);
+
+    let mut gpiob = dp.GPIOB.split(&mut rcc.ahb);
+    let scl = gpiob.pb6.into_af4(&mut gpiob.moder, &mut gpiob.afrl);
+    let sda = gpiob.pb7.into_af4(&mut gpiob.moder, &mut gpiob.afrl);
+
+    let i2c = I2c::new(dp.I2C1, (scl, sda), 100.khz(), clocks, &mut rcc.apb1);
+    set_i2c1_timing(profile, 100_000);
+
+    let mut gpioc = dp.GPIOC.split(&mut rcc.ahb);
+    let tx = gpioc.pc4.into_af7(&mut gpioc.moder, &mut gpioc.afrl);
+    let rx = gpioc.pc5.into_af7(&mut gpioc.moder, &mut gpioc.afrl);
+
+    let serial = Serial::usart1(dp.USART1, (tx, rx), 115_200.bps(), clocks, &mut rcc.apb2);
+    let (tx, rx) = serial.split();
+
+    (i2c, SerialPort { tx, rx }, cp.ITM)
+}
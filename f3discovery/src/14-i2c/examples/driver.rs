@@ -0,0 +1,53 @@
+//! Reads the magnetometer through the `aux14::i2c` driver instead of raw register accesses.
+
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+#[allow(unused_imports)]
+use aux14::{entry, i2c::I2cMaster, iprint, iprintln, prelude::*};
+use lsm303_regs::dlhc::{MagRegister, MAGNETOMETER};
+
+// Nothing answers at this address
+const NOBODY: u8 = 0b111_0000;
+
+// Addresses of the magnetometer's registers
+const OUT_X_H_M: u8 = MagRegister::OUT_X_H_M.addr();
+const IRA_REG_M: u8 = MagRegister::IRA_REG_M.addr();
+
+#[entry]
+fn main() -> ! {
+    let (i2c1, mut delay, mut itm) = aux14::init();
+    let mut i2c = I2cMaster::new(i2c1);
+
+    let mut byte = [0];
+    i2c.write_read(MAGNETOMETER, &[IRA_REG_M], &mut byte)
+        .unwrap();
+    // Expected output: 0x0A - 0b01001000
+    iprintln!(&mut itm.stim[0], "0x{:02X} - 0b{:08b}", IRA_REG_M, byte[0]);
+
+    // Expected output: Err(Nack)
+    let result = i2c.write_read(NOBODY, &[IRA_REG_M], &mut byte);
+    iprintln!(&mut itm.stim[0], "{:?}", result);
+
+    loop {
+        let mut buffer = [0u8; 6];
+        i2c.write_read(MAGNETOMETER, &[OUT_X_H_M], &mut buffer)
+            .unwrap();
+
+        let x_h = u16::from(buffer[0]);
+        let x_l = u16::from(buffer[1]);
+        let z_h = u16::from(buffer[2]);
+        let z_l = u16::from(buffer[3]);
+        let y_h = u16::from(buffer[4]);
+        let y_l = u16::from(buffer[5]);
+
+        let x = ((x_h << 8) + x_l) as i16;
+        let y = ((y_h << 8) + y_l) as i16;
+        let z = ((z_h << 8) + z_l) as i16;
+
+        iprintln!(&mut itm.stim[0], "{:?}", (x, y, z));
+
+        delay.delay_ms(1_000_u16);
+    }
+}
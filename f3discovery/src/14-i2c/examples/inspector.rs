@@ -0,0 +1,17 @@
+//! Scans the I2C bus on PB6 (SCL) / PB7 (SDA) and inspects the registers of the devices on
+//! it, driven from a serial terminal. See the `i2c-inspector` crate for the commands.
+
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+#[allow(unused_imports)]
+use aux14::{entry, iprint, iprintln};
+
+#[entry]
+fn main() -> ! {
+    let (i2c, serial, mut itm) = aux14::init_inspector();
+
+    iprintln!(&mut itm.stim[0], "I2C inspector running on the serial port");
+    i2c_inspector::run(i2c, serial)
+}
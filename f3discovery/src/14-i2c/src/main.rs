@@ -4,13 +4,14 @@
 
 #[allow(unused_imports)]
 use aux14::{entry, iprint, iprintln, prelude::*};
+use lsm303_regs::dlhc::{self, MagRegister};
 
-// Slave address
-const MAGNETOMETER: u16 = 0b0011_1100;
+// Slave address, shifted into the bits `SADD` expects a 7-bit address in
+const MAGNETOMETER: u16 = (dlhc::MAGNETOMETER as u16) << 1;
 
 // Addresses of the magnetometer's registers
-const OUT_X_H_M: u8 = 0x03;
-const IRA_REG_M: u8 = 0x0A;
+const OUT_X_H_M: u8 = MagRegister::OUT_X_H_M.addr();
+const IRA_REG_M: u8 = MagRegister::IRA_REG_M.addr();
 
 #[entry]
 fn main() -> ! {
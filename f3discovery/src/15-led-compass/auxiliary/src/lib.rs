@@ -5,28 +5,35 @@
 #[allow(unused_extern_crates)] // NOTE(allow) bug rust-lang/rust#53964
 extern crate panic_itm; // panic handler
 
+pub use clock_profile::{self, Profile};
 pub use cortex_m::{asm::bkpt, iprint, iprintln, peripheral::ITM};
 pub use cortex_m_rt::entry;
 pub use stm32f3_discovery::{
     leds::Leds,
     lsm303dlhc::I16x3,
-    stm32f3xx_hal::{delay::Delay, prelude, stm32::i2c1},
+    stm32f3xx_hal::{delay::Delay, prelude, stm32::i2c1, time::MonoTimer},
     switch_hal,
 };
 
+use clock_profile::hal_0_6::{freeze, set_i2c1_timing};
 use stm32f3_discovery::{
     lsm303dlhc,
     stm32f3xx_hal::{
+        gpio::gpioa::{PA10, PA9},
         gpio::gpiob::{PB6, PB7},
-        gpio::AF4,
+        gpio::{AF4, AF7},
         i2c::I2c,
         prelude::*,
-        stm32::{self, I2C1},
+        serial::Serial,
+        stm32::{self, I2C1, USART1},
     },
 };
 
 pub type Lsm303dlhc = lsm303dlhc::Lsm303dlhc<I2c<I2C1, (PB6<AF4>, PB7<AF4>)>>;
 
+/// USART1 on the pins the Bluetooth module is wired to in the Serial over Bluetooth chapter.
+pub type Serial1 = Serial<USART1, (PA9<AF7>, PA10<AF7>)>;
+
 /// Cardinal directions. Each one matches one of the user LEDs.
 pub enum Direction {
     /// North / LD3
@@ -48,13 +55,31 @@ pub enum Direction {
 }
 
 pub fn init() -> (Leds, Lsm303dlhc, Delay, ITM) {
+    let (leds, lsm303dlhc, delay, _serial, _mono_timer, itm) = init_all(Profile::default());
+    (leds, lsm303dlhc, delay, itm)
+}
+
+/// Like `init` but also hands out USART1 at 115200 bps, TX on PA9 and RX on PA10, and a
+/// `MonoTimer` to keep time without blocking like `Delay` does.
+pub fn init_with_serial() -> (Leds, Lsm303dlhc, Serial1, MonoTimer, ITM) {
+    init_with_profile(Profile::default())
+}
+
+/// Like `init_with_serial`, with the clocks set up as `profile` says. The baud rate, the I2C bus
+/// speed and the `MonoTimer` adapt to them.
+pub fn init_with_profile(profile: Profile) -> (Leds, Lsm303dlhc, Serial1, MonoTimer, ITM) {
+    let (leds, lsm303dlhc, _delay, serial, mono_timer, itm) = init_all(profile);
+    (leds, lsm303dlhc, serial, mono_timer, itm)
+}
+
+fn init_all(profile: Profile) -> (Leds, Lsm303dlhc, Delay, Serial1, MonoTimer, ITM) {
     let cp = cortex_m::Peripherals::take().unwrap();
     let dp = stm32::Peripherals::take().unwrap();
 
     let mut flash = dp.FLASH.constrain();
     let mut rcc = dp.RCC.constrain();
 
-    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+    let clocks = freeze(rcc.cfgr, &mut flash.acr, profile);
 
     let mut gpioe = dp.GPIOE.split(&mut rcc.ahb);
     let leds = Leds::new(
@@ -75,10 +100,18 @@ pub fn init() -> (Leds, Lsm303dlhc, Delay, ITM) {
     let sda = gpiob.pb7.into_af4(&mut gpiob.moder, &mut gpiob.afrl);
 
     let i2c = I2c::new(dp.I2C1, (scl, sda), 400.khz(), clocks, &mut rcc.apb1);
+    set_i2c1_timing(profile, 400_000);
 
     let lsm303dlhc = Lsm303dlhc::new(i2c).unwrap();
 
+    let mut gpioa = dp.GPIOA.split(&mut rcc.ahb);
+    let tx = gpioa.pa9.into_af7(&mut gpioa.moder, &mut gpioa.afrh);
+    let rx = gpioa.pa10.into_af7(&mut gpioa.moder, &mut gpioa.afrh);
+
+    let serial = Serial::usart1(dp.USART1, (tx, rx), 115_200.bps(), clocks, &mut rcc.apb2);
+
     let delay = Delay::new(cp.SYST, clocks);
+    let mono_timer = MonoTimer::new(cp.DWT, clocks);
 
-    (leds, lsm303dlhc, delay, cp.ITM)
+    (leds, lsm303dlhc, delay, serial, mono_timer, cp.ITM)
 }
@@ -0,0 +1,137 @@
+//! Lets a computer control the compass LEDs and read the sensors over USART1, with the
+//! `led-protocol` requests. Wire the serial module, or the Bluetooth module, as in the Serial
+//! over Bluetooth chapter and use the `led-remote` tool, or type the requests into a terminal.
+
+#![deny(unsafe_code)]
+#![no_main]
+#![no_std]
+
+use core::fmt::{self, Write};
+
+#[allow(unused_imports)]
+use aux15::{entry, iprint, iprintln, prelude::*, switch_hal::OutputSwitch, I16x3, Serial1};
+use led_protocol::{LineBuffer, Request, Response, LEDS};
+
+/// Lights one LED after the other.
+struct Roulette {
+    period_ticks: u32,
+    /// Laps to go, `None` to go on until told to stop.
+    laps: Option<u8>,
+    /// The LED that is on.
+    led: usize,
+}
+
+/// Lets `write!` send to the serial port.
+struct Port<'a>(&'a mut Serial1);
+
+impl Write for Port<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            nb::block!(self.0.write(byte)).map_err(|_| fmt::Error)?;
+        }
+        Ok(())
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    let (leds, mut lsm303dlhc, mut serial, mono_timer, mut itm) = aux15::init_with_serial();
+    let mut leds = leds.into_array();
+    let ticks_per_ms = mono_timer.frequency().0 / 1_000;
+
+    let mut lines = LineBuffer::new();
+    let mut roulette: Option<Roulette> = None;
+    let mut last_step = mono_timer.now();
+
+    iprintln!(&mut itm.stim[0], "Waiting for requests");
+    loop {
+        let byte = match serial.read() {
+            Ok(byte) => Some(byte),
+            // Whatever was being received is lost, and the request will be reported as invalid
+            Err(nb::Error::Other(_)) | Err(nb::Error::WouldBlock) => None,
+        };
+
+        if let Some(line) = byte.and_then(|byte| lines.push(byte)) {
+            let response = match line.and_then(Request::parse) {
+                Ok(Request::Ping) => Response::Pong,
+                Ok(Request::Led { index, on }) => {
+                    roulette = None;
+                    let led = &mut leds[usize::from(index)];
+                    if on {
+                        led.on().ok();
+                    } else {
+                        led.off().ok();
+                    }
+                    Response::Ok
+                }
+                Ok(Request::Leds(mask)) => {
+                    roulette = None;
+                    for (i, led) in leds.iter_mut().enumerate() {
+                        if mask & (1 << i) != 0 {
+                            led.on().ok();
+                        } else {
+                            led.off().ok();
+                        }
+                    }
+                    Response::Ok
+                }
+                Ok(Request::Roulette { period_ms, laps }) => {
+                    for led in leds.iter_mut() {
+                        led.off().ok();
+                    }
+                    leds[0].on().ok();
+                    last_step = mono_timer.now();
+                    roulette = Some(Roulette {
+                        period_ticks: u32::from(period_ms) * ticks_per_ms,
+                        laps: if laps == 0 { None } else { Some(laps) },
+                        led: 0,
+                    });
+                    Response::Ok
+                }
+                Ok(Request::Stop) => {
+                    roulette = None;
+                    for led in leds.iter_mut() {
+                        led.off().ok();
+                    }
+                    Response::Ok
+                }
+                Ok(Request::Accel) => match lsm303dlhc.accel() {
+                    Ok(I16x3 { x, y, z }) => Response::Accel([x, y, z]),
+                    Err(_) => Response::Error(led_protocol::Error::Sensor),
+                },
+                Ok(Request::Mag) => match lsm303dlhc.mag() {
+                    Ok(I16x3 { x, y, z }) => Response::Mag([x, y, z]),
+                    Err(_) => Response::Error(led_protocol::Error::Sensor),
+                },
+                Err(e) => Response::Error(e),
+            };
+
+            iprintln!(&mut itm.stim[0], "{}", response);
+            write!(Port(&mut serial), "{}\r\n", response).ok();
+        }
+
+        // Move the roulette on without blocking, so that requests keep being answered
+        let done = match roulette.as_mut() {
+            Some(r) if last_step.elapsed() >= r.period_ticks => {
+                last_step = mono_timer.now();
+                leds[r.led].off().ok();
+                r.led = (r.led + 1) % usize::from(LEDS);
+                if r.led == 0 {
+                    if let Some(laps) = r.laps.as_mut() {
+                        *laps -= 1;
+                    }
+                }
+                if r.laps == Some(0) {
+                    true
+                } else {
+                    leds[r.led].on().ok();
+                    false
+                }
+            }
+            _ => false,
+        };
+        if done {
+            roulette = None;
+        }
+    }
+}
@@ -5,48 +5,149 @@
 #[allow(unused_extern_crates)] // NOTE(allow) bug rust-lang/rust#53964
 extern crate panic_itm; // panic handler
 
-pub use cortex_m::{asm::bkpt, iprint, iprintln, peripheral::ITM};
+pub use clock_profile::{self, Profile};
+pub use cortex_m::{
+    asm::bkpt,
+    iprint, iprintln,
+    peripheral::{DWT, ITM},
+};
 pub use cortex_m_rt::entry;
 pub use stm32f3_discovery::{
+    leds::Leds,
     lsm303dlhc::{self, I16x3, Sensitivity},
     stm32f3xx_hal::{delay::Delay, prelude, time::MonoTimer},
+    switch_hal,
 };
 
+use core::cell::RefCell;
+use core::ops::{Deref, DerefMut};
+
+use clock_profile::hal_0_6::{freeze, set_i2c1_timing};
+use lsm303_regs::{dlhc::ACCELEROMETER, Register, StatusRegA};
 use stm32f3_discovery::stm32f3xx_hal::{
     gpio::gpiob::{PB6, PB7},
     gpio::AF4,
-    i2c::I2c,
+    hal::blocking::i2c::{Write, WriteRead},
+    i2c::{self, I2c},
     prelude::*,
     stm32::{self, I2C1},
 };
 
-pub type Lsm303dlhc = lsm303dlhc::Lsm303dlhc<I2c<I2C1, (PB6<AF4>, PB7<AF4>)>>;
+type I2c1 = I2c<I2C1, (PB6<AF4>, PB7<AF4>)>;
+
+/// The I2C bus, shared by the driver and [`Lsm303dlhc::accel_data_ready`].
+#[derive(Clone, Copy)]
+pub struct SharedI2c(&'static RefCell<I2c1>);
+
+impl Write for SharedI2c {
+    type Error = i2c::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), i2c::Error> {
+        self.0.borrow_mut().write(address, bytes)
+    }
+}
+
+impl WriteRead for SharedI2c {
+    type Error = i2c::Error;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), i2c::Error> {
+        self.0.borrow_mut().write_read(address, bytes, buffer)
+    }
+}
+
+/// The `lsm303dlhc` driver, which it dereferences to, plus the status register the driver
+/// has no method for.
+pub struct Lsm303dlhc {
+    driver: lsm303dlhc::Lsm303dlhc<SharedI2c>,
+    i2c: SharedI2c,
+}
+
+impl Lsm303dlhc {
+    /// Whether the accelerometer has a sample it hasn't handed out yet, i.e. `ZYXDA` in
+    /// `STATUS_REG_A`. Reading the sample with `accel` clears it.
+    pub fn accel_data_ready(&mut self) -> Result<bool, i2c::Error> {
+        let mut bits = [0];
+        self.i2c
+            .write_read(ACCELEROMETER, &[StatusRegA::ADDRESS], &mut bits)?;
+        // Every bit pattern is a valid status
+        let status = StatusRegA::from_bits(bits[0]).unwrap_or_default();
+        Ok(status.0.zyx_data_available)
+    }
+}
+
+impl Deref for Lsm303dlhc {
+    type Target = lsm303dlhc::Lsm303dlhc<SharedI2c>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.driver
+    }
+}
+
+impl DerefMut for Lsm303dlhc {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.driver
+    }
+}
 
 pub fn init() -> (Lsm303dlhc, Delay, MonoTimer, ITM) {
+    let (_leds, lsm303dlhc, delay, mono_timer, itm) = init_with_leds();
+    (lsm303dlhc, delay, mono_timer, itm)
+}
+
+/// Like `init` but also hands out the eight user LEDs around the compass rose.
+pub fn init_with_leds() -> (Leds, Lsm303dlhc, Delay, MonoTimer, ITM) {
+    init_with_profile(Profile::default())
+}
+
+/// Like `init_with_leds`, with the clocks set up as `profile` says. The I2C bus speed, `Delay`
+/// and the `MonoTimer` adapt to them.
+pub fn init_with_profile(profile: Profile) -> (Leds, Lsm303dlhc, Delay, MonoTimer, ITM) {
     let cp = cortex_m::Peripherals::take().unwrap();
     let dp = stm32::Peripherals::take().unwrap();
 
     let mut flash = dp.FLASH.constrain();
     let mut rcc = dp.RCC.constrain();
 
-    let clocks = rcc.cfgr.freeze(&mut flash.acr);
+    let clocks = freeze(rcc.cfgr, &mut flash.acr, profile);
 
     let mut gpioe = dp.GPIOE.split(&mut rcc.ahb);
     let mut nss = gpioe
         .pe3
         .into_push_pull_output(&mut gpioe.moder, &mut gpioe.otyper);
     nss.set_high().unwrap();
+    let leds = Leds::new(
+        gpioe.pe8,
+        gpioe.pe9,
+        gpioe.pe10,
+        gpioe.pe11,
+        gpioe.pe12,
+        gpioe.pe13,
+        gpioe.pe14,
+        gpioe.pe15,
+        &mut gpioe.moder,
+        &mut gpioe.otyper,
+    );
 
     let mut gpiob = dp.GPIOB.split(&mut rcc.ahb);
     let scl = gpiob.pb6.into_af4(&mut gpiob.moder, &mut gpiob.afrl);
     let sda = gpiob.pb7.into_af4(&mut gpiob.moder, &mut gpiob.afrl);
 
     let i2c = I2c::new(dp.I2C1, (scl, sda), 400.khz(), clocks, &mut rcc.apb1);
+    set_i2c1_timing(profile, 400_000);
 
-    let lsm303dlhc = Lsm303dlhc::new(i2c).unwrap();
+    let i2c = SharedI2c(cortex_m::singleton!(: RefCell<I2c1> = RefCell::new(i2c)).unwrap());
+    let lsm303dlhc = Lsm303dlhc {
+        driver: lsm303dlhc::Lsm303dlhc::new(i2c).unwrap(),
+        i2c,
+    };
 
     let delay = Delay::new(cp.SYST, clocks);
     let mono_timer = MonoTimer::new(cp.DWT, clocks);
 
-    (lsm303dlhc, delay, mono_timer, cp.ITM)
+    (leds, lsm303dlhc, delay, mono_timer, cp.ITM)
 }
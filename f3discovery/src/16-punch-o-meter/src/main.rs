@@ -3,25 +3,82 @@
 #![no_std]
 
 #[allow(unused_imports)]
-use aux16::{entry, iprint, iprintln, prelude::*, I16x3, Sensitivity};
+use aux16::{
+    entry, iprint, iprintln, prelude::*, switch_hal::OutputSwitch, I16x3, Sensitivity, DWT,
+};
+use punch::{level, DetectorConfig, Event, Impact, Meter};
+
+/// Largest acceleration the sensor can measure with `Sensitivity::G12`.
+const FULL_SCALE_G: f32 = 12.;
+const RESULT_MS: u16 = 3_000;
 
 #[entry]
 fn main() -> ! {
-    let (mut lsm303dlhc, mut delay, _mono_timer, mut itm) = aux16::init();
+    const SENSITIVITY_MG: f32 = 12_000. / (1 << 14) as f32;
+
+    let (leds, mut lsm303dlhc, mut delay, mono_timer, mut itm) = aux16::init_with_leds();
+    let mut leds = leds.into_array();
 
     // extend sensing range to `[-12g, +12g]`
     lsm303dlhc.set_accel_sensitivity(Sensitivity::G12).unwrap();
+
+    let mut meter = Meter::new(DetectorConfig::default());
+    // The detector works with timestamps in microseconds. They are derived from every cycle
+    // counted so far rather than added up interval by interval, which would lose the rounded
+    // off part of each interval.
+    let ticks_per_us = u64::from(mono_timer.frequency().0 / 1_000_000);
+    let mut ticks: u64 = 0;
+    let mut last = DWT::get_cycle_count();
+
+    iprintln!(&mut itm.stim[0], "Armed, punch!");
     loop {
-        const SENSITIVITY: f32 = 12. / (1 << 14) as f32;
+        // The sensor produces a new sample every 2.5 ms at its default 400 Hz
+        while !lsm303dlhc.accel_data_ready().unwrap() {}
+        let cycles = DWT::get_cycle_count();
+        ticks += u64::from(cycles.wrapping_sub(last));
+        last = cycles;
+        let now_us = (ticks / ticks_per_us) as u32;
 
         let I16x3 { x, y, z } = lsm303dlhc.accel().unwrap();
+        let accel = [
+            (f32::from(x) * SENSITIVITY_MG) as i32,
+            (f32::from(y) * SENSITIVITY_MG) as i32,
+            (f32::from(z) * SENSITIVITY_MG) as i32,
+        ];
 
-        let x = f32::from(x) * SENSITIVITY;
-        let y = f32::from(y) * SENSITIVITY;
-        let z = f32::from(z) * SENSITIVITY;
+        match meter.update(now_us, accel) {
+            Some(Event::Started) => iprintln!(&mut itm.stim[0], "Measuring..."),
+            Some(Event::Finished(Impact {
+                peak_g,
+                duration_us,
+            })) => {
+                iprintln!(
+                    &mut itm.stim[0],
+                    "Peak acceleration: {}g over {}ms",
+                    peak_g,
+                    duration_us / 1000
+                );
 
-        iprintln!(&mut itm.stim[0], "{:?}", (x, y, z));
+                // A level meter around the compass rose, starting at North and going
+                // clockwise, each LED standing for 1.5g
+                let lit = level(peak_g, FULL_SCALE_G, leds.len());
+                for (i, led) in leds.iter_mut().enumerate() {
+                    if i < lit {
+                        led.on().unwrap();
+                    } else {
+                        led.off().unwrap();
+                    }
+                }
+                delay.delay_ms(RESULT_MS);
+                leds.iter_mut().for_each(|led| led.off().unwrap());
 
-        delay.delay_ms(1_000_u16);
+                meter.rearm();
+                iprintln!(&mut itm.stim[0], "Armed, punch!");
+            }
+            Some(Event::Dropped) => {
+                iprintln!(&mut itm.stim[0], "Too long for a punch, armed again")
+            }
+            None => {}
+        }
     }
 }
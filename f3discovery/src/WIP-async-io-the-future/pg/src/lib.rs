@@ -8,6 +8,8 @@ extern crate f3;
 
 use core::marker::PhantomData;
 
+use clock_profile::Profile;
+
 #[doc(hidden)]
 pub use f3::itm;
 
@@ -90,9 +92,12 @@ impl Serial {
                 // Disable hardware flow control
                 usart1.cr3.write(|w| w.rtse(false).ctse(false));
 
-                const APB2_CLOCK: u32 = 8_000_000;
+                // The clocks are left as they come out of reset
                 const BAUD_RATE: u32 = 115_200;
-                let brr = (APB2_CLOCK / BAUD_RATE) as u16;
+                let brr = Profile::Hsi8MHz
+                    .frequencies()
+                    .usart1_brr(BAUD_RATE)
+                    .unwrap();
                 usart1.brr.write(|w| {
                     w.div_fraction((brr & 0b1111) as u8)
                         .div_mantissa(brr >> 4)
@@ -0,0 +1,133 @@
+//! A small command shell on the serial port, built with the `serial-shell` crate.
+
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+
+use cortex_m_rt::entry;
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+use microbit::pac::{interrupt, UARTE0};
+use serial_config::{parse_baud_rate, SerialConfig};
+use serial_shell::{ArgError, Args, Command, Shell};
+use uarte_port::{Shared, UartePort};
+
+static SERIAL: Shared<UARTE0, 256, 256> = Shared::new();
+
+#[interrupt]
+fn UARTE0_UART0() {
+    SERIAL.on_interrupt();
+}
+
+/// State the commands share.
+struct Context {
+    /// Sum of all the numbers given to `sum` so far.
+    total: i32,
+    /// Baud rate requested by `baud`, applied once the command is done.
+    baud_rate: Option<u32>,
+}
+
+fn echo(_: &mut Context, args: Args, out: &mut dyn Write) -> Result<(), ArgError> {
+    for word in args.rest() {
+        write!(out, "{} ", word).ok();
+    }
+    out.write_str("\r\n").ok();
+    Ok(())
+}
+
+fn reverse(_: &mut Context, mut args: Args, out: &mut dyn Write) -> Result<(), ArgError> {
+    let word = args.word("word")?;
+    args.finish()?;
+    for c in word.chars().rev() {
+        out.write_char(c).ok();
+    }
+    out.write_str("\r\n").ok();
+    Ok(())
+}
+
+fn sum(context: &mut Context, mut args: Args, out: &mut dyn Write) -> Result<(), ArgError> {
+    let mut sum: i32 = args.required("number")?;
+    while let Some(number) = args.optional::<i32>("number")? {
+        sum = sum.wrapping_add(number);
+    }
+    context.total = context.total.wrapping_add(sum);
+    write!(out, "{} (total {})\r\n", sum, context.total).ok();
+    Ok(())
+}
+
+fn baud(context: &mut Context, mut args: Args, out: &mut dyn Write) -> Result<(), ArgError> {
+    let rate = args.word("rate")?;
+    args.finish()?;
+    match parse_baud_rate(rate) {
+        Ok(baud_rate) => {
+            write!(
+                out,
+                "switching to {} bps, reconnect at that rate\r\n",
+                baud_rate
+            )
+            .ok();
+            context.baud_rate = Some(baud_rate);
+        }
+        Err(e) => {
+            write!(out, "error: {}\r\n", e).ok();
+        }
+    }
+    Ok(())
+}
+
+static COMMANDS: [Command<Context>; 4] = [
+    Command {
+        name: "baud",
+        help: "baud <rate>           switch to another baud rate",
+        run: baud,
+    },
+    Command {
+        name: "echo",
+        help: "echo <word>...        print the words back",
+        run: echo,
+    },
+    Command {
+        name: "reverse",
+        help: "reverse <word>        print the word backwards",
+        run: reverse,
+    },
+    Command {
+        name: "sum",
+        help: "sum <number>...       add numbers up, 0x.. and 0b.. work too",
+        run: sum,
+    },
+];
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    let mut serial = UartePort::with_config(
+        &SERIAL,
+        board.UARTE0,
+        board.uart.into(),
+        &SerialConfig::default(),
+    )
+    .unwrap();
+    write!(serial, "\r\nmicro:bit shell, type `help` for commands\r\n").unwrap();
+
+    // Lines of up to 64 bytes, the last 8 of them are remembered
+    let mut shell: Shell<_, _, 64, 8> = Shell::new(serial, &COMMANDS, "> ");
+    let mut context = Context {
+        total: 0,
+        baud_rate: None,
+    };
+
+    rprintln!("Shell running on the serial port");
+    loop {
+        shell.poll(&mut context);
+        if let Some(baud_rate) = context.baud_rate.take() {
+            // Only baud rates the UARTE supports get past `parse_baud_rate`
+            shell.serial_mut().set_baud_rate(baud_rate).unwrap();
+            rprintln!("Switched to {} bps", baud_rate);
+        }
+    }
+}
@@ -20,9 +20,18 @@ use microbit::{
 };
 
 #[cfg(feature = "v2")]
-mod serial_setup;
+use microbit::pac::{interrupt, UARTE0};
 #[cfg(feature = "v2")]
-use serial_setup::UartePort;
+use uarte_port::{Shared, UartePort};
+
+#[cfg(feature = "v2")]
+static SERIAL: Shared<UARTE0, 256, 256> = Shared::new();
+
+#[cfg(feature = "v2")]
+#[interrupt]
+fn UARTE0_UART0() {
+    SERIAL.on_interrupt();
+}
 
 #[entry]
 fn main() -> ! {
@@ -47,7 +56,7 @@ fn main() -> ! {
             Parity::EXCLUDED,
             Baudrate::BAUD115200,
         );
-        UartePort::new(serial)
+        UartePort::new(&SERIAL, serial)
     };
 
     nb::block!(serial.write(b'X')).unwrap();
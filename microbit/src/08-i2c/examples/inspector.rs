@@ -0,0 +1,49 @@
+//! Scans the external I2C bus and inspects the registers of the devices on it, driven from a
+//! serial terminal. See the `i2c-inspector` crate for the available commands.
+
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+use microbit::{
+    hal::{
+        twim,
+        uarte::{self, Baudrate, Parity},
+    },
+    pac::{interrupt, twim0::frequency::FREQUENCY_A, UARTE0},
+};
+
+use uarte_port::{Shared, UartePort};
+
+static SERIAL: Shared<UARTE0, 256, 256> = Shared::new();
+
+#[interrupt]
+fn UARTE0_UART0() {
+    SERIAL.on_interrupt();
+}
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    let serial = {
+        let serial = uarte::Uarte::new(
+            board.UARTE0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        );
+        UartePort::new(&SERIAL, serial)
+    };
+
+    // SCL on pin 19 and SDA on pin 20 of the edge connector. Use `board.i2c_internal`
+    // instead to look at the LSM303AGR.
+    let i2c = twim::Twim::new(board.TWIM0, board.i2c_external.into(), FREQUENCY_A::K100);
+
+    rprintln!("I2C inspector running on the serial port");
+    i2c_inspector::run(i2c, serial)
+}
@@ -20,11 +20,13 @@ use microbit::{
     pac::twim0::frequency::FREQUENCY_A,
 };
 
-const ACCELEROMETER_ADDR: u8 = 0b0011001;
-const MAGNETOMETER_ADDR: u8 = 0b0011110;
+use lsm303_regs::agr::{AccelRegister, MagRegister};
 
-const ACCELEROMETER_ID_REG: u8 = 0x0f;
-const MAGNETOMETER_ID_REG: u8 = 0x4f;
+const ACCELEROMETER_ADDR: u8 = lsm303_regs::agr::ACCELEROMETER;
+const MAGNETOMETER_ADDR: u8 = lsm303_regs::agr::MAGNETOMETER;
+
+const ACCELEROMETER_ID_REG: u8 = AccelRegister::WHO_AM_I_A.addr();
+const MAGNETOMETER_ID_REG: u8 = MagRegister::WHO_AM_I_M.addr();
 
 #[entry]
 fn main() -> ! {
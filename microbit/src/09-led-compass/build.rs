@@ -6,11 +6,15 @@
 //! build script becomes required. Additionally, by requesting that
 //! Cargo re-run the build script whenever `memory.x` is changed,
 //! a rebuild of the application with new memory settings is ensured after updating `memory.x`.
+//!
+//! It also turns the `DECLINATION` or `LOCATION` environment variables into constants for
+//! `src/declination.rs`, so that a mistyped value fails the build instead of the firmware.
 
 use std::env;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 fn main() {
     // Put `memory.x` in our output directory and ensure it's
@@ -27,4 +31,71 @@ fn main() {
     // here, we ensure the build script is only re-run when
     // `memory.x` is changed.
     println!("cargo:rerun-if-changed=memory.x");
+
+    File::create(out.join("declination.rs"))
+        .unwrap()
+        .write_all(declination_source().as_bytes())
+        .unwrap();
+    println!("cargo:rerun-if-env-changed=DECLINATION");
+    println!("cargo:rerun-if-env-changed=LOCATION");
+}
+
+/// Rust source for the `COMPILED` constant of `src/declination.rs`.
+fn declination_source() -> String {
+    let declination = env::var("DECLINATION").ok();
+    let location = env::var("LOCATION").ok();
+    let source = match (declination, location) {
+        (Some(_), Some(_)) => panic!("set either DECLINATION or LOCATION, not both"),
+        (Some(declination), None) => {
+            let degrees = parse("DECLINATION", &declination, -180.0, 180.0);
+            format!("Compiled::Degrees({:?})", degrees)
+        }
+        (None, Some(location)) => {
+            let parts: Vec<&str> = location.split(',').collect();
+            if parts.len() != 2 && parts.len() != 3 {
+                panic!(
+                    "LOCATION must be `latitude,longitude` or `latitude,longitude,year`, got {:?}",
+                    location
+                );
+            }
+            let latitude = parse("the latitude in LOCATION", parts[0], -90.0, 90.0);
+            let longitude = parse("the longitude in LOCATION", parts[1], -180.0, 360.0);
+            let year = match parts.get(2) {
+                Some(year) => parse("the year in LOCATION", year, 1900.0, 2100.0),
+                None => current_year(),
+            };
+            let model = wmm::model_for(year);
+            if !model.is_valid(year) {
+                println!(
+                    "cargo:warning=none of the models in the `wmm` crate covers {:.1}, WMM{} will be less accurate",
+                    year, model.epoch
+                );
+            }
+            format!(
+                "Compiled::Location {{ latitude: {:?}, longitude: {:?}, year: {:?} }}",
+                latitude, longitude, year
+            )
+        }
+        (None, None) => "Compiled::Default".to_string(),
+    };
+    format!("const COMPILED: Compiled = {};\n", source)
+}
+
+fn parse(name: &str, value: &str, min: f32, max: f32) -> f32 {
+    match value.trim().parse::<f32>() {
+        Ok(number) if (min..=max).contains(&number) => number,
+        _ => panic!(
+            "{} must be a number between {} and {}, got {:?}",
+            name, min, max, value
+        ),
+    }
+}
+
+/// Today's date in decimal years, close enough for a field that changes over decades.
+fn current_year() -> f32 {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs_f64();
+    (1970.0 + seconds / (365.2425 * 24.0 * 60.0 * 60.0)) as f32
 }
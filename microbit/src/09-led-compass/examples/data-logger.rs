@@ -0,0 +1,92 @@
+//! Streams timestamped accelerometer and magnetometer samples over the serial port.
+//!
+//! Every sample is sent as a COBS encoded, CRC protected frame (see the `sensor-frame` crate).
+//! Use the `sensor-log` tool in the `tools` directory of this repository to turn the stream
+//! into CSV on your computer.
+
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use embedded_hal::blocking::serial::Write as _;
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+use microbit::hal::prelude::*;
+use microbit::hal::uarte::{self, Baudrate, Parity};
+use microbit::hal::Timer;
+use microbit::{
+    hal::twim,
+    pac::{interrupt, twim0::frequency::FREQUENCY_A, UARTE0},
+};
+
+use lsm303agr::{AccelOutputDataRate, Lsm303agr, MagOutputDataRate};
+use lsm303agr_sampler::{configure_data_ready, Sampler};
+use sensor_frame::{Sample, MAX_FRAME_LEN};
+use uarte_port::{Shared, UartePort};
+
+static SERIAL: Shared<UARTE0, 256, 256> = Shared::new();
+
+#[interrupt]
+fn UARTE0_UART0() {
+    SERIAL.on_interrupt();
+}
+
+#[interrupt]
+fn GPIOTE() {
+    lsm303agr_sampler::handle_interrupt();
+}
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    let mut i2c = twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100);
+
+    let mut serial = {
+        let serial = uarte::Uarte::new(
+            board.UARTE0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        );
+        UartePort::new(&SERIAL, serial)
+    };
+
+    // A free running 1 MHz counter for the timestamps
+    let mut clock = Timer::periodic(board.TIMER0);
+    clock.start(u32::MAX);
+
+    configure_data_ready(&mut i2c).unwrap();
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    sensor.set_mag_odr(MagOutputDataRate::Hz50).unwrap();
+    sensor.set_accel_odr(AccelOutputDataRate::Hz50).unwrap();
+    let sensor = sensor.into_mag_continuous().ok().unwrap();
+    let mut sampler = Sampler::new(sensor, board.GPIOTE, board.pins.p0_25).unwrap();
+
+    rprintln!("Streaming samples");
+    let mut frame = [0; MAX_FRAME_LEN];
+    loop {
+        // Both sensors run at the same rate, so pace the stream with the accelerometer's
+        // data ready interrupt and sleep in between
+        let (accel, mag) = match sampler.accel_and_mag() {
+            Ok(readings) => readings,
+            Err(error) => {
+                rprintln!("Reading the sensor failed: {:?}", error);
+                continue;
+            }
+        };
+        let timestamp_us = clock.read();
+
+        let sample = Sample {
+            timestamp_us,
+            accel: [accel.x, accel.y, accel.z],
+            mag: [mag.x, mag.y, mag.z],
+        };
+        let len = sample.encode(&mut frame);
+        serial.bwrite_all(&frame[..len]).unwrap();
+        serial.bflush().unwrap();
+    }
+}
@@ -0,0 +1,96 @@
+//! Correction from magnetic north to true (geographic) north.
+//!
+//! The magnetometer points towards magnetic north, which differs from true north by the
+//! *magnetic declination* of the place you are standing at. Declination is positive when
+//! magnetic north lies east of true north and negative when it lies west of it.
+//!
+//! The declination is compiled into the firmware. Either
+//!
+//! - set `LOCATION` to your latitude and longitude in degrees, e.g.
+//!   `LOCATION=52.5,13.4 cargo embed`, and the board works the declination out with the
+//!   World Magnetic Model from the `wmm` crate when it starts, or
+//! - look it up with NOAA's [magnetic field calculator] and set `DECLINATION` instead, e.g.
+//!   `DECLINATION=-3.5 cargo embed`, or change [`LOCAL_DECLINATION_DEG`].
+//!
+//! `build.rs` checks both variables, so a value that isn't a number fails the build.
+//!
+//! [magnetic field calculator]: https://www.ngdc.noaa.gov/geomag/calculators/magcalc.shtml#declination
+
+use core::f32::consts::PI;
+
+/// Where the declination comes from, see `build.rs`.
+// Every build only uses one of the variants
+#[allow(dead_code)]
+enum Compiled {
+    /// Neither `DECLINATION` nor `LOCATION` was set.
+    Default,
+    /// `DECLINATION`, in degrees.
+    Degrees(f32),
+    /// `LOCATION`, in degrees, and the decimal year the firmware was built in unless given.
+    Location {
+        latitude: f32,
+        longitude: f32,
+        year: f32,
+    },
+}
+
+include!(concat!(env!("OUT_DIR"), "/declination.rs"));
+
+/// Declination used when `DECLINATION` is not set at compile time, in degrees.
+pub const LOCAL_DECLINATION_DEG: f32 = 0.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Declination {
+    radians: f32,
+}
+
+impl Declination {
+    pub fn from_degrees(degrees: f32) -> Declination {
+        Declination {
+            radians: degrees * PI / 180.0,
+        }
+    }
+
+    /// The declination at a geodetic `latitude` and `longitude` in degrees in the decimal
+    /// `year`, according to the World Magnetic Model made for that year.
+    pub fn at(latitude: f32, longitude: f32, year: f32) -> Declination {
+        let field = wmm::model_for(year).field(latitude, longitude, 0.0, year);
+        Declination::from_degrees(field.declination())
+    }
+
+    /// The declination compiled into the firmware.
+    ///
+    /// Evaluates the World Magnetic Model if `LOCATION` was set when building, uses
+    /// `DECLINATION` (in degrees, east positive) if that was set and falls back to
+    /// [`LOCAL_DECLINATION_DEG`] otherwise.
+    pub fn compiled() -> Declination {
+        match COMPILED {
+            Compiled::Default => Declination::from_degrees(LOCAL_DECLINATION_DEG),
+            Compiled::Degrees(degrees) => Declination::from_degrees(degrees),
+            Compiled::Location {
+                latitude,
+                longitude,
+                year,
+            } => Declination::at(latitude, longitude, year),
+        }
+    }
+
+    pub fn degrees(&self) -> f32 {
+        self.radians * 180.0 / PI
+    }
+
+    /// Turns the angle of the magnetic field, as returned by `atan2f(y, x)` on a calibrated
+    /// measurement, into the angle of true north in the same `-PI..=PI` range.
+    ///
+    /// Angles grow counterclockwise, so true north sits `declination` further
+    /// counterclockwise than magnetic north.
+    pub fn true_north(&self, theta: f32) -> f32 {
+        let mut corrected = theta + self.radians;
+        if corrected > PI {
+            corrected -= 2.0 * PI;
+        } else if corrected < -PI {
+            corrected += 2.0 * PI;
+        }
+        corrected
+    }
+}
@@ -9,8 +9,12 @@ use rtt_target::{rprintln, rtt_init_print};
 mod calibration;
 use crate::calibration::calc_calibration;
 use crate::calibration::calibrated_measurement;
+mod declination;
+use crate::declination::Declination;
+use lsm303agr_sampler::{configure_data_ready, Sampler};
 
-use microbit::{display::blocking::Display, hal::Timer};
+use libm::atan2f;
+use microbit::{display::blocking::Display, hal::Timer, pac::interrupt};
 
 #[cfg(feature = "v1")]
 use microbit::{hal::twi, pac::twi0::frequency::FREQUENCY_A};
@@ -29,24 +33,51 @@ fn main() -> ! {
     let i2c = { twi::Twi::new(board.TWI0, board.i2c.into(), FREQUENCY_A::K100) };
 
     #[cfg(feature = "v2")]
-    let i2c = { twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100) };
+    let mut i2c = { twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100) };
 
     let mut timer = Timer::new(board.TIMER0);
     let mut display = Display::new(board.display_pins);
 
+    configure_data_ready(&mut i2c).unwrap();
     let mut sensor = Lsm303agr::new_with_i2c(i2c);
     sensor.init().unwrap();
     sensor.set_mag_odr(MagOutputDataRate::Hz10).unwrap();
     sensor.set_accel_odr(AccelOutputDataRate::Hz10).unwrap();
-    let mut sensor = sensor.into_mag_continuous().ok().unwrap();
+    let sensor = sensor.into_mag_continuous().ok().unwrap();
+    let mut sampler = Sampler::new(sensor, board.GPIOTE, board.pins.p0_25).unwrap();
 
-    let calibration = calc_calibration(&mut sensor, &mut display, &mut timer);
+    let calibration = calc_calibration(&mut sampler, &mut display, &mut timer);
     rprintln!("Calibration: {:?}", calibration);
-    rprintln!("Calibration done, entering busy loop");
+    rprintln!("Calibration score: {}/100", calibration.quality().score());
+
+    let declination = Declination::compiled();
+    rprintln!("Declination: {} degrees", declination.degrees());
+    rprintln!("Calibration done, sleeping between samples");
     loop {
-        while !sensor.mag_status().unwrap().xyz_new_data {}
-        let mut data = sensor.mag_data().unwrap();
-        data = calibrated_measurement(data, &calibration);
-        rprintln!("x: {}, y: {}, z: {}", data.x, data.y, data.z);
+        let data = match sampler.mag() {
+            Ok(data) => calibrated_measurement(data, &calibration),
+            Err(error) => {
+                rprintln!("Reading the magnetometer failed: {:?}", error);
+                continue;
+            }
+        };
+        // ANCHOR: heading
+        // use libm's atan2f since this isn't in core yet
+        let theta = atan2f(data.y as f32, data.x as f32);
+        // rotate from magnetic north to true north
+        let theta = declination.true_north(theta);
+        // ANCHOR_END: heading
+        rprintln!(
+            "x: {}, y: {}, z: {}, true north at {} degrees",
+            data.x,
+            data.y,
+            data.z,
+            theta.to_degrees()
+        );
     }
 }
+
+#[interrupt]
+fn GPIOTE() {
+    lsm303agr_sampler::handle_interrupt();
+}
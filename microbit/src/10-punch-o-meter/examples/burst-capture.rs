@@ -0,0 +1,103 @@
+//! Records every impact at 1.344 kHz and prints it over the serial port as CSV.
+
+#![no_main]
+#![no_std]
+
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use heapless::Vec;
+use libm::sqrtf;
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+use microbit::{
+    hal::{
+        gpiote::Gpiote,
+        twim,
+        uarte::{self, Baudrate, Parity},
+    },
+    pac::{interrupt, twim0::frequency::FREQUENCY_A, NVIC, UARTE0},
+};
+use uarte_port::{Shared, UartePort};
+
+#[path = "../src/burst.rs"]
+mod burst;
+use burst::{BurstCapture, CAPTURE_LEN, SAMPLE_RATE_HZ};
+
+static SERIAL: Shared<UARTE0, 256, 256> = Shared::new();
+
+#[interrupt]
+fn UARTE0_UART0() {
+    SERIAL.on_interrupt();
+}
+
+/// Acceleration on top of gravity that counts as an impact.
+const THRESHOLD_MG: u16 = 2_000;
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+    let mut core = cortex_m::Peripherals::take().unwrap();
+
+    let mut serial = {
+        let serial = uarte::Uarte::new(
+            board.UARTE0,
+            board.uart.into(),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200,
+        );
+        UartePort::new(&SERIAL, serial)
+    };
+
+    // Draining the FIFO has to be faster than the sensor fills it
+    let i2c = twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K400);
+    let mut capture = BurstCapture::new(i2c);
+    capture.configure(THRESHOLD_MG).unwrap();
+
+    // The GPIOTE interrupt stays masked in the NVIC, it only has to become pending to wake
+    // the processor up from `wfe`
+    let int_pin = board.pins.p0_25.into_pullup_input().degrade();
+    let gpiote = Gpiote::new(board.GPIOTE);
+    gpiote
+        .channel0()
+        .input_pin(&int_pin)
+        .hi_to_lo()
+        .enable_interrupt();
+    core.SCB.set_sevonpend();
+
+    let mut samples: Vec<[i32; 3], CAPTURE_LEN> = Vec::new();
+    let mut impact = 0;
+    loop {
+        gpiote.reset_events();
+        NVIC::unpend(interrupt::GPIOTE);
+        capture.arm().unwrap();
+
+        rprintln!("Armed, punch!");
+        while !gpiote.channel0().is_event_triggered() {
+            cortex_m::asm::wfe();
+        }
+
+        capture.capture(&mut samples).unwrap();
+
+        let peak_g = samples
+            .iter()
+            .map(|[x, y, z]| sqrtf((x * x + y * y + z * z) as f32) / 1000.0)
+            .fold(0.0, f32::max);
+        rprintln!("Impact {}: peak {}g", impact, peak_g);
+
+        writeln!(serial, "impact,t_us,x_mg,y_mg,z_mg\r").unwrap();
+        for (i, sample) in samples.iter().enumerate() {
+            // The samples are evenly spaced, multiplying before dividing keeps the rounding
+            // from adding up over the capture
+            let t_us = i as u32 * 1_000_000 / SAMPLE_RATE_HZ;
+            writeln!(
+                serial,
+                "{},{},{},{},{}\r",
+                impact, t_us, sample[0], sample[1], sample[2]
+            )
+            .unwrap();
+        }
+        impact += 1;
+    }
+}
@@ -0,0 +1,33 @@
+use embedded_hal::blocking::delay::DelayMs;
+use microbit::{
+    hal::{
+        gpio::{p0::P0_00, Disconnected, Level},
+        pwm::{Channel, Pwm},
+        time::Hertz,
+    },
+    pac::PWM0,
+};
+
+/// Square wave beeps on the speaker of the micro:bit v2.
+pub struct Beeper {
+    pwm: Pwm<PWM0>,
+}
+
+impl Beeper {
+    pub fn new(pwm: PWM0, speaker: P0_00<Disconnected>) -> Beeper {
+        let speaker = speaker.into_push_pull_output(Level::Low).degrade();
+        let pwm = Pwm::new(pwm);
+        pwm.set_output_pin(Channel::C0, speaker);
+        pwm.disable();
+        Beeper { pwm }
+    }
+
+    /// Plays a tone of `frequency` Hz for `duration_ms`, blocking until it is done.
+    pub fn beep<D: DelayMs<u16>>(&mut self, delay: &mut D, frequency: u32, duration_ms: u16) {
+        self.pwm.set_period(Hertz(frequency));
+        self.pwm.set_duty_on_common(self.pwm.max_duty() / 2);
+        self.pwm.enable();
+        delay.delay_ms(duration_ms);
+        self.pwm.disable();
+    }
+}
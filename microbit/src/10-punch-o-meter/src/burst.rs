@@ -0,0 +1,175 @@
+//! Capturing a whole impact with the FIFO of the accelerometer.
+//!
+//! At 50 Hz there are only one or two samples in a punch, and most likely none of them at its
+//! peak. The accelerometer can sample much faster than we can read it over I2C, though, by
+//! storing up to 32 samples in its own FIFO. In *stream-to-FIFO* mode the FIFO keeps the
+//! latest samples until the activity interrupt fires and then stops overwriting them, so it
+//! holds what led up to the impact. Switching to stream mode and draining the FIFO for a
+//! little longer records the rest.
+//!
+//! The switch happens before the frozen samples are read. Reading 32 samples takes about
+//! 5 ms at 400 kHz, during which the sensor takes seven more; in stream mode those overwrite
+//! the oldest samples from before the impact instead of getting lost, so a capture has no
+//! holes and sample `i` was taken `i / SAMPLE_RATE_HZ` seconds after the first one.
+//!
+//! This talks to the registers directly, the `lsm303agr` driver doesn't know about the FIFO.
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use heapless::Vec;
+
+/// Samples the FIFO can hold.
+pub const FIFO_DEPTH: usize = 32;
+/// Samples in a capture, about 95 ms worth.
+pub const CAPTURE_LEN: usize = 128;
+/// Highest output data rate in high resolution mode.
+pub const SAMPLE_RATE_HZ: u32 = 1_344;
+
+const ACCELEROMETER_ADDR: u8 = 0b0011001;
+/// Makes the register address increment with every byte read.
+const AUTO_INCREMENT: u8 = 0x80;
+
+const CTRL_REG1_A: u8 = 0x20;
+const CTRL_REG2_A: u8 = 0x21;
+const CTRL_REG3_A: u8 = 0x22;
+const CTRL_REG4_A: u8 = 0x23;
+const CTRL_REG5_A: u8 = 0x24;
+const CTRL_REG6_A: u8 = 0x25;
+const OUT_X_L_A: u8 = 0x28;
+const FIFO_CTRL_REG_A: u8 = 0x2E;
+const FIFO_SRC_REG_A: u8 = 0x2F;
+const INT1_CFG_A: u8 = 0x30;
+const INT1_SRC_A: u8 = 0x31;
+const INT1_THS_A: u8 = 0x32;
+const INT1_DURATION_A: u8 = 0x33;
+
+/// 1.344 kHz, all three axes enabled.
+const ODR_1344HZ_XYZ: u8 = 0b1001_0111;
+/// Feed the high-pass filtered data, i.e. without gravity, to interrupt generator 1.
+const HPIS1: u8 = 1 << 0;
+/// Route interrupt generator 1 to the interrupt pin.
+const I1_AOI1: u8 = 1 << 6;
+/// Block data update, +-16g, high resolution.
+const BDU_FS_16G_HR: u8 = 0b1011_1000;
+const FIFO_EN: u8 = 1 << 6;
+/// Keep the interrupt active until `INT1_SRC_A` is read.
+const LIR_INT1: u8 = 1 << 3;
+/// Drive the interrupt pin low while the interrupt is active.
+const H_LACTIVE: u8 = 1 << 1;
+/// Fire when any axis goes above the threshold.
+const XYZ_HIGH: u8 = 0b0010_1010;
+
+const FM_BYPASS: u8 = 0b00 << 6;
+const FM_STREAM: u8 = 0b10 << 6;
+const FM_STREAM_TO_FIFO: u8 = 0b11 << 6;
+
+const OVRN_FIFO: u8 = 1 << 6;
+const EMPTY: u8 = 1 << 5;
+const FSS_MASK: u8 = 0b1_1111;
+
+/// Threshold resolution at +-16g.
+const THRESHOLD_LSB_MG: u16 = 186;
+/// Sensitivity in high resolution mode at +-16g.
+const MG_PER_DIGIT: i32 = 12;
+
+pub struct BurstCapture<I> {
+    i2c: I,
+}
+
+impl<I, E> BurstCapture<I>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+{
+    pub fn new(i2c: I) -> BurstCapture<I> {
+        BurstCapture { i2c }
+    }
+
+    /// Gives the bus back.
+    pub fn release(self) -> I {
+        self.i2c
+    }
+
+    /// Sets the accelerometer up for burst captures. An impact is any acceleration, gravity
+    /// left out, above `threshold_mg` on any axis.
+    pub fn configure(&mut self, threshold_mg: u16) -> Result<(), E> {
+        let threshold = (threshold_mg / THRESHOLD_LSB_MG).clamp(1, 127) as u8;
+
+        self.write(CTRL_REG1_A, ODR_1344HZ_XYZ)?;
+        self.write(CTRL_REG2_A, HPIS1)?;
+        self.write(CTRL_REG4_A, BDU_FS_16G_HR)?;
+        self.write(CTRL_REG5_A, FIFO_EN | LIR_INT1)?;
+        self.write(CTRL_REG6_A, H_LACTIVE)?;
+        self.write(INT1_THS_A, threshold)?;
+        self.write(INT1_DURATION_A, 0)?;
+        self.write(INT1_CFG_A, XYZ_HIGH)?;
+        self.write(CTRL_REG3_A, I1_AOI1)
+    }
+
+    /// Empties the FIFO and waits for the next impact. The interrupt pin goes low once it
+    /// happened.
+    pub fn arm(&mut self) -> Result<(), E> {
+        // Going through bypass mode is the only way to reset the FIFO
+        self.write(FIFO_CTRL_REG_A, FM_BYPASS)?;
+        self.write(FIFO_CTRL_REG_A, FM_STREAM_TO_FIFO)?;
+        // Clears the latched interrupt
+        self.read(INT1_SRC_A)?;
+        Ok(())
+    }
+
+    /// Reads the impact out of the sensor, in milli-g. Call this after the interrupt fired.
+    ///
+    /// The first samples come from the frozen FIFO, the rest is read while the sensor keeps
+    /// streaming, so this blocks for the ~95 ms the capture lasts. The samples are evenly
+    /// spaced, see the module documentation.
+    pub fn capture(&mut self, samples: &mut Vec<[i32; 3], CAPTURE_LEN>) -> Result<(), E> {
+        samples.clear();
+        // Leaving stream-to-FIFO mode for anything but bypass mode keeps what the FIFO holds
+        self.write(FIFO_CTRL_REG_A, FM_STREAM)?;
+        while !samples.is_full() {
+            self.drain(samples)?;
+        }
+        self.write(FIFO_CTRL_REG_A, FM_BYPASS)
+    }
+
+    /// Moves everything the FIFO holds, as far as there is room, into `samples`.
+    fn drain(&mut self, samples: &mut Vec<[i32; 3], CAPTURE_LEN>) -> Result<(), E> {
+        let src = self.read(FIFO_SRC_REG_A)?;
+        let stored = if src & EMPTY != 0 {
+            0
+        } else if src & OVRN_FIFO != 0 {
+            FIFO_DEPTH
+        } else {
+            (src & FSS_MASK) as usize
+        };
+        let count = stored.min(samples.capacity() - samples.len());
+        if count == 0 {
+            return Ok(());
+        }
+
+        // With the FIFO enabled the address wraps around from the last output register
+        // to the first, so all samples can be read in one go
+        let mut buffer = [0; 6 * FIFO_DEPTH];
+        let buffer = &mut buffer[..6 * count];
+        self.i2c
+            .write_read(ACCELEROMETER_ADDR, &[OUT_X_L_A | AUTO_INCREMENT], buffer)?;
+
+        for raw in buffer.chunks(6) {
+            // 12 bit values, left justified
+            let axis =
+                |i: usize| i32::from(i16::from_le_bytes([raw[i], raw[i + 1]]) >> 4) * MG_PER_DIGIT;
+            // There is room for `count` samples
+            samples.push([axis(0), axis(2), axis(4)]).ok();
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, register: u8, value: u8) -> Result<(), E> {
+        self.i2c.write(ACCELEROMETER_ADDR, &[register, value])
+    }
+
+    fn read(&mut self, register: u8) -> Result<u8, E> {
+        let mut value = [0];
+        self.i2c
+            .write_read(ACCELEROMETER_ADDR, &[register], &mut value)?;
+        Ok(value[0])
+    }
+}
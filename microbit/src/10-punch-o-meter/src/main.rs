@@ -3,13 +3,97 @@
 #![no_std]
 
 use cortex_m_rt::entry;
-use rtt_target::rtt_init_print;
 use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+
+use lsm303agr::{AccelOutputDataRate, AccelScale, Lsm303agr};
+use lsm303agr_sampler::{configure_data_ready, Sampler};
+use microbit::{
+    display::blocking::Display,
+    hal::{twim, Timer},
+    pac::{interrupt, twim0::frequency::FREQUENCY_A},
+};
+
+use punch::{bar_graph, level, pitch_hz, DetectorConfig, Event, Impact, Meter, MATRIX_LEDS};
+
+mod beep;
+use beep::Beeper;
+
+/// Time between two accelerometer samples at 400 Hz.
+const SAMPLE_PERIOD_US: u32 = 2_500;
+/// Largest acceleration the sensor can measure with `AccelScale::G16`.
+const FULL_SCALE_G: f32 = 16.0;
+const RESULT_MS: u32 = 3_000;
 
 #[entry]
 fn main() -> ! {
     rtt_init_print!();
     let board = microbit::Board::take().unwrap();
 
-    loop {}
+    // Reading a sample at 400 Hz keeps the bus quite busy, so run it at full speed
+    let mut i2c = twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K400);
+    configure_data_ready(&mut i2c).unwrap();
+
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().unwrap();
+    // Punches are short, sample fast enough to catch their peak
+    sensor.set_accel_odr(AccelOutputDataRate::Hz400).unwrap();
+    sensor.set_accel_scale(AccelScale::G16).unwrap();
+    let mut sampler = Sampler::new(sensor, board.GPIOTE, board.pins.p0_25).unwrap();
+
+    let mut timer = Timer::new(board.TIMER0);
+    let mut display = Display::new(board.display_pins);
+    let mut beeper = Beeper::new(board.PWM0, board.speaker_pin);
+
+    let mut meter = Meter::new(DetectorConfig::default());
+    // Samples arrive at a fixed rate, so counting them is as good as a clock
+    let mut now_us: u32 = 0;
+
+    rprintln!("Armed, punch!");
+    loop {
+        let accel = match sampler.accel() {
+            Ok(accel) => accel,
+            Err(error) => {
+                rprintln!("Reading the accelerometer failed: {:?}", error);
+                continue;
+            }
+        };
+        now_us = now_us.wrapping_add(SAMPLE_PERIOD_US);
+
+        match meter.update(now_us, [accel.x, accel.y, accel.z]) {
+            Some(Event::Started) => rprintln!(
+                "Measuring... (threshold: {}g)",
+                meter.detector().threshold()
+            ),
+            Some(Event::Finished(Impact {
+                peak_g,
+                duration_us,
+            })) => {
+                rprintln!(
+                    "Peak acceleration: {}g over {}ms",
+                    peak_g,
+                    duration_us / 1000
+                );
+
+                let leds = level(peak_g, FULL_SCALE_G, MATRIX_LEDS);
+                beeper.beep(&mut timer, pitch_hz(leds), 200);
+                display.show(&mut timer, bar_graph(leds), RESULT_MS);
+                display.clear();
+
+                // Whatever was measured while showing the result is stale by now
+                if let Err(error) = sampler.clear() {
+                    rprintln!("Reading the accelerometer failed: {:?}", error);
+                }
+                meter.rearm();
+                rprintln!("Armed, punch!");
+            }
+            Some(Event::Dropped) => rprintln!("Too long for a punch, armed again"),
+            None => {}
+        }
+    }
+}
+
+#[interrupt]
+fn GPIOTE() {
+    lsm303agr_sampler::handle_interrupt();
 }
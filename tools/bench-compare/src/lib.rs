@@ -0,0 +1,156 @@
+//! Comparison of two benchmark reports written by the `cycle-bench` harness, the library half
+//! of the `bench-compare` tool.
+//!
+//! ```
+//! let before = "bench,tdr-loop,100,22415,22480,22610,2801.875,2810.000,2826.250\n";
+//! let after = "bench,tdr-loop,100,22300,22310,22400,2787.500,2788.750,2800.000\n";
+//! let comparison = bench_compare::compare(before, after, 5.0);
+//! assert!(!comparison.slower());
+//! assert_eq!(
+//!     comparison.to_string(),
+//!     "name                 before      after   change\n\
+//!      tdr-loop              22480      22310    -0.8%\n"
+//! );
+//! ```
+
+use std::fmt;
+
+use cycle_bench::Record;
+
+/// How the mean cycle count of a benchmark changed between the two reports.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Row<'r> {
+    /// In both reports.
+    Changed {
+        name: &'r str,
+        before: u64,
+        after: u64,
+        /// Relative change in percent, positive when it got slower.
+        percent: f64,
+        /// Whether the change is larger than the threshold.
+        flagged: bool,
+    },
+    /// Only in the new report.
+    Added { name: &'r str, after: u64 },
+    /// Only in the old report.
+    Removed { name: &'r str, before: u64 },
+}
+
+/// The benchmarks of the new report in its order, followed by those that disappeared.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison<'r> {
+    pub rows: Vec<Row<'r>>,
+}
+
+impl Comparison<'_> {
+    /// Whether any benchmark got slower by more than the threshold.
+    pub fn slower(&self) -> bool {
+        self.rows.iter().any(|row| match *row {
+            Row::Changed {
+                percent, flagged, ..
+            } => flagged && percent > 0.0,
+            _ => false,
+        })
+    }
+}
+
+impl fmt::Display for Comparison<'_> {
+    /// The table `bench-compare` prints.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<16} {:>10} {:>10} {:>8}",
+            "name", "before", "after", "change"
+        )?;
+        for row in &self.rows {
+            match *row {
+                Row::Changed {
+                    name,
+                    before,
+                    after,
+                    percent,
+                    flagged,
+                } => {
+                    let flag = match (flagged, percent > 0.0) {
+                        (false, _) => "",
+                        (true, true) => "  slower",
+                        (true, false) => "  faster",
+                    };
+                    writeln!(
+                        f,
+                        "{:<16} {:>10} {:>10} {:>+7.1}%{}",
+                        name, before, after, percent, flag
+                    )?;
+                }
+                Row::Added { name, after } => {
+                    writeln!(f, "{:<16} {:>10} {:>10}", name, "-", after)?
+                }
+                Row::Removed { name, before } => {
+                    writeln!(f, "{:<16} {:>10} {:>10}", name, before, "-")?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compares the mean cycle counts in two reports, flagging changes of more than
+/// `threshold_percent`.
+pub fn compare<'r>(before: &'r str, after: &'r str, threshold_percent: f64) -> Comparison<'r> {
+    let before = records(before);
+    let after = records(after);
+
+    let mut rows = Vec::new();
+    for new in &after {
+        match before.iter().find(|old| old.name == new.name) {
+            Some(old) => {
+                let percent = percent_change(old.mean_cycles, new.mean_cycles);
+                rows.push(Row::Changed {
+                    name: new.name,
+                    before: old.mean_cycles,
+                    after: new.mean_cycles,
+                    percent,
+                    flagged: percent.abs() > threshold_percent,
+                });
+            }
+            None => rows.push(Row::Added {
+                name: new.name,
+                after: new.mean_cycles,
+            }),
+        }
+    }
+    for old in before
+        .iter()
+        .filter(|old| after.iter().all(|new| new.name != old.name))
+    {
+        rows.push(Row::Removed {
+            name: old.name,
+            before: old.mean_cycles,
+        });
+    }
+    Comparison { rows }
+}
+
+/// The results in a report, which can be a whole log with other output mixed in. If a
+/// benchmark shows up more than once, the last run wins.
+pub fn records(report: &str) -> Vec<Record<'_>> {
+    let mut records: Vec<Record> = Vec::new();
+    for record in report.lines().filter_map(cycle_bench::parse_line) {
+        records.retain(|r| r.name != record.name);
+        records.push(record);
+    }
+    records
+}
+
+/// Change from `before` to `after` in percent. Anything is infinitely slower than nothing.
+pub fn percent_change(before: u64, after: u64) -> f64 {
+    if before == 0 {
+        if after == 0 {
+            0.0
+        } else {
+            f64::INFINITY
+        }
+    } else {
+        (after as f64 - before as f64) / before as f64 * 100.0
+    }
+}
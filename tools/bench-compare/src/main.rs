@@ -0,0 +1,62 @@
+//! Compares two benchmark reports written by the `cycle-bench` harness.
+//!
+//! ``` console
+//! $ bench-compare before.txt after.txt
+//! name                 before      after   change
+//! tdr-loop              22480      22310    -0.8%
+//! txe-loop              30499      33150    +8.7%  slower
+//! ```
+//!
+//! The reports can be whole ITM or serial logs, only the `bench,` lines are looked at. Mean
+//! cycle counts are compared; a benchmark whose mean changed by more than the threshold (5% by
+//! default, `--threshold PERCENT` to change it) is flagged, and any one that got slower makes
+//! the exit status 1 so the comparison can fail a script.
+
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "usage: bench-compare [--threshold PERCENT] BEFORE AFTER";
+
+fn main() {
+    let mut threshold = 5.0;
+    let mut paths = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                eprintln!("{}", USAGE);
+                process::exit(0);
+            }
+            "--threshold" => match args.next().and_then(|t| t.parse().ok()) {
+                Some(t) => threshold = t,
+                None => fail("--threshold needs a number"),
+            },
+            _ => paths.push(arg),
+        }
+    }
+    if paths.len() != 2 {
+        fail(USAGE);
+    }
+
+    let before = read(&paths[0]);
+    let after = read(&paths[1]);
+    let comparison = bench_compare::compare(&before, &after, threshold);
+    print!("{}", comparison);
+
+    if comparison.slower() {
+        process::exit(1);
+    }
+}
+
+fn read(path: &str) -> String {
+    match fs::read_to_string(path) {
+        Ok(report) => report,
+        Err(e) => fail(&format!("could not read {}: {}", path, e)),
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(2);
+}
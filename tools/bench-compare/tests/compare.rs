@@ -0,0 +1,154 @@
+use bench_compare::{compare, percent_change, records, Row};
+
+const BEFORE: &str = "\
+Running benchmarks at 8000000 Hz
+bench,name,runs,min_cycles,mean_cycles,max_cycles,min_us,mean_us,max_us
+bench,tdr-loop,100,22415,22480,22610,2801.875,2810.000,2826.250
+bench,txe-loop,100,30400,30499,30700,3800.000,3812.375,3837.500
+bench,old-loop,100,900,1000,1100,112.500,125.000,137.500
+done
+";
+
+const AFTER: &str = "\
+bench,name,runs,min_cycles,mean_cycles,max_cycles,min_us,mean_us,max_us
+bench,txe-loop,100,33000,33150,33300,4125.000,4143.750,4162.500
+bench,tdr-loop,100,22300,22310,22400,2787.500,2788.750,2800.000
+bench,new-loop,100,400,500,600,50.000,62.500,75.000
+";
+
+#[test]
+fn records_skip_other_output() {
+    let records = records(BEFORE);
+    let names: Vec<_> = records.iter().map(|r| r.name).collect();
+    assert_eq!(names, ["tdr-loop", "txe-loop", "old-loop"]);
+    assert_eq!(records[1].mean_cycles, 30_499);
+    assert_eq!(records[1].runs, 100);
+}
+
+#[test]
+fn records_keep_the_last_run() {
+    let report = "\
+bench,a,10,1,2,3,0.125,0.250,0.375
+bench,b,10,1,2,3,0.125,0.250,0.375
+bench,a,10,4,5,6,0.500,0.625,0.750
+";
+    let records = records(report);
+    let means: Vec<_> = records.iter().map(|r| (r.name, r.mean_cycles)).collect();
+    assert_eq!(means, [("b", 2), ("a", 5)]);
+}
+
+#[test]
+fn records_skip_broken_lines() {
+    let report = "\
+bench,cut-off,10,1,2
+bench,extra,10,1,2,3,0.125,0.250,0.375,4
+bench,not-a-number,10,1,two,3,0.125,0.250,0.375
+bench,fine,10,1,2,3,0.125,0.250,0.375
+";
+    let records = records(report);
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].name, "fine");
+}
+
+#[test]
+fn percentages() {
+    assert_eq!(percent_change(200, 250), 25.0);
+    assert_eq!(percent_change(200, 150), -25.0);
+    assert_eq!(percent_change(200, 200), 0.0);
+    assert_eq!(percent_change(0, 0), 0.0);
+    assert_eq!(percent_change(0, 1), f64::INFINITY);
+}
+
+#[test]
+fn changes_are_flagged_beyond_the_threshold() {
+    let comparison = compare(BEFORE, AFTER, 5.0);
+    match comparison.rows[0] {
+        Row::Changed {
+            name,
+            before,
+            after,
+            percent,
+            flagged,
+        } => {
+            assert_eq!((name, before, after), ("txe-loop", 30_499, 33_150));
+            assert!((percent - 8.69).abs() < 0.01, "{}", percent);
+            assert!(flagged);
+        }
+        row => panic!("unexpected {:?}", row),
+    }
+    match comparison.rows[1] {
+        Row::Changed { name, flagged, .. } => {
+            assert_eq!(name, "tdr-loop");
+            assert!(!flagged);
+        }
+        row => panic!("unexpected {:?}", row),
+    }
+    assert!(comparison.slower());
+
+    // With a higher threshold the same change is fine
+    assert!(!compare(BEFORE, AFTER, 10.0).slower());
+}
+
+#[test]
+fn faster_is_not_slower() {
+    let comparison = compare(AFTER, BEFORE, 5.0);
+    match comparison.rows[1] {
+        Row::Changed {
+            name,
+            percent,
+            flagged,
+            ..
+        } => {
+            assert_eq!(name, "txe-loop");
+            assert!(percent < -5.0);
+            assert!(flagged);
+        }
+        row => panic!("unexpected {:?}", row),
+    }
+    assert!(!comparison.slower());
+}
+
+#[test]
+fn added_and_removed_benchmarks() {
+    let comparison = compare(BEFORE, AFTER, 5.0);
+    assert_eq!(comparison.rows.len(), 4);
+    assert_eq!(
+        comparison.rows[2],
+        Row::Added {
+            name: "new-loop",
+            after: 500
+        }
+    );
+    assert_eq!(
+        comparison.rows[3],
+        Row::Removed {
+            name: "old-loop",
+            before: 1000
+        }
+    );
+}
+
+#[test]
+fn nothing_to_compare() {
+    let comparison = compare("", "no benchmarks here\n", 5.0);
+    assert!(comparison.rows.is_empty());
+    assert!(!comparison.slower());
+    assert_eq!(
+        comparison.to_string(),
+        "name                 before      after   change\n"
+    );
+}
+
+#[test]
+fn table() {
+    assert_eq!(
+        compare(BEFORE, AFTER, 5.0).to_string(),
+        "\
+name                 before      after   change
+txe-loop              30499      33150    +8.7%  slower
+tdr-loop              22480      22310    -0.8%
+new-loop                  -        500
+old-loop               1000          -
+"
+    );
+}
@@ -0,0 +1,201 @@
+//! Host side of the LED remote control protocol, see the `led-protocol` crate.
+//!
+//! `Client` works over anything that can be read and written: the serial device of the board
+//! or the `/dev/rfcomm0` of a paired HC-05, set up by `serial::SerialPort`, one end of a pty pair
+//! or, as below, a fake board.
+//!
+//! ```
+//! use std::io::{self, Read, Write};
+//!
+//! use led_protocol::{LineBuffer, Request, Response};
+//! use led_remote::Client;
+//!
+//! /// Answers requests the way the firmware does, minus the LEDs and sensors.
+//! #[derive(Default)]
+//! struct FakeBoard {
+//!     requests: LineBuffer,
+//!     replies: Vec<u8>,
+//!     leds: u8,
+//! }
+//!
+//! impl Write for FakeBoard {
+//!     fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+//!         for byte in bytes {
+//!             let response = match self.requests.push(*byte) {
+//!                 Some(Ok(line)) => match Request::parse(line) {
+//!                     Ok(Request::Ping) => Response::Pong,
+//!                     Ok(Request::Leds(mask)) => {
+//!                         self.leds = mask;
+//!                         Response::Ok
+//!                     }
+//!                     Ok(Request::Accel) => Response::Accel([0, 0, 1000]),
+//!                     Ok(_) => Response::Ok,
+//!                     Err(e) => Response::Error(e),
+//!                 },
+//!                 Some(Err(e)) => Response::Error(e),
+//!                 None => continue,
+//!             };
+//!             write!(self.replies, "{}\r\n", response)?;
+//!         }
+//!         Ok(bytes.len())
+//!     }
+//!
+//!     fn flush(&mut self) -> io::Result<()> {
+//!         Ok(())
+//!     }
+//! }
+//!
+//! impl Read for FakeBoard {
+//!     fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+//!         let n = self.replies.len().min(buffer.len());
+//!         buffer[..n].copy_from_slice(&self.replies[..n]);
+//!         self.replies.drain(..n);
+//!         Ok(n)
+//!     }
+//! }
+//!
+//! let mut client = Client::new(FakeBoard::default());
+//! client.ping().unwrap();
+//! client.set_leds(0b1010_0101).unwrap();
+//! assert_eq!(client.accel().unwrap(), [0, 0, 1000]);
+//! assert_eq!(client.into_inner().leds, 0b1010_0101);
+//! ```
+
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use led_protocol::{LineBuffer, Request, Response};
+
+pub mod serial;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// The board didn't answer in time.
+    Timeout,
+    /// The connection was closed before the board answered.
+    Closed,
+    /// The board rejected the request.
+    Board(led_protocol::Error),
+    /// The board's answer doesn't fit the request.
+    UnexpectedResponse(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Timeout => f.write_str("the board didn't answer"),
+            Error::Closed => f.write_str("the connection was closed"),
+            Error::Board(e) => write!(f, "the board answered `err {}`", e),
+            Error::UnexpectedResponse(line) => write!(f, "unexpected answer `{}`", line),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+/// A connection to a board running the `remote-leds` firmware.
+pub struct Client<T> {
+    port: T,
+    lines: LineBuffer,
+}
+
+impl<T> Client<T>
+where
+    T: Read + Write,
+{
+    pub fn new(port: T) -> Self {
+        Client {
+            port,
+            lines: LineBuffer::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.port
+    }
+
+    pub fn ping(&mut self) -> Result<(), Error> {
+        self.expect(Request::Ping, Response::Pong)
+    }
+
+    /// Turns LED `index`, 0 being North and counting clockwise, on or off.
+    pub fn set_led(&mut self, index: u8, on: bool) -> Result<(), Error> {
+        self.expect(Request::Led { index, on }, Response::Ok)
+    }
+
+    /// Sets all the LEDs at once, bit `i` of `mask` being LED `i`.
+    pub fn set_leds(&mut self, mask: u8) -> Result<(), Error> {
+        self.expect(Request::Leds(mask), Response::Ok)
+    }
+
+    /// Starts the roulette, `laps` 0 meaning until `stop`. Returns once the board has started
+    /// it, not once it is over.
+    pub fn roulette(&mut self, period_ms: u16, laps: u8) -> Result<(), Error> {
+        self.expect(Request::Roulette { period_ms, laps }, Response::Ok)
+    }
+
+    pub fn stop(&mut self) -> Result<(), Error> {
+        self.expect(Request::Stop, Response::Ok)
+    }
+
+    pub fn accel(&mut self) -> Result<[i16; 3], Error> {
+        match self.request(Request::Accel)? {
+            Response::Accel(accel) => Ok(accel),
+            other => Err(Error::UnexpectedResponse(other.to_string())),
+        }
+    }
+
+    pub fn mag(&mut self) -> Result<[i16; 3], Error> {
+        match self.request(Request::Mag)? {
+            Response::Mag(mag) => Ok(mag),
+            other => Err(Error::UnexpectedResponse(other.to_string())),
+        }
+    }
+
+    fn expect(&mut self, request: Request, expected: Response) -> Result<(), Error> {
+        match self.request(request)? {
+            response if response == expected => Ok(()),
+            other => Err(Error::UnexpectedResponse(other.to_string())),
+        }
+    }
+
+    /// Sends `request` and waits for the answer, turning `err` answers into errors.
+    pub fn request(&mut self, request: Request) -> Result<Response, Error> {
+        write!(self.port, "{}\r\n", request)?;
+        self.port.flush()?;
+
+        let mut byte = [0];
+        loop {
+            match self.port.read(&mut byte) {
+                Ok(0) => return Err(Error::Closed),
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                // What ports with a read timeout, like `SerialPort`, fail with
+                Err(e)
+                    if e.kind() == io::ErrorKind::TimedOut
+                        || e.kind() == io::ErrorKind::WouldBlock =>
+                {
+                    return Err(Error::Timeout)
+                }
+                Err(e) => return Err(Error::Io(e)),
+            }
+            let line = match self.lines.push(byte[0]) {
+                Some(Ok(line)) => line,
+                Some(Err(_)) | None => continue,
+            };
+            return match Response::parse(line) {
+                Ok(Response::Error(e)) => Err(Error::Board(e)),
+                Ok(response) => Ok(response),
+                Err(_) => Err(Error::UnexpectedResponse(line.to_owned())),
+            };
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! Controls the F3's compass LEDs over a serial port running the `remote-leds` example.
+//!
+//! ``` console
+//! $ led-remote /dev/rfcomm0 led 0 on
+//! $ led-remote /dev/rfcomm0 roulette 50 3
+//! $ led-remote /dev/rfcomm0 accel
+//! -12 3 1010
+//! ```
+//!
+//! The port is switched to raw mode at 115200 bps, and a board that doesn't answer within a
+//! second is reported as such.
+
+use std::env;
+use std::process;
+
+use led_protocol::{Request, Response};
+use led_remote::{serial::SerialPort, Client};
+
+const USAGE: &str = "usage: led-remote SERIAL_DEVICE REQUEST
+
+requests:
+    ping
+    led <0-7> <on|off>
+    leds <mask>
+    roulette <period_ms> <laps>
+    stop
+    accel
+    mag";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args[0] == "-h" || args[0] == "--help" {
+        eprintln!("{}", USAGE);
+        process::exit(if args.is_empty() { 2 } else { 0 });
+    }
+
+    // The same syntax the board takes, so there is only one parser
+    let request = match Request::parse(&args[1..].join(" ")) {
+        Ok(request) => request,
+        Err(e) => {
+            eprintln!("error: invalid request ({})\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let port = match SerialPort::open(&args[0]) {
+        Ok(port) => port,
+        Err(e) => {
+            eprintln!("error: could not open {}: {}", args[0], e);
+            process::exit(1);
+        }
+    };
+
+    let mut client = Client::new(port);
+    match client.request(request) {
+        Ok(Response::Accel([x, y, z])) | Ok(Response::Mag([x, y, z])) => {
+            println!("{} {} {}", x, y, z)
+        }
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! A serial device set up for talking to the board.
+//!
+//! Serial devices start out in the terminal's line editing mode, which holds input back until
+//! a line is complete and turns carriage returns into line feeds, at whatever baud rate the
+//! last program left them. `SerialPort` switches to raw mode at the baud rate the firmware
+//! uses and gives reads a timeout. The timeout is kept by waiting in `poll` rather than by the
+//! terminal's `VTIME`, which ends a read the same way the other end hanging up does: by
+//! returning 0 bytes.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::time::Duration;
+
+use nix::poll::{poll, PollFd, PollFlags};
+use nix::sys::termios::{self, BaudRate, SetArg, SpecialCharacterIndices};
+
+/// What the `remote-leds` firmware runs USART1 at.
+pub const BAUD_RATE: BaudRate = BaudRate::B115200;
+
+/// How long `read` waits for the first byte.
+pub const TIMEOUT: Duration = Duration::from_secs(1);
+
+pub struct SerialPort {
+    file: File,
+    timeout: Duration,
+}
+
+impl SerialPort {
+    /// Opens the device at `path` with `BAUD_RATE` and `TIMEOUT`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<SerialPort> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        SerialPort::new(file, BAUD_RATE, TIMEOUT)
+    }
+
+    /// Sets an already open serial device, or the slave end of a pty, up.
+    pub fn new(file: File, baud_rate: BaudRate, timeout: Duration) -> io::Result<SerialPort> {
+        let fd = file.as_raw_fd();
+        let mut settings = termios::tcgetattr(fd)?;
+        termios::cfmakeraw(&mut settings);
+        termios::cfsetspeed(&mut settings, baud_rate)?;
+        // Reads return as soon as there is a byte, `poll` takes care of the timeout
+        settings.control_chars[SpecialCharacterIndices::VMIN as usize] = 1;
+        settings.control_chars[SpecialCharacterIndices::VTIME as usize] = 0;
+        termios::tcsetattr(fd, SetArg::TCSANOW, &settings)?;
+        // Whatever arrived before doesn't answer any of our requests
+        termios::tcflush(fd, termios::FlushArg::TCIFLUSH)?;
+
+        Ok(SerialPort { file, timeout })
+    }
+}
+
+impl Read for SerialPort {
+    /// Fails with `ErrorKind::TimedOut` if nothing arrives in time, and returns 0 bytes once the
+    /// other end has hung up.
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let mut fds = [PollFd::new(self.file.as_raw_fd(), PollFlags::POLLIN)];
+        let timeout_ms = self.timeout.as_millis().min(i32::MAX as u128) as i32;
+        if poll(&mut fds, timeout_ms)? == 0 {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.file.read(buffer)
+    }
+}
+
+impl Write for SerialPort {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.file.write(bytes)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
@@ -0,0 +1,110 @@
+//! The client over a pty pair: `SerialPort` on the slave end, like on a real serial device, and a
+//! fake board on the master end.
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::unix::io::FromRawFd;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use led_protocol::{LineBuffer, Request, Response};
+use led_remote::{serial::SerialPort, Client, Error};
+use nix::pty::openpty;
+
+const TIMEOUT: Duration = Duration::from_millis(200);
+
+/// What the fake board does with the requests it gets.
+#[derive(Clone, Copy)]
+enum Board {
+    /// Answers like the firmware.
+    Answers,
+    /// Reads the requests and says nothing.
+    Silent,
+    /// Hangs up after the first request, halfway through the answer.
+    HangsUp,
+}
+
+/// Starts the board on the master end and returns the client on the slave end. The board
+/// thread returns the LED mask it was left with.
+fn connect(board: Board) -> (Client<SerialPort>, JoinHandle<u8>) {
+    let pty = openpty(None, None).unwrap();
+    // SAFETY: the file descriptors are fresh and each is owned by one `File` only
+    let (mut master, slave) =
+        unsafe { (File::from_raw_fd(pty.master), File::from_raw_fd(pty.slave)) };
+    let port = SerialPort::new(slave, led_remote::serial::BAUD_RATE, TIMEOUT).unwrap();
+
+    let handle = thread::spawn(move || {
+        let mut requests = LineBuffer::new();
+        let mut leds = 0;
+        let mut byte = [0];
+        // Ends with an error once the client end is closed
+        while let Ok(1) = master.read(&mut byte) {
+            let line = match requests.push(byte[0]) {
+                Some(Ok(line)) => line,
+                _ => continue,
+            };
+            let response = match Request::parse(line) {
+                Ok(Request::Ping) => Response::Pong,
+                Ok(Request::Leds(mask)) => {
+                    leds = mask;
+                    Response::Ok
+                }
+                Ok(Request::Accel) => Response::Accel([-12, 3, 1010]),
+                Ok(_) => Response::Ok,
+                Err(e) => Response::Error(e),
+            };
+            match board {
+                Board::Answers => write!(master, "{}\r\n", response).unwrap(),
+                Board::Silent => {}
+                Board::HangsUp => {
+                    master.write_all(b"po").unwrap();
+                    break;
+                }
+            }
+        }
+        leds
+    });
+    (Client::new(port), handle)
+}
+
+#[test]
+fn requests_and_answers() {
+    let (mut client, board) = connect(Board::Answers);
+    client.ping().unwrap();
+    client.set_leds(0b1000_0001).unwrap();
+    assert_eq!(client.accel().unwrap(), [-12, 3, 1010]);
+    match client.request(Request::Leds(0b0100_0010)) {
+        Ok(Response::Ok) => {}
+        other => panic!("unexpected {:?}", other),
+    }
+    drop(client);
+    assert_eq!(board.join().unwrap(), 0b0100_0010);
+}
+
+#[test]
+fn silent_board_times_out() {
+    let (mut client, board) = connect(Board::Silent);
+    let start = Instant::now();
+    match client.ping() {
+        Err(Error::Timeout) => {}
+        other => panic!("unexpected {:?}", other),
+    }
+    let elapsed = start.elapsed();
+    assert!(elapsed >= TIMEOUT, "{:?}", elapsed);
+    assert!(elapsed < 10 * TIMEOUT, "{:?}", elapsed);
+    drop(client);
+    board.join().unwrap();
+}
+
+#[test]
+fn hang_up_is_not_a_timeout() {
+    let (mut client, board) = connect(Board::HangsUp);
+    let start = Instant::now();
+    match client.ping() {
+        Err(Error::Closed) => {}
+        other => panic!("unexpected {:?}", other),
+    }
+    // Noticed right away instead of after the timeout
+    assert!(start.elapsed() < TIMEOUT, "{:?}", start.elapsed());
+    board.join().unwrap();
+}
@@ -0,0 +1,85 @@
+//! Decodes the sample stream of the led-compass `data-logger` example into CSV.
+//!
+//! ``` console
+//! $ stty -F /dev/ttyACM0 115200 raw -echo
+//! $ sensor-log /dev/ttyACM0 > samples.csv
+//! ```
+//!
+//! Without an argument the stream is read from stdin. Frames that fail to decode are
+//! counted and reported on stderr, the remaining samples are still written out.
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::process;
+
+use sensor_frame::FrameDecoder;
+
+fn main() {
+    let input: Box<dyn Read> = match env::args().nth(1) {
+        Some(path) if path == "-h" || path == "--help" => {
+            eprintln!("usage: sensor-log [SERIAL_DEVICE]");
+            process::exit(0);
+        }
+        Some(path) => match File::open(&path) {
+            Ok(file) => Box::new(file),
+            Err(e) => {
+                eprintln!("error: could not open {}: {}", path, e);
+                process::exit(1);
+            }
+        },
+        None => Box::new(io::stdin()),
+    };
+
+    if let Err(e) = run(input) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(mut input: Box<dyn Read>) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    writeln!(
+        out,
+        "timestamp_us,accel_x_mg,accel_y_mg,accel_z_mg,mag_x_nt,mag_y_nt,mag_z_nt"
+    )?;
+
+    let mut decoder = FrameDecoder::new();
+    let mut buffer = [0; 256];
+    let mut errors = 0;
+    loop {
+        let n = match input.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        for byte in &buffer[..n] {
+            match decoder.push(*byte) {
+                Some(Ok(sample)) => {
+                    writeln!(
+                        out,
+                        "{},{},{},{},{},{},{}",
+                        sample.timestamp_us,
+                        sample.accel[0],
+                        sample.accel[1],
+                        sample.accel[2],
+                        sample.mag[0],
+                        sample.mag[1],
+                        sample.mag[2]
+                    )?;
+                }
+                Some(Err(e)) => {
+                    errors += 1;
+                    eprintln!("dropped frame: {:?} ({} so far)", e, errors);
+                }
+                None => {}
+            }
+        }
+        out.flush()?;
+    }
+
+    Ok(())
+}